@@ -1,10 +1,84 @@
 use std::path::PathBuf;
 
+use crate::{run::DEFAULT_ADDRESS, setup::LogFormat};
+
 #[derive(clap::Parser, Debug, Clone)]
 #[command(author, version, about, long_about = None)]
 pub struct Arguments {
-    #[arg(short, long, value_name = "FILE")]
-    pub graph: PathBuf,
-    #[arg(short, long, default_value_t = String::from("localhost:3000"))]
+    /// Graph of the system, as a CSV edge list if the file ends with `.csv`, in DIMACS otherwise.
+    ///
+    /// When given several times, the graphs are merged into one.
+    #[arg(
+        short,
+        long,
+        value_name = "FILE",
+        required_unless_present = "complete",
+        conflicts_with = "complete"
+    )]
+    pub graph: Vec<PathBuf>,
+    /// Runs on the complete graph of N vertices, every node connected to every other one,
+    /// rather than reading a graph file.
+    #[arg(long, value_name = "N")]
+    pub complete: Option<usize>,
+    /// Address the nodes register on, as `host:port`.
+    #[arg(short, long, default_value_t = DEFAULT_ADDRESS.to_string())]
     pub address: String,
+    /// Size in bytes of the receive buffer of the coordinator's socket (`SO_RCVBUF`).
+    ///
+    /// Nodes registering at the same time may overflow the default buffer, their hello being
+    /// silently dropped. A larger buffer avoids this at the cost of kernel memory.
+    #[arg(long, value_name = "BYTES")]
+    pub receive_buffer: Option<usize>,
+    /// Only waits for this many nodes, running on the subgraph induced by their ids. At least
+    /// 1 and at most the number of vertices of the graph.
+    #[arg(long, value_name = "K")]
+    pub expected_nodes: Option<usize>,
+    /// Gives up waiting for nodes after this many seconds, exiting with a failure.
+    #[arg(long, value_name = "SECONDS")]
+    pub registration_timeout: Option<u64>,
+    /// Rejects graph files holding a line longer than this many bytes.
+    #[arg(long, value_name = "BYTES")]
+    pub max_line_bytes: Option<usize>,
+    /// Rejects graph files holding more than this many lines.
+    #[arg(long, value_name = "LINES")]
+    pub max_lines: Option<usize>,
+    /// Rejects graph files naming a vertex whose id is above this one.
+    #[arg(long, value_name = "ID")]
+    pub max_vertex_id: Option<usize>,
+    /// Writes the connections planned for each vertex into this file, as JSON, before waiting
+    /// for any node.
+    #[arg(long, value_name = "FILE")]
+    pub plan: Option<PathBuf>,
+    /// Rejects graphs holding the same edge more than once, rather than ignoring duplicates.
+    #[arg(long)]
+    pub strict: bool,
+    /// Accepts DIMACS files listing edges before their problem line, or without any, the
+    /// vertices being inferred from the edges.
+    #[arg(long)]
+    pub lenient: bool,
+    /// Gives the vertices with the highest degree to the nodes that advertised the highest
+    /// capacity, rather than following the registration order.
+    #[arg(long)]
+    pub balance: bool,
+    /// Gives each node the number of its vertex in the graph file, even when some numbers
+    /// are skipped, rather than numbering the nodes from 1.
+    #[arg(long)]
+    pub keep_vertex_ids: bool,
+    /// Once the system is set up, checks mutual exclusion from what the nodes started with
+    /// `--witness` tell about their critical sections, failing on any violation. Stops once
+    /// none of them told anything for this many seconds.
+    #[arg(long, value_name = "SECONDS")]
+    pub witness: Option<u64>,
+    /// Once the system is set up, keeps on admitting nodes saying hello, each connected to
+    /// every node already in the system. Only nodes running Lamport's or Ricart-Agrawala's
+    /// algorithm connect to it, the others refusing it, whose setup then fails. Stops once
+    /// none of them said hello for this many seconds.
+    #[arg(long, value_name = "SECONDS", conflicts_with = "witness")]
+    pub resident: Option<u64>,
+    /// Prints the number of vertices of each degree once the graph is loaded.
+    #[arg(short, long)]
+    pub verbose: bool,
+    /// How the progress of each node is reported on the standard output.
+    #[arg(long, value_enum, default_value_t = LogFormat::Human)]
+    pub log_format: LogFormat,
 }
@@ -0,0 +1,81 @@
+use std::collections::{HashMap, HashSet};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use renraku_shared::{Connection, NodeId};
+
+use crate::Graph;
+
+// Standard topologies, numbering their vertices from `1` to `n`
+impl Graph {
+    /// Builds the graph whose vertices are joined by the given pairs, the lower vertex of each
+    /// pair connecting to the other one.
+    fn with_edges(n: usize, edges: impl IntoIterator<Item = (usize, usize)>) -> Graph {
+        Graph {
+            vertices: (1..=n).map(NodeId).collect(),
+            edges: edges
+                .into_iter()
+                .map(|(a, b)| Connection::new(NodeId(a), NodeId(b)))
+                .collect::<HashSet<_>>(),
+            roles: HashMap::new(),
+            labels: HashMap::new(),
+        }
+    }
+
+    /// Every vertex joined to every other one.
+    pub fn complete(n: usize) -> Graph {
+        Self::with_edges(n, (1..=n).flat_map(|a| (a + 1..=n).map(move |b| (a, b))))
+    }
+
+    /// Each vertex joined to the next one, the last one closing the ring with the first.
+    ///
+    /// Two vertices are joined once, no ring being possible below three of them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use renraku_coordinator::Graph;
+    ///
+    /// let ring = Graph::ring(5);
+    /// assert_eq!(ring.vertices.len(), 5);
+    /// assert!(ring.stats().min_degree == 2 && ring.stats().max_degree == 2);
+    /// ```
+    pub fn ring(n: usize) -> Graph {
+        let closing = (n > 2).then_some((1, n));
+        Self::with_edges(n, (1..n).map(|a| (a, a + 1)).chain(closing))
+    }
+
+    /// Every vertex joined to the vertex `1`, at the center of the star.
+    pub fn star(n: usize) -> Graph {
+        Self::with_edges(n, (2..=n).map(|b| (1, b)))
+    }
+
+    /// A grid of `width` columns and `height` rows, laid out row by row, each vertex being
+    /// joined to the ones on its right and below it.
+    pub fn grid(width: usize, height: usize) -> Graph {
+        let vertex = |x: usize, y: usize| y * width + x + 1;
+        let right = (0..height).flat_map(move |y| (1..width).map(move |x| (x - 1, y)));
+        let below = (1..height).flat_map(move |y| (0..width).map(move |x| (x, y - 1)));
+        Self::with_edges(
+            width * height,
+            right
+                .map(|(x, y)| (vertex(x, y), vertex(x + 1, y)))
+                .chain(below.map(|(x, y)| (vertex(x, y), vertex(x, y + 1))))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    /// Joins each pair of vertices with probability `p`, following the Erdős–Rényi model.
+    ///
+    /// The same seed always gives the same graph. A probability outside of `[0, 1]` is brought
+    /// back within it, while a NaN one joins no pair.
+    pub fn random_erdos_renyi(n: usize, p: f64, seed: u64) -> Graph {
+        let mut rng = StdRng::seed_from_u64(seed);
+        // Clamping leaves NaN as it is, which sampling would panic on
+        let p = if p.is_nan() { 0.0 } else { p.clamp(0.0, 1.0) };
+        let edges: Vec<_> = (1..=n)
+            .flat_map(|a| (a + 1..=n).map(move |b| (a, b)))
+            .filter(|_| rng.gen_bool(p))
+            .collect();
+        Self::with_edges(n, edges)
+    }
+}
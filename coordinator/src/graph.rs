@@ -1,7 +1,15 @@
-use std::{collections::HashSet, fs::File, io::Read, str::FromStr};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Write,
+    fs::File,
+    io::{self, BufRead, BufReader, Read},
+    path::Path,
+    str::FromStr,
+};
 
 use color_eyre::{Report, Result};
-use renraku_shared::{Connection, NodeId};
+use flate2::read::MultiGzDecoder;
+use renraku_shared::{Connection, NodeId, Placement};
 use thiserror::Error;
 
 /// Represents a graph structure within the `renraku_coordinator` crate.
@@ -13,23 +21,677 @@ use thiserror::Error;
 /// ```
 /// # use renraku_coordinator::Graph;
 /// # use renraku_shared::{NodeId, Connection};
-/// use std::collections::HashSet;
+/// use std::collections::{HashMap, HashSet};
 ///
 /// let vertices: HashSet<NodeId> = HashSet::new();
 /// let edges: HashSet<Connection> = HashSet::new();
-/// let graph = Graph { vertices, edges };
+/// let graph = Graph { vertices, edges, roles: HashMap::new(), labels: HashMap::new() };
 /// ```
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct Graph {
     pub vertices: HashSet<NodeId>,
     pub edges: HashSet<Connection>,
+    /// Role of the tagged vertices, relayed to their node during the setup.
+    pub roles: HashMap<NodeId, String>,
+    /// Label of the labelled edges, relayed to the nodes at both of their ends.
+    pub labels: HashMap<Connection, String>,
+}
+
+/// Statistics about the topology of a [`Graph`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphStats {
+    pub vertices: usize,
+    pub edges: usize,
+    pub min_degree: usize,
+    pub max_degree: usize,
+    pub average_degree: f64,
+    pub components: usize,
+    pub is_tree: bool,
+}
+
+impl Graph {
+    /// Returns the vertices of the graph in ascending order, so that runs are reproducible.
+    pub fn sorted_vertices(&self) -> Vec<NodeId> {
+        let mut vertices: Vec<NodeId> = self.vertices.iter().cloned().collect();
+        vertices.sort();
+        vertices
+    }
+
+    /// Returns the edges of the graph in ascending order, so that runs are reproducible.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use renraku_coordinator::Graph;
+    /// # use renraku_shared::{Connection, NodeId};
+    ///
+    /// let graph: Graph = "p edge 4 4\ne 3 4\ne 2 1\ne 1 4\ne 2 3\n".parse().unwrap();
+    /// let edges = |pairs: &[(usize, usize)]| {
+    ///     pairs.iter().map(|(a, b)| Connection(NodeId(*a), NodeId(*b))).collect::<Vec<_>>()
+    /// };
+    ///
+    /// assert_eq!(graph.sorted_edges(), edges(&[(1, 2), (1, 4), (2, 3), (3, 4)]));
+    /// assert_eq!(graph.sorted_vertices(), (1..=4).map(NodeId).collect::<Vec<_>>());
+    ///
+    /// // The order of the file does not matter
+    /// let shuffled: Graph = "p edge 4 4\ne 2 3\ne 4 1\ne 1 2\ne 4 3\n".parse().unwrap();
+    /// assert_eq!(shuffled.sorted_edges(), graph.sorted_edges());
+    /// ```
+    pub fn sorted_edges(&self) -> Vec<Connection> {
+        let mut edges: Vec<Connection> = self.edges.iter().cloned().collect();
+        edges.sort();
+        edges
+    }
+
+    /// Returns the subgraph induced by the vertices to keep: the other vertices are dropped,
+    /// along with every edge incident to them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use renraku_coordinator::Graph;
+    /// # use renraku_shared::{Connection, NodeId};
+    /// use std::collections::HashSet;
+    ///
+    /// // A cycle of 5 vertices, plus a chord between 1 and 3
+    /// let graph: Graph = "p edge 5 6\ne 1 2\ne 2 3\ne 3 4\ne 4 5\ne 5 1\ne 1 3\nn 2 hub\n"
+    ///     .parse()
+    ///     .unwrap();
+    /// let keep: HashSet<NodeId> = [1, 2, 3].into_iter().map(NodeId).collect();
+    /// let subgraph = graph.subgraph(&keep);
+    ///
+    /// assert_eq!(subgraph.vertices, keep);
+    /// assert_eq!(
+    ///     subgraph.sorted_edges(),
+    ///     vec![
+    ///         Connection(NodeId(1), NodeId(2)),
+    ///         Connection(NodeId(1), NodeId(3)),
+    ///         Connection(NodeId(2), NodeId(3)),
+    ///     ]
+    /// );
+    /// assert_eq!(subgraph.roles[&NodeId(2)], "hub");
+    ///
+    /// let keep: HashSet<NodeId> = [2, 4].into_iter().map(NodeId).collect();
+    /// assert!(graph.subgraph(&keep).edges.is_empty());
+    /// ```
+    pub fn subgraph(&self, keep: &HashSet<NodeId>) -> Graph {
+        Graph {
+            vertices: self.vertices.intersection(keep).cloned().collect(),
+            edges: self
+                .edges
+                .iter()
+                .filter(|Connection(a, b)| keep.contains(a) && keep.contains(b))
+                .cloned()
+                .collect(),
+            roles: self
+                .roles
+                .iter()
+                .filter(|(v, _)| keep.contains(v))
+                .map(|(v, role)| (v.clone(), role.clone()))
+                .collect(),
+            labels: self
+                .labels
+                .iter()
+                .filter(|(Connection(a, b), _)| keep.contains(a) && keep.contains(b))
+                .map(|(edge, label)| (edge.clone(), label.clone()))
+                .collect(),
+        }
+    }
+
+    /// Adds the vertices, edges, roles and labels of the other graph to this one.
+    ///
+    /// Edges present in both graphs are kept once. Nothing is merged when the graphs
+    /// connect two vertices in opposite directions, tag a vertex with different roles or
+    /// label an edge differently.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use renraku_coordinator::Graph;
+    ///
+    /// let mut graph: Graph = "p edge 3 2\ne 1 2\ne 2 3\n".parse().unwrap();
+    /// graph.merge("p edge 4 2\ne 3 4\ne 2 3\n".parse().unwrap()).unwrap();
+    /// assert_eq!(graph.vertices.len(), 4);
+    /// assert_eq!(graph.edges.len(), 3);
+    ///
+    /// let tagged: Graph = "p edge 2 1\nn 1 producer\ne 1 2\n".parse().unwrap();
+    /// graph.merge(tagged.clone()).unwrap();
+    /// let retagged: Graph = "p edge 2 1\nn 1 consumer\ne 1 2\n".parse().unwrap();
+    /// assert!(graph.merge(retagged).is_err());
+    /// ```
+    pub fn merge(&mut self, other: Graph) -> Result<(), MergeError> {
+        if let Some(Connection(a, b)) = other
+            .edges
+            .iter()
+            .find(|Connection(a, b)| self.edges.contains(&Connection(b.clone(), a.clone())))
+        {
+            return Err(MergeError::OppositeEdges(a.clone(), b.clone()));
+        }
+        if let Some((vertex, role)) = other
+            .roles
+            .iter()
+            .find(|(v, role)| self.roles.get(v).is_some_and(|r| r != *role))
+        {
+            return Err(MergeError::ConflictingRoles {
+                vertex: vertex.clone(),
+                first: self.roles[vertex].clone(),
+                second: role.clone(),
+            });
+        }
+        if let Some((edge, label)) = other
+            .labels
+            .iter()
+            .find(|(e, label)| self.labels.get(e).is_some_and(|l| l != *label))
+        {
+            return Err(MergeError::ConflictingLabels {
+                edge: edge.clone(),
+                first: self.labels[edge].clone(),
+                second: label.clone(),
+            });
+        }
+        self.vertices.extend(other.vertices);
+        self.edges.extend(other.edges);
+        self.roles.extend(other.roles);
+        self.labels.extend(other.labels);
+        Ok(())
+    }
+
+    /// Returns the label of each labelled edge of the vertex, along with the vertex at its
+    /// other end, sorted by that vertex.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use renraku_coordinator::Graph;
+    /// # use renraku_shared::NodeId;
+    ///
+    /// let graph: Graph = "p edge 3 3\ne 1 2 label=fast\ne 3 2 label=slow\ne 1 3\n".parse().unwrap();
+    /// assert_eq!(
+    ///     graph.neighbour_labels(&NodeId(2)),
+    ///     vec![(NodeId(1), "fast".to_string()), (NodeId(3), "slow".to_string())]
+    /// );
+    /// assert!(graph.neighbour_labels(&NodeId(4)).is_empty());
+    /// ```
+    pub fn neighbour_labels(&self, vertex: &NodeId) -> Vec<(NodeId, String)> {
+        let mut labels: Vec<(NodeId, String)> = self
+            .labels
+            .iter()
+            .filter_map(|(Connection(a, b), label)| match vertex {
+                v if v == a => Some((b.clone(), label.clone())),
+                v if v == b => Some((a.clone(), label.clone())),
+                _ => None,
+            })
+            .collect();
+        labels.sort();
+        labels
+    }
+
+    /// Returns the set of vertices connected to each vertex, edges being undirected.
+    pub fn adjacency(&self) -> HashMap<NodeId, HashSet<NodeId>> {
+        let mut adjacency: HashMap<NodeId, HashSet<NodeId>> = self
+            .vertices
+            .iter()
+            .map(|v| (v.clone(), HashSet::new()))
+            .collect();
+        for Connection(a, b) in self.edges.iter() {
+            adjacency.entry(a.clone()).or_default().insert(b.clone());
+            adjacency.entry(b.clone()).or_default().insert(a.clone());
+        }
+        adjacency
+    }
+
+    /// Returns the number of edges incident to the given vertex.
+    pub fn degree(&self, vertex: &NodeId) -> usize {
+        self.edges
+            .iter()
+            .filter(|e| &e.0 == vertex || &e.1 == vertex)
+            .count()
+    }
+
+    /// Returns where the vertex stands in the directed topology, each edge going from the
+    /// vertex dialing to the vertex accepting the connection.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use renraku_coordinator::graph::GraphBuilder;
+    /// # use renraku_shared::{NodeId, Placement};
+    ///
+    /// let mut builder = GraphBuilder::new();
+    /// for v in 1..=4 {
+    ///     builder.add_vertex(NodeId(v));
+    /// }
+    /// let graph = builder
+    ///     .add_directed_edge(NodeId(2), NodeId(1))
+    ///     .add_directed_edge(NodeId(2), NodeId(3))
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(graph.placement(&NodeId(1)), Placement::Sink);
+    /// assert_eq!(graph.placement(&NodeId(2)), Placement::Source);
+    /// assert_eq!(graph.placement(&NodeId(4)), Placement::Isolated);
+    /// ```
+    pub fn placement(&self, vertex: &NodeId) -> Placement {
+        let incoming = self.edges.iter().filter(|e| &e.1 == vertex).count();
+        let outgoing = self.edges.iter().filter(|e| &e.0 == vertex).count();
+        Placement::new(incoming, outgoing)
+    }
+
+    /// Returns the number of connected components of the graph.
+    pub fn connected_components(&self) -> usize {
+        let adjacency = self.adjacency();
+        let mut visited = HashSet::with_capacity(adjacency.len());
+        let mut components = 0;
+
+        for start in adjacency.keys() {
+            if !visited.insert(start) {
+                continue;
+            }
+            components += 1;
+            let mut stack = vec![start];
+            while let Some(vertex) = stack.pop() {
+                for neighbour in adjacency[vertex].iter() {
+                    if visited.insert(neighbour) {
+                        stack.push(neighbour);
+                    }
+                }
+            }
+        }
+        components
+    }
+
+    /// Computes statistics about the topology of the graph, degrees being given by
+    /// [`Graph::degree`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use renraku_coordinator::Graph;
+    ///
+    /// // A star centered on 1, plus an isolated edge
+    /// let graph: Graph = "p edge 6 4\ne 1 2\ne 1 3\ne 1 4\ne 5 6\n".parse().unwrap();
+    /// let stats = graph.stats();
+    ///
+    /// assert_eq!(stats.vertices, 6);
+    /// assert_eq!(stats.edges, 4);
+    /// assert_eq!(stats.min_degree, 1);
+    /// assert_eq!(stats.max_degree, 3);
+    /// assert!((stats.average_degree - 8.0 / 6.0).abs() < f64::EPSILON);
+    /// assert_eq!(stats.components, 2);
+    /// assert!(!stats.is_tree);
+    /// ```
+    pub fn stats(&self) -> GraphStats {
+        let degrees: Vec<usize> = self.degrees().into_values().collect();
+        GraphStats {
+            vertices: self.vertices.len(),
+            edges: self.edges.len(),
+            min_degree: degrees.iter().copied().min().unwrap_or(0),
+            max_degree: degrees.iter().copied().max().unwrap_or(0),
+            average_degree: if degrees.is_empty() {
+                0.0
+            } else {
+                degrees.iter().sum::<usize>() as f64 / degrees.len() as f64
+            },
+            components: self.connected_components(),
+            is_tree: self.is_tree(),
+        }
+    }
+
+    /// Counts the vertices of each degree, as given by [`Graph::degree`], isolated vertices
+    /// having a degree of `0`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use renraku_coordinator::Graph;
+    /// # use renraku_shared::{Connection, NodeId};
+    /// use std::collections::BTreeMap;
+    ///
+    /// let mut graph: Graph = "p edge 4 3\ne 1 2\ne 2 3\ne 3 4\n".parse().unwrap();
+    /// assert_eq!(graph.degree_histogram(), BTreeMap::from([(1, 2), (2, 2)]));
+    ///
+    /// // Each edge counts, even when another one joins the same vertices
+    /// graph.edges.insert(Connection(NodeId(2), NodeId(1)));
+    /// assert_eq!(graph.degree(&NodeId(1)), 2);
+    /// assert_eq!(graph.degree_histogram(), BTreeMap::from([(1, 1), (2, 2), (3, 1)]));
+    /// ```
+    pub fn degree_histogram(&self) -> BTreeMap<usize, usize> {
+        let mut histogram = BTreeMap::new();
+        for degree in self.degrees().into_values() {
+            *histogram.entry(degree).or_default() += 1;
+        }
+        histogram
+    }
+
+    /// Returns the degree of every vertex, as given by [`Graph::degree`].
+    fn degrees(&self) -> HashMap<&NodeId, usize> {
+        // Degrees are counted in a single pass over the edges rather than one per vertex
+        let mut degrees: HashMap<&NodeId, usize> = self.vertices.iter().map(|v| (v, 0)).collect();
+        for Connection(a, b) in self.edges.iter() {
+            if let Some(degree) = degrees.get_mut(a) {
+                *degree += 1;
+            }
+            if let Some(degree) = degrees.get_mut(b).filter(|_| a != b) {
+                *degree += 1;
+            }
+        }
+        degrees
+    }
+
+    /// Returns whether the graph contains a cycle, found using a union-find over its edges.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use renraku_coordinator::Graph;
+    ///
+    /// let path: Graph = "p edge 3 2\ne 1 2\ne 2 3\n".parse().unwrap();
+    /// let cycle: Graph = "p edge 3 3\ne 1 2\ne 2 3\ne 3 1\n".parse().unwrap();
+    ///
+    /// assert!(!path.has_cycle());
+    /// assert!(cycle.has_cycle());
+    /// ```
+    pub fn has_cycle(&self) -> bool {
+        let index: HashMap<&NodeId, usize> = self
+            .vertices
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (v, i))
+            .collect();
+        let mut parents: Vec<usize> = (0..index.len()).collect();
+
+        fn find(parents: &mut [usize], mut i: usize) -> usize {
+            while parents[i] != i {
+                parents[i] = parents[parents[i]];
+                i = parents[i];
+            }
+            i
+        }
+
+        self.edges.iter().any(|Connection(a, b)| {
+            // An edge towards a vertex the graph does not list cannot close a cycle
+            let (Some(&a), Some(&b)) = (index.get(a), index.get(b)) else {
+                return false;
+            };
+            let (a, b) = (find(&mut parents, a), find(&mut parents, b));
+            parents[a] = b;
+            a == b
+        })
+    }
+
+    /// Returns whether the graph is a tree: connected, and without any cycle.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use renraku_coordinator::Graph;
+    ///
+    /// let path: Graph = "p edge 3 2\ne 1 2\ne 2 3\n".parse().unwrap();
+    /// let cycle: Graph = "p edge 3 3\ne 1 2\ne 2 3\ne 3 1\n".parse().unwrap();
+    /// let forest: Graph = "p edge 4 2\ne 1 2\ne 3 4\n".parse().unwrap();
+    ///
+    /// assert!(path.is_tree());
+    /// assert!(!cycle.is_tree());
+    /// assert!(!forest.is_tree());
+    /// assert!(!forest.has_cycle());
+    /// ```
+    pub fn is_tree(&self) -> bool {
+        self.check_tree().is_ok()
+    }
+
+    /// Ensures the graph is a tree, as required by tree-based algorithms.
+    pub fn check_tree(&self) -> Result<(), TopologyError> {
+        if self.has_cycle() {
+            return Err(TopologyError::Cyclic);
+        }
+        match self.connected_components() {
+            0 | 1 => Ok(()),
+            components => Err(TopologyError::Disconnected(components)),
+        }
+    }
+}
+
+/// Renders a [`Graph::degree_histogram`] with a line per degree, each holding a bar as long as
+/// its share of the vertices.
+///
+/// # Examples
+///
+/// ```
+/// # use renraku_coordinator::graph::format_histogram;
+/// use std::collections::BTreeMap;
+///
+/// let histogram = BTreeMap::from([(1, 4), (4, 1)]);
+/// assert_eq!(
+///     format_histogram(&histogram),
+///     "degree 1 | ████████████████████ 4\ndegree 4 | █████ 1\n"
+/// );
+/// ```
+pub fn format_histogram(histogram: &BTreeMap<usize, usize>) -> String {
+    /// Length of the bar of the most common degree.
+    const WIDTH: usize = 20;
+    let most = histogram.values().copied().max().unwrap_or(0);
+    let digits = histogram.keys().last().map_or(1, |d| d.to_string().len());
+    let mut rendered = String::new();
+    for (degree, count) in histogram.iter() {
+        let bar = "█".repeat((count * WIDTH).div_ceil(most));
+        let _ = writeln!(rendered, "degree {degree:>digits$} | {bar} {count}");
+    }
+    rendered
+}
+
+#[derive(Error, Debug)]
+pub enum MergeError {
+    #[error("{0:?} and {1:?} are connected both ways by the merged graphs")]
+    OppositeEdges(NodeId, NodeId),
+    #[error("{vertex:?} is tagged both as {first:?} and as {second:?}")]
+    ConflictingRoles {
+        vertex: NodeId,
+        first: String,
+        second: String,
+    },
+    #[error("{edge:?} is labelled both as {first:?} and as {second:?}")]
+    ConflictingLabels {
+        edge: Connection,
+        first: String,
+        second: String,
+    },
+}
+
+#[derive(Error, Debug)]
+pub enum TopologyError {
+    #[error("The graph contains a cycle, while a tree is expected")]
+    Cyclic,
+    #[error("The graph has {0} connected components, while a tree is expected")]
+    Disconnected(usize),
+}
+
+/// Builds a [`Graph`] programmatically, rather than formatting a DIMACS file.
+///
+/// Like the parsers, [`GraphBuilder::add_edge`] stores an undirected edge from its lowest
+/// vertex to its highest, the lowest one connecting to the other during the setup.
+/// [`GraphBuilder::add_directed_edge`] keeps the given direction instead.
+///
+/// # Examples
+///
+/// ```
+/// # use renraku_coordinator::graph::{Graph, GraphBuilder};
+/// # use renraku_shared::NodeId;
+///
+/// let mut builder = GraphBuilder::new();
+/// for v in 1..=3 {
+///     builder.add_vertex(NodeId(v));
+/// }
+/// let built = builder
+///     .add_edge(NodeId(2), NodeId(1))
+///     .add_edge(NodeId(2), NodeId(3))
+///     .build()
+///     .unwrap();
+///
+/// let parsed: Graph = "p edge 3 2\ne 1 2\ne 2 3\n".parse().unwrap();
+/// assert_eq!(built, parsed);
+///
+/// // Every vertex of an edge must have been added
+/// assert!(GraphBuilder::new()
+///     .add_vertex(NodeId(1))
+///     .add_edge(NodeId(1), NodeId(2))
+///     .build()
+///     .is_err());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct GraphBuilder {
+    vertices: HashSet<NodeId>,
+    edges: Vec<Connection>,
+}
+
+impl GraphBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_vertex(&mut self, vertex: NodeId) -> &mut Self {
+        self.vertices.insert(vertex);
+        self
+    }
+
+    /// Adds an undirected edge between two vertices.
+    pub fn add_edge(&mut self, a: NodeId, b: NodeId) -> &mut Self {
+        self.edges.push(Connection::new(a, b));
+        self
+    }
+
+    /// Adds an edge along which `from` connects to `to` during the setup.
+    pub fn add_directed_edge(&mut self, from: NodeId, to: NodeId) -> &mut Self {
+        self.edges.push(Connection(from, to));
+        self
+    }
+
+    /// Builds the graph, ensuring each edge joins two distinct vertices that have been added,
+    /// that no two vertices are joined twice and that the reserved vertex `0` is not used.
+    pub fn build(&self) -> Result<Graph, GraphBuildError> {
+        if self.vertices.contains(&NodeId(0)) {
+            return Err(GraphBuildError::ReservedVertex);
+        }
+        let mut edges = HashSet::with_capacity(self.edges.len());
+        for edge in self.edges.iter() {
+            let Connection(from, to) = edge;
+            if let Some(missing) = [from, to].into_iter().find(|v| !self.vertices.contains(v)) {
+                return Err(GraphBuildError::UnknownVertex(
+                    missing.clone(),
+                    edge.clone(),
+                ));
+            }
+            if from == to {
+                return Err(GraphBuildError::SelfLoop(from.clone()));
+            }
+            if edges.contains(&Connection(to.clone(), from.clone())) || !edges.insert(edge.clone())
+            {
+                return Err(GraphBuildError::DuplicateEdge(edge.clone()));
+            }
+        }
+        Ok(Graph {
+            vertices: self.vertices.clone(),
+            edges,
+            roles: HashMap::new(),
+            labels: HashMap::new(),
+        })
+    }
+}
+
+/// How [`Graph::from_adjacency_matrix`] reads a matrix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatrixKind {
+    /// The matrix must equal its transpose, each pair of entries making an undirected edge.
+    Symmetric,
+    /// Each entry makes an edge along which its row connects to its column.
+    Directed,
+}
+
+#[derive(Error, Debug)]
+pub enum MatrixError {
+    #[error("Row {row} has {length} entries, while the matrix has {expected} rows")]
+    NotSquare {
+        row: usize,
+        length: usize,
+        expected: usize,
+    },
+    #[error("Entries ({0}, {1}) and ({1}, {0}) differ, while the matrix is symmetric")]
+    Asymmetric(usize, usize),
+    #[error(transparent)]
+    Build(#[from] GraphBuildError),
+}
+
+impl Graph {
+    /// Builds the graph described by an adjacency matrix, the vertex `i` being described by
+    /// the row `i - 1`: any non-zero entry makes an edge.
+    ///
+    /// The diagonal must be zero. A directed matrix may not connect two vertices both ways,
+    /// as a single connection joins two nodes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use renraku_coordinator::graph::{Graph, MatrixKind};
+    ///
+    /// let triangle = vec![vec![0, 1, 1], vec![1, 0, 1], vec![1, 1, 0]];
+    /// let graph = Graph::from_adjacency_matrix(&triangle, MatrixKind::Symmetric).unwrap();
+    /// assert_eq!(graph.vertices.len(), 3);
+    /// assert_eq!(graph.edges.len(), 3);
+    ///
+    /// assert!(Graph::from_adjacency_matrix(&[vec![0, 1], vec![1]], MatrixKind::Symmetric).is_err());
+    /// assert!(Graph::from_adjacency_matrix(&[vec![1]], MatrixKind::Directed).is_err());
+    /// ```
+    pub fn from_adjacency_matrix(rows: &[Vec<u8>], kind: MatrixKind) -> Result<Graph, MatrixError> {
+        let mut builder = GraphBuilder::new();
+        for (i, row) in rows.iter().enumerate() {
+            if row.len() != rows.len() {
+                return Err(MatrixError::NotSquare {
+                    row: i + 1,
+                    length: row.len(),
+                    expected: rows.len(),
+                });
+            }
+            builder.add_vertex(NodeId(i + 1));
+        }
+        for (i, row) in rows.iter().enumerate() {
+            for (j, entry) in row.iter().enumerate() {
+                let (from, to) = (NodeId(i + 1), NodeId(j + 1));
+                match kind {
+                    MatrixKind::Symmetric if (*entry != 0) != (rows[j][i] != 0) => {
+                        return Err(MatrixError::Asymmetric(i + 1, j + 1));
+                    }
+                    // Each undirected edge is read from the upper triangle, and the diagonal
+                    MatrixKind::Symmetric if *entry != 0 && i <= j => {
+                        builder.add_edge(from, to);
+                    }
+                    MatrixKind::Directed if *entry != 0 => {
+                        builder.add_directed_edge(from, to);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(builder.build()?)
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum GraphBuildError {
+    #[error("Vertex 0 is reserved, vertices are numbered from 1")]
+    ReservedVertex,
+    #[error("{1:?} references {0:?}, which is not a vertex of the graph")]
+    UnknownVertex(NodeId, Connection),
+    #[error("{0:?} is connected to itself")]
+    SelfLoop(NodeId),
+    #[error("{0:?} joins two vertices that are already connected")]
+    DuplicateEdge(Connection),
 }
 
 /// Enumerates each type of line in a graph file
 enum Line {
-    Comment(String),
+    Comment,
     Manifest(usize, usize),
-    Edge(usize, usize),
+    Edge(usize, usize, Option<String>),
+    Role(usize, String),
 }
 
 #[derive(Error, Debug)]
@@ -40,19 +702,34 @@ pub enum LineParsingError {
     UnknownMarker(String, char),
     #[error("Unexpected arguments length (actual: {1}, expected: {2}) in \"{0}\"")]
     UnexpectedArguments(String, usize, usize),
+    #[error("Unexpected problem type '{1}' in \"{0}\", expected one of {PROBLEM_TYPES:?}")]
+    UnexpectedProblemType(String, String),
+    #[error("Unexpected vertex '{1}' in \"{0}\", expected a vertex number")]
+    UnexpectedVertex(String, String),
 }
 
+/// Problem types of the DIMACS formats describing a graph by its edges.
+const PROBLEM_TYPES: [&str; 2] = ["edge", "col"];
+
 impl FromStr for Line {
     type Err = LineParsingError;
 
     fn from_str(s: &str) -> Result<Self, LineParsingError> {
+        let s = s.trim_start();
         let first_char = s.chars().next().ok_or(LineParsingError::EmptyLine)?;
 
         match first_char {
-            // Comments are skipped
-            'c' => Ok(Self::Comment(s.to_string())),
+            // Comments are skipped, generators often use '#' rather than DIMACS' 'c'
+            'c' | '#' => Ok(Self::Comment),
             'p' => {
                 // p edge X X
+                let problem = s.split_whitespace().nth(1).unwrap_or_default();
+                if !PROBLEM_TYPES.contains(&problem) {
+                    return Err(LineParsingError::UnexpectedProblemType(
+                        s.into(),
+                        problem.into(),
+                    ));
+                }
                 let hints: Vec<usize> = s
                     .split_whitespace()
                     .skip(2)
@@ -60,8 +737,8 @@ impl FromStr for Line {
                     .collect();
                 if hints.len() == 2 {
                     Ok(Self::Manifest(
-                        hints.get(0).unwrap().clone(),
-                        hints.get(1).unwrap().clone(),
+                        *hints.first().unwrap(),
+                        *hints.get(1).unwrap(),
                     ))
                 } else {
                     Err(LineParsingError::UnexpectedArguments(
@@ -72,17 +749,22 @@ impl FromStr for Line {
                 }
             }
             'e' => {
-                // e X X
-                let hints: Vec<usize> = s
-                    .split_whitespace()
-                    .skip(1)
-                    .filter_map(|s| s.parse().ok())
-                    .collect();
+                // e X X [label=L]
+                let mut fields: Vec<&str> = s.split_whitespace().skip(1).collect();
+                let label = match fields.last().and_then(|f| f.strip_prefix("label=")) {
+                    Some(label) => {
+                        fields.pop();
+                        Some(label.to_string())
+                    }
+                    None => None,
+                };
+                let hints: Vec<usize> = fields.iter().filter_map(|s| s.parse().ok()).collect();
 
                 if hints.len() == 2 {
                     Ok(Self::Edge(
-                        hints.get(0).unwrap().clone(),
-                        hints.get(1).unwrap().clone(),
+                        *hints.first().unwrap(),
+                        *hints.get(1).unwrap(),
+                        label,
                     ))
                 } else {
                     Err(LineParsingError::UnexpectedArguments(
@@ -92,6 +774,24 @@ impl FromStr for Line {
                     ))
                 }
             }
+            'n' => {
+                // n X role
+                let fields: Vec<&str> = s.split_whitespace().skip(1).collect();
+                match fields.as_slice() {
+                    [vertex, role] => match vertex.parse() {
+                        Ok(vertex) => Ok(Self::Role(vertex, role.to_string())),
+                        Err(_) => Err(LineParsingError::UnexpectedVertex(
+                            s.into(),
+                            vertex.to_string(),
+                        )),
+                    },
+                    _ => Err(LineParsingError::UnexpectedArguments(
+                        s.into(),
+                        2,
+                        fields.len(),
+                    )),
+                }
+            }
             _ => Err(LineParsingError::UnknownMarker(s.into(), first_char)),
         }
     }
@@ -107,60 +807,474 @@ pub enum GraphParsingError {
         "An edge has been read when the graph is not yet initialized (there must be a line that starts with p before edges)"
     )]
     Uninitialized,
+    #[error("Line {0} of the CSV is not an edge: \"{1}\"")]
+    InvalidCsvLine(usize, String),
+    #[error("Vertex 0 is reserved, vertices are numbered from 1")]
+    ReservedVertex,
+    #[error("{0} edges are repeated, while duplicates are rejected")]
+    DuplicateEdges(usize),
+    #[error("Line {line} is not valid UTF-8 from byte {column}")]
+    NonUtf8 { line: usize, column: usize },
+    #[error("Line {line} is longer than the maximum of {max} bytes")]
+    LineTooLong { line: usize, max: usize },
+    #[error("The file has more than the maximum of {0} lines")]
+    TooManyLines(usize),
+    #[error("Vertex {vertex} is above the maximum id of {max}")]
+    VertexOutOfRange { vertex: usize, max: usize },
+    #[error("{edge:?} is labelled both as {first:?} and as {second:?}")]
+    ConflictingLabels {
+        edge: Connection,
+        first: String,
+        second: String,
+    },
     #[error(transparent)]
     LineParsing(#[from] LineParsingError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// Identifies a vertex read from a graph file, rejecting the reserved `0` and the ids above
+/// the limit.
+fn vertex(v: usize, limits: &ParseLimits) -> Result<NodeId, GraphParsingError> {
+    if let Some(max) = limits.max_vertex_id.filter(|max| v > *max) {
+        return Err(GraphParsingError::VertexOutOfRange { vertex: v, max });
+    }
+    NodeId::new(v).ok_or(GraphParsingError::ReservedVertex)
+}
+
+/// Returns the set initialized by the problem line, or a fresh one when parsing leniently.
+fn initialized<T>(
+    set: &mut Option<HashSet<T>>,
+    lenient: bool,
+) -> Result<&mut HashSet<T>, GraphParsingError> {
+    if lenient && set.is_none() {
+        *set = Some(HashSet::new());
+    }
+    set.as_mut().ok_or(GraphParsingError::Uninitialized)
+}
+
+/// Bounds on the files read by the graph parsers, so that a huge or malformed file is
+/// rejected rather than loaded whole into memory.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ParseLimits {
+    /// Longest line accepted, in bytes, its line ending excluded.
+    pub max_line_bytes: Option<usize>,
+    /// Largest number of lines accepted, empty ones included.
+    pub max_lines: Option<usize>,
+    /// Largest vertex id accepted.
+    pub max_vertex_id: Option<usize>,
+}
+
+/// Reads the lines of a graph file one at a time, within the limits, without their ending.
+struct Lines<R> {
+    reader: R,
+    limits: ParseLimits,
+    read: usize,
+}
+
+impl<R: BufRead> Iterator for Lines<R> {
+    type Item = Result<String, GraphParsingError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut buffer = Vec::new();
+        // Reading stops right after the maximum, whether or not the line ends there
+        let limit = self
+            .limits
+            .max_line_bytes
+            .map_or(u64::MAX, |max| max as u64 + 2);
+        match (&mut self.reader)
+            .take(limit)
+            .read_until(b'\n', &mut buffer)
+        {
+            Ok(0) => return None,
+            Ok(_) => {}
+            Err(e) => return Some(Err(e.into())),
+        }
+        self.read += 1;
+        let line = self.read;
+        if let Some(max) = self.limits.max_lines.filter(|max| line > *max) {
+            return Some(Err(GraphParsingError::TooManyLines(max)));
+        }
+        if buffer.ends_with(b"\n") {
+            buffer.pop();
+            if buffer.ends_with(b"\r") {
+                buffer.pop();
+            }
+        }
+        if let Some(max) = self.limits.max_line_bytes.filter(|max| buffer.len() > *max) {
+            return Some(Err(GraphParsingError::LineTooLong { line, max }));
+        }
+        Some(
+            String::from_utf8(buffer).map_err(|e| GraphParsingError::NonUtf8 {
+                line,
+                column: e.utf8_error().valid_up_to(),
+            }),
+        )
+    }
+}
+
+/// A parsed [`Graph`], along with the number of edges that were read more than once.
+///
+/// Edges being stored in a set, repeated edges collapse into a single one.
+#[derive(Debug, Clone)]
+pub struct ParsedGraph {
+    pub graph: Graph,
+    pub duplicate_edges: usize,
+}
+
+impl ParsedGraph {
+    /// Returns the graph, or an error if any edge has been repeated.
+    pub fn strict(self) -> Result<Graph, GraphParsingError> {
+        match self.duplicate_edges {
+            0 => Ok(self.graph),
+            duplicates => Err(GraphParsingError::DuplicateEdges(duplicates)),
+        }
+    }
 }
 
 impl FromStr for Graph {
     type Err = GraphParsingError;
 
+    /// Parses a graph written in the DIMACS format.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use renraku_coordinator::Graph;
+    /// # use renraku_shared::{Connection, NodeId};
+    ///
+    /// let graph: Graph = "c DIMACS comment\n# generator comment\np edge 3 2\n  # indented\ne 1 2\ne 2 3\n"
+    ///     .parse()
+    ///     .unwrap();
+    /// assert_eq!(graph.edges.len(), 2);
+    ///
+    /// // Files edited on Windows use CRLF line endings
+    /// let graph: Graph = "p edge 3 2\r\ne 1 2 \r\ne 2 3\r\n\r\n".parse().unwrap();
+    /// assert_eq!(graph.vertices.len(), 3);
+    ///
+    /// // Vertices may be tagged with a role
+    /// let graph: Graph = "p edge 3 2\nn 1 producer\nn 3 consumer\ne 1 2\ne 2 3\n".parse().unwrap();
+    /// assert_eq!(graph.roles[&NodeId(1)], "producer");
+    /// assert!(!graph.roles.contains_key(&NodeId(2)));
+    /// assert!("p edge 3 2\nn 1\ne 1 2\n".parse::<Graph>().is_err());
+    ///
+    /// // Edges may carry a label
+    /// let graph: Graph = "p edge 3 2\ne 2 1 label=fast\ne 2 3\n".parse().unwrap();
+    /// assert_eq!(graph.labels[&Connection(NodeId(1), NodeId(2))], "fast");
+    /// assert_eq!(graph.labels.len(), 1);
+    /// assert!("p edge 2 2\ne 1 2 label=fast\ne 2 1 label=slow\n".parse::<Graph>().is_err());
+    ///
+    /// // Coloring instances describe their graph the same way, unlike other problems
+    /// assert!("p col 2 1\ne 1 2\n".parse::<Graph>().is_ok());
+    /// assert!("p sp 2 1\na 1 2 4\n".parse::<Graph>().is_err());
+    /// assert!("p egde 2 1\ne 1 2\n".parse::<Graph>().is_err());
+    ///
+    /// // Vertices are numbered from 1
+    /// assert!("p edge 2 1\ne 0 1\n".parse::<Graph>().is_err());
+    /// ```
     fn from_str(s: &str) -> Result<Self, GraphParsingError> {
+        Ok(Self::parse_dimacs(s)?.graph)
+    }
+}
+
+impl Graph {
+    /// Parses a graph written in the DIMACS format, counting the repeated edges.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use renraku_coordinator::Graph;
+    ///
+    /// let parsed = Graph::parse_dimacs("p edge 3 3\ne 1 2\ne 2 3\ne 2 1\n").unwrap();
+    /// assert_eq!(parsed.graph.edges.len(), 2);
+    /// assert_eq!(parsed.duplicate_edges, 1);
+    /// assert!(parsed.strict().is_err());
+    /// ```
+    pub fn parse_dimacs(s: &str) -> Result<ParsedGraph, GraphParsingError> {
+        Self::from_reader(s.as_bytes(), ParseLimits::default())
+    }
+
+    /// Parses a graph written in the DIMACS format, reading it one line at a time within the
+    /// limits.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use renraku_coordinator::graph::{Graph, GraphParsingError, ParseLimits};
+    ///
+    /// let limits = ParseLimits {
+    ///     max_line_bytes: Some(16),
+    ///     max_lines: Some(3),
+    ///     max_vertex_id: Some(3),
+    /// };
+    /// let parsed = Graph::from_reader("p edge 3 2\r\ne 1 2\r\ne 2 3\r\n".as_bytes(), limits).unwrap();
+    /// assert_eq!(parsed.graph.edges.len(), 2);
+    ///
+    /// let comment = format!("p edge 2 1\nc {}\ne 1 2\n", "-".repeat(64));
+    /// assert!(matches!(
+    ///     Graph::from_reader(comment.as_bytes(), limits),
+    ///     Err(GraphParsingError::LineTooLong { line: 2, max: 16 })
+    /// ));
+    /// assert!(matches!(
+    ///     Graph::from_reader("p edge 3 3\ne 1 2\ne 2 3\ne 1 3\n".as_bytes(), limits),
+    ///     Err(GraphParsingError::TooManyLines(3))
+    /// ));
+    /// assert!(matches!(
+    ///     Graph::from_reader("p edge 4 1\ne 1 4\n".as_bytes(), limits),
+    ///     Err(GraphParsingError::VertexOutOfRange { vertex: 4, max: 3 })
+    /// ));
+    /// ```
+    pub fn from_reader<R: BufRead>(
+        r: R,
+        limits: ParseLimits,
+    ) -> Result<ParsedGraph, GraphParsingError> {
+        Self::dimacs_from_reader(r, limits, false)
+    }
+
+    /// Parses a graph written in the DIMACS format as [`Graph::from_reader`] does, accepting
+    /// edges listed before the problem line, or without any problem line at all.
+    ///
+    /// The problem line only sizes the graph, its vertices and edges being inferred from the
+    /// edge lines: a single pass is enough wherever it stands. A file describing no edge and
+    /// no problem line is still rejected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use renraku_coordinator::graph::{Graph, GraphParsingError, ParseLimits};
+    ///
+    /// let last = "e 1 2\ne 2 3\np edge 3 2\n".as_bytes();
+    /// assert!(matches!(
+    ///     Graph::from_reader(last, ParseLimits::default()),
+    ///     Err(GraphParsingError::Uninitialized)
+    /// ));
+    /// let parsed = Graph::lenient_from_reader(last, ParseLimits::default()).unwrap();
+    /// assert_eq!(parsed.graph, "p edge 3 2\ne 1 2\ne 2 3\n".parse().unwrap());
+    ///
+    /// assert!(Graph::lenient_from_reader("c empty\n".as_bytes(), ParseLimits::default()).is_err());
+    /// ```
+    pub fn lenient_from_reader<R: BufRead>(
+        r: R,
+        limits: ParseLimits,
+    ) -> Result<ParsedGraph, GraphParsingError> {
+        Self::dimacs_from_reader(r, limits, true)
+    }
+
+    fn dimacs_from_reader<R: BufRead>(
+        r: R,
+        limits: ParseLimits,
+        lenient: bool,
+    ) -> Result<ParsedGraph, GraphParsingError> {
         let mut vertices: Option<HashSet<NodeId>> = None;
         let mut edges: Option<HashSet<Connection>> = None;
+        let mut roles = HashMap::new();
+        let mut labels: HashMap<Connection, String> = HashMap::new();
+        let mut duplicate_edges = 0;
 
-        let lines: Vec<Result<Line, LineParsingError>> = s
-            .split("\n")
-            .filter(|s| !s.is_empty())
-            .map(|s| Line::from_str(s))
-            .collect();
-
+        let lines = Lines {
+            reader: r,
+            limits,
+            read: 0,
+        };
         for line in lines {
-            match line? {
-                Line::Comment(_) => {}
-                Line::Manifest(v, e) => {
-                    vertices = Some(HashSet::with_capacity(v));
-                    edges = Some(HashSet::with_capacity(e));
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            match line.parse()? {
+                Line::Comment => {}
+                Line::Manifest(v, e) => match (vertices.as_mut(), edges.as_mut()) {
+                    // Leniently, the edges read before the problem line are kept
+                    (Some(vertices), Some(edges)) if lenient => {
+                        vertices.reserve(v);
+                        edges.reserve(e);
+                    }
+                    _ => {
+                        vertices = Some(HashSet::with_capacity(v));
+                        edges = Some(HashSet::with_capacity(e));
+                    }
+                },
+                Line::Edge(v1, v2, label) => {
+                    let (v1, v2) = (vertex(v1, &limits)?, vertex(v2, &limits)?);
+                    let vertices = initialized(&mut vertices, lenient)?;
+                    vertices.insert(v1.clone());
+                    vertices.insert(v2.clone());
+                    let edge = Connection::new(v1, v2);
+                    let inserted = initialized(&mut edges, lenient)?.insert(edge.clone());
+                    if !inserted {
+                        duplicate_edges += 1;
+                    }
+                    match (label, labels.get(&edge)) {
+                        (Some(second), Some(first)) if *first != second => {
+                            return Err(GraphParsingError::ConflictingLabels {
+                                edge,
+                                first: first.clone(),
+                                second,
+                            });
+                        }
+                        (Some(label), _) => {
+                            labels.insert(edge, label);
+                        }
+                        (None, _) => {}
+                    }
                 }
-                Line::Edge(v1, v2) => {
-                    vertices
-                        .as_mut()
-                        .ok_or(GraphParsingError::Uninitialized)?
-                        .insert(NodeId(v1));
-                    vertices
-                        .as_mut()
-                        .ok_or(GraphParsingError::Uninitialized)?
-                        .insert(NodeId(v2));
-                    edges
-                        .as_mut()
-                        .ok_or(GraphParsingError::Uninitialized)?
-                        .insert(Connection(NodeId(v1.min(v2)), NodeId(v1.max(v2))));
+                Line::Role(v, role) => {
+                    roles.insert(vertex(v, &limits)?, role);
                 }
             }
         }
 
-        Ok(Self {
-            vertices: vertices.ok_or(GraphParsingError::InvalidGraph)?,
-            edges: edges.ok_or(GraphParsingError::InvalidGraph)?,
+        Ok(ParsedGraph {
+            graph: Self {
+                vertices: vertices.ok_or(GraphParsingError::InvalidGraph)?,
+                edges: edges.ok_or(GraphParsingError::InvalidGraph)?,
+                roles,
+                labels,
+            },
+            duplicate_edges,
+        })
+    }
+
+    /// Parses a graph written as a CSV edge list, one `src,dst` edge per line.
+    ///
+    /// A third `weight` column is accepted and ignored, and a header line is skipped when
+    /// present. Vertices are inferred from the edges.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use renraku_coordinator::Graph;
+    ///
+    /// let graph = Graph::from_csv("1,2\n2,3\n".as_bytes()).unwrap();
+    /// assert_eq!(graph.vertices.len(), 3);
+    /// assert_eq!(graph.edges.len(), 2);
+    ///
+    /// let weighted = Graph::from_csv("src,dst,weight\n1,2,0.5\n3, 1, 2\n".as_bytes()).unwrap();
+    /// assert_eq!(weighted.vertices.len(), 3);
+    /// assert_eq!(weighted.edges.len(), 2);
+    ///
+    /// assert!(Graph::from_csv("1,2\n2;3\n".as_bytes()).is_err());
+    /// ```
+    pub fn from_csv<R: Read>(r: R) -> Result<Graph, GraphParsingError> {
+        Ok(Self::parse_csv(r)?.graph)
+    }
+
+    /// Parses a graph written as a CSV edge list, counting the repeated edges.
+    pub fn parse_csv<R: Read>(r: R) -> Result<ParsedGraph, GraphParsingError> {
+        Self::csv_from_reader(BufReader::new(r), ParseLimits::default())
+    }
+
+    /// Parses a graph written as a CSV edge list, reading it one line at a time within the
+    /// limits.
+    pub fn csv_from_reader<R: BufRead>(
+        r: R,
+        limits: ParseLimits,
+    ) -> Result<ParsedGraph, GraphParsingError> {
+        let mut vertices = HashSet::new();
+        let mut edges = HashSet::new();
+        let mut duplicate_edges = 0;
+        let lines = Lines {
+            reader: r,
+            limits,
+            read: 0,
+        };
+        for (i, line) in lines.enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let edge = match fields.as_slice() {
+                [src, dst] => src.parse::<usize>().ok().zip(dst.parse::<usize>().ok()),
+                [src, dst, weight] if weight.parse::<f64>().is_ok() => {
+                    src.parse::<usize>().ok().zip(dst.parse::<usize>().ok())
+                }
+                _ => None,
+            };
+            match edge {
+                Some((v1, v2)) => {
+                    let (v1, v2) = (vertex(v1, &limits)?, vertex(v2, &limits)?);
+                    vertices.insert(v1.clone());
+                    vertices.insert(v2.clone());
+                    if !edges.insert(Connection::new(v1, v2)) {
+                        duplicate_edges += 1;
+                    }
+                }
+                // The first line may name the columns
+                None if i == 0 && (2..=3).contains(&fields.len()) => {}
+                None => return Err(GraphParsingError::InvalidCsvLine(i + 1, line.into())),
+            }
+        }
+        Ok(ParsedGraph {
+            graph: Self {
+                vertices,
+                edges,
+                roles: HashMap::new(),
+                labels: HashMap::new(),
+            },
+            duplicate_edges,
         })
     }
+
+    /// Reads a graph from a file, written as a CSV edge list when its extension is `csv`
+    /// and in the DIMACS format otherwise.
+    ///
+    /// Files compressed with gzip, either named with a `gz` extension or starting with the
+    /// gzip header, are decompressed while read: `graph.csv.gz` is then read as a CSV edge
+    /// list.
+    pub fn open(path: &Path) -> Result<ParsedGraph> {
+        Self::open_with_limits(path, ParseLimits::default())
+    }
+
+    /// Reads a graph from a file as [`Graph::open`] does, within the limits.
+    pub fn open_with_limits(path: &Path, limits: ParseLimits) -> Result<ParsedGraph> {
+        Self::open_as(path, limits, false)
+    }
+
+    /// Reads a graph from a file as [`Graph::open_with_limits`] does, DIMACS files being
+    /// parsed with [`Graph::lenient_from_reader`].
+    pub fn open_lenient(path: &Path, limits: ParseLimits) -> Result<ParsedGraph> {
+        Self::open_as(path, limits, true)
+    }
+
+    fn open_as(path: &Path, limits: ParseLimits, lenient: bool) -> Result<ParsedGraph> {
+        let mut file = BufReader::new(File::open(path)?);
+        let compressed = has_extension(path, "gz");
+        let file: Box<dyn BufRead> = if compressed || file.fill_buf()?.starts_with(&GZIP_MAGIC) {
+            Box::new(BufReader::new(MultiGzDecoder::new(file)))
+        } else {
+            Box::new(file)
+        };
+        // The format is told by the extension preceding the one of the compression
+        let path = match path.file_stem() {
+            Some(stem) if compressed => Path::new(stem),
+            _ => path,
+        };
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(extension) if extension.eq_ignore_ascii_case("csv") => {
+                Ok(Self::csv_from_reader(file, limits)?)
+            }
+            _ => Ok(Self::dimacs_from_reader(file, limits, lenient)?),
+        }
+    }
+}
+
+/// First bytes of any file compressed with gzip.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Returns whether the extension of the path is the given one, whatever its case.
+fn has_extension(path: &Path, extension: &str) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case(extension))
 }
 
 impl TryFrom<File> for Graph {
     type Error = Report;
 
-    fn try_from(mut value: File) -> Result<Self> {
-        let mut buffer = String::new();
-        value.read_to_string(&mut buffer)?;
-        Ok(buffer.parse()?)
+    fn try_from(value: File) -> Result<Self> {
+        Ok(Self::from_reader(BufReader::new(value), ParseLimits::default())?.graph)
     }
 }
@@ -0,0 +1,11 @@
+pub mod command;
+pub mod generator;
+pub mod graph;
+pub mod plan;
+pub mod quorum;
+pub mod run;
+pub mod setup;
+pub mod witness;
+
+pub use graph::Graph;
+pub use run::{run, CoordinatorOptions, CoordinatorOptionsBuilder, TopologyReport};
@@ -1,66 +1,58 @@
 use std::{
-    fs::File,
-    net::{SocketAddr, UdpSocket},
+    net::{SocketAddr, ToSocketAddrs},
+    process::ExitCode,
+    time::Duration,
 };
 
 use clap::Parser;
-use color_eyre::eyre::Result;
-use command::Arguments;
-use graph::Graph;
-use renraku_shared::NodeId;
-
-pub mod command;
-pub mod graph;
+use color_eyre::eyre::{eyre, Result};
+use renraku_coordinator::{
+    command::Arguments,
+    graph::{Graph, ParseLimits},
+    run::{load, run},
+    CoordinatorOptions,
+};
 
-fn main() -> Result<()> {
+fn main() -> Result<ExitCode> {
     color_eyre::install()?;
 
     let arguments = Arguments::try_parse()?;
-    let graph = Graph::try_from(File::open(arguments.graph)?)?;
-
-    let socket = UdpSocket::bind(arguments.address)?;
-    let mut addresses = Vec::<SocketAddr>::new();
-    let mut listeners = Vec::<SocketAddr>::new();
-    while addresses.len() < graph.vertices.len() {
-        let mut buf = [0; 1024];
-        let (_, addr) = socket.recv_from(&mut buf)?;
-        let port = bincode::deserialize::<u16>(&buf)?;
-
-        addresses.push(addr.clone());
-
-        let mut addr = addr.clone();
-        addr.set_port(port);
-        listeners.push(addr);
-        println!(
-            "👋 A new client has arrived, he is listening on: {:?}",
-            addr
-        );
-    }
-
-    for (i, addr) in addresses.iter().enumerate() {
-        // First sends each of the program their ids
-        let id = NodeId(i + 1);
-        socket.send_to(&bincode::serialize(&(addresses.len(), id.clone()))?, addr)?;
-        // Then we count the number of connections they will receive
-        let incoming_connections = graph.edges.iter().filter(|e| e.1 == id).count();
-        socket.send_to(&bincode::serialize(&incoming_connections)?, addr)?;
-        // Then we send the address of each of the programs they have to connect to
-        let outgoing_addresses: Vec<SocketAddr> = graph
-            .edges
-            .iter()
-            .filter(|e| e.0 == id)
-            .map(|e| e.1.clone())
-            .map(|v| listeners.get(v.0 - 1))
-            .filter(|o| o.is_some())
-            .map(|o| o.unwrap().to_owned())
-            .collect();
-
-        socket.send_to(&bincode::serialize(&outgoing_addresses.len())?, addr)?;
-        for tcp_addr in outgoing_addresses.iter() {
-            socket.send_to(&bincode::serialize(tcp_addr)?, addr)?;
-        }
-        println!("🥳 Node #{} is now ready ! He will receive {} connections and connect to {} neighbours", id.0, incoming_connections, outgoing_addresses.len());
-    }
-
-    Ok(())
+    let address: SocketAddr = arguments
+        .address
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| eyre!("{} resolves to no address", arguments.address))?;
+    let options = CoordinatorOptions {
+        address,
+        limits: ParseLimits {
+            max_line_bytes: arguments.max_line_bytes,
+            max_lines: arguments.max_lines,
+            max_vertex_id: arguments.max_vertex_id,
+        },
+        strict: arguments.strict,
+        lenient: arguments.lenient,
+        receive_buffer: arguments.receive_buffer,
+        expected_nodes: arguments.expected_nodes,
+        registration_timeout: arguments.registration_timeout.map(Duration::from_secs),
+        balance: arguments.balance,
+        keep_vertex_ids: arguments.keep_vertex_ids,
+        log_format: arguments.log_format,
+        plan: arguments.plan,
+        verbose: arguments.verbose,
+        listening: None,
+        witness: arguments.witness.map(Duration::from_secs),
+        resident: arguments.resident.map(Duration::from_secs),
+    };
+
+    let graph = match arguments.complete {
+        Some(n) => Graph::complete(n),
+        None => Graph::default(),
+    };
+    let graph = load(graph, &arguments.graph, &options)?;
+    let report = run(graph, options)?;
+    Ok(if report.is_success() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
 }
@@ -0,0 +1,98 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    net::SocketAddr,
+};
+
+use renraku_shared::NodeId;
+use serde::Serialize;
+
+use crate::{graph::Graph, setup::SetupError};
+
+/// Connections of every vertex of a graph, computed from the graph alone before any node
+/// registers.
+///
+/// Each edge is dialed by its first vertex and accepted by its second one, as during the
+/// setup. Vertices are sorted by identifier, so that the same graph always gives the same
+/// plan.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TopologyPlan {
+    pub vertices: BTreeMap<NodeId, VertexPlan>,
+}
+
+/// Connections of a single vertex of a [`TopologyPlan`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct VertexPlan {
+    /// Vertices whose listener the node connects to.
+    pub outgoing: BTreeSet<NodeId>,
+    /// Vertices connecting to the listener of the node.
+    pub incoming: BTreeSet<NodeId>,
+}
+
+impl Graph {
+    /// Plans the connections of every vertex of the graph.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use renraku_coordinator::Graph;
+    /// # use renraku_shared::NodeId;
+    ///
+    /// // Undirected edges go from their lowest vertex to their highest
+    /// let graph: Graph = "p edge 3 2\ne 1 3\ne 3 2\n".parse().unwrap();
+    /// let plan = graph.plan();
+    ///
+    /// assert_eq!(plan.vertices[&NodeId(3)].incoming.len(), 2);
+    /// assert!(plan.vertices[&NodeId(3)].outgoing.is_empty());
+    /// assert_eq!(plan.vertices[&NodeId(2)].outgoing.first(), Some(&NodeId(3)));
+    /// assert_eq!(plan.connections(), 2);
+    /// ```
+    pub fn plan(&self) -> TopologyPlan {
+        let mut vertices: BTreeMap<NodeId, VertexPlan> = self
+            .vertices
+            .iter()
+            .map(|v| (v.clone(), VertexPlan::default()))
+            .collect();
+        for edge in self.edges.iter() {
+            let from = vertices.entry(edge.0.clone()).or_default();
+            from.outgoing.insert(edge.1.clone());
+            let to = vertices.entry(edge.1.clone()).or_default();
+            to.incoming.insert(edge.0.clone());
+        }
+        TopologyPlan { vertices }
+    }
+}
+
+impl TopologyPlan {
+    /// Returns the number of directed connections of the plan.
+    pub fn connections(&self) -> usize {
+        self.vertices.values().map(|v| v.outgoing.len()).sum()
+    }
+
+    /// Returns the number of nodes connecting to the vertex, none if it is not planned.
+    pub fn incoming_count(&self, vertex: &NodeId) -> usize {
+        self.vertices.get(vertex).map_or(0, |v| v.incoming.len())
+    }
+
+    /// Computes the listener address of each node the vertex connects to.
+    ///
+    /// `listeners` holds the listener address of each registered node. An edge towards a node
+    /// that has not registered is an error.
+    pub fn outgoing_addresses(
+        &self,
+        vertex: &NodeId,
+        listeners: &HashMap<NodeId, SocketAddr>,
+    ) -> Result<Vec<SocketAddr>, SetupError> {
+        let Some(plan) = self.vertices.get(vertex) else {
+            return Ok(Vec::new());
+        };
+        plan.outgoing
+            .iter()
+            .map(|peer| {
+                listeners
+                    .get(peer)
+                    .copied()
+                    .ok_or_else(|| SetupError::MissingListener(peer.clone()))
+            })
+            .collect()
+    }
+}
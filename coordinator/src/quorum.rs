@@ -0,0 +1,23 @@
+use renraku_shared::NodeId;
+
+/// Computes the voting quorum of a node, as used by Maekawa's algorithm.
+///
+/// Nodes are laid out row by row in a grid of `⌈√n⌉` columns, the quorum of a node being
+/// every node sharing its row or its column (itself included). Any two quorums intersect,
+/// even when the last row of the grid is incomplete: either the row of the first node crosses
+/// the column of the second, or the other way around.
+///
+/// `members` holds every node of the system in increasing order, each of them standing at
+/// its position in the grid whatever its identifier.
+pub fn grid_quorum(members: &[NodeId], id: &NodeId) -> Vec<NodeId> {
+    let width = (1..).find(|w| w * w >= members.len()).unwrap_or(1);
+    let position = members.iter().position(|member| member == id).unwrap_or(0);
+    let (row, column) = (position / width, position % width);
+
+    members
+        .iter()
+        .enumerate()
+        .filter(|(n, _)| n / width == row || n % width == column)
+        .map(|(_, member)| member.clone())
+        .collect()
+}
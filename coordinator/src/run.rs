@@ -0,0 +1,362 @@
+use std::{
+    fs,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    path::{Path, PathBuf},
+    sync::mpsc::Sender,
+    time::Duration,
+};
+
+use color_eyre::{eyre::eyre, Result};
+
+use crate::{
+    graph::{format_histogram, Graph, GraphStats, ParseLimits},
+    plan::TopologyPlan,
+    setup::{admit, bind, prune_unregistered, serve, LogFormat, SetupEvent, SetupSummary},
+    witness::{watch, Witness},
+};
+
+/// Address the coordinator listens on unless told otherwise.
+pub const DEFAULT_ADDRESS: SocketAddr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 3000);
+
+/// Settings of a coordinator run with [`run`], mirroring its command line arguments.
+///
+/// Built field by field or with a [`CoordinatorOptionsBuilder`], every setting left out
+/// keeping its default.
+#[derive(Debug, Clone)]
+pub struct CoordinatorOptions {
+    /// Address the nodes register on, [`DEFAULT_ADDRESS`] by default.
+    pub address: SocketAddr,
+    /// Bounds on the graph files read with [`load`].
+    pub limits: ParseLimits,
+    /// Rejects graph files holding the same edge more than once, rather than ignoring
+    /// duplicates.
+    pub strict: bool,
+    /// Accepts DIMACS files listing edges before their problem line, or without any.
+    pub lenient: bool,
+    /// Size in bytes of the receive buffer of the coordinator's socket.
+    pub receive_buffer: Option<usize>,
+    /// Only waits for this many nodes, running on the subgraph induced by their ids.
+    pub expected_nodes: Option<usize>,
+    /// Gives up waiting for nodes after this delay.
+    pub registration_timeout: Option<Duration>,
+    /// Gives the vertices with the highest degree to the nodes with the highest capacity.
+    pub balance: bool,
+    /// Gives the nodes the vertices of the graph as they are numbered in its files, rather
+    /// than identifiers from `1` to the number of nodes.
+    pub keep_vertex_ids: bool,
+    /// How the progress of each node is reported on the standard output.
+    pub log_format: LogFormat,
+    /// Writes the connections planned for each vertex into this file, as JSON.
+    pub plan: Option<PathBuf>,
+    /// Prints the number of vertices of each degree.
+    pub verbose: bool,
+    /// Told the address the coordinator listens on once bound, for the nodes started
+    /// alongside it to know where to register.
+    pub listening: Option<Sender<SocketAddr>>,
+    /// Once the system is set up, checks the nodes telling their critical sections never are
+    /// within the same one, until none of them told anything for this long.
+    pub witness: Option<Duration>,
+    /// Once the system is set up, admits the nodes saying hello late, until none of them did
+    /// for this long. Both read the socket of the coordinator, so it cannot witness as well.
+    pub resident: Option<Duration>,
+}
+
+impl Default for CoordinatorOptions {
+    fn default() -> Self {
+        Self {
+            address: DEFAULT_ADDRESS,
+            limits: ParseLimits::default(),
+            strict: false,
+            lenient: false,
+            receive_buffer: None,
+            expected_nodes: None,
+            registration_timeout: None,
+            balance: false,
+            keep_vertex_ids: false,
+            log_format: LogFormat::default(),
+            plan: None,
+            verbose: false,
+            listening: None,
+            witness: None,
+            resident: None,
+        }
+    }
+}
+
+impl CoordinatorOptions {
+    pub fn builder() -> CoordinatorOptionsBuilder {
+        CoordinatorOptionsBuilder::new()
+    }
+}
+
+/// Builds the [`CoordinatorOptions`] of a run, one setting at a time.
+///
+/// # Examples
+///
+/// ```
+/// # use renraku_coordinator::{run::DEFAULT_ADDRESS, CoordinatorOptions};
+/// use std::time::Duration;
+///
+/// let options = CoordinatorOptions::builder()
+///     .address("127.0.0.1:4000".parse().unwrap())
+///     .expected_nodes(3)
+///     .registration_timeout(Duration::from_secs(5))
+///     .build();
+///
+/// assert_eq!(options.address.port(), 4000);
+/// assert_eq!(options.expected_nodes, Some(3));
+/// assert!(!options.balance);
+/// assert_eq!(CoordinatorOptions::builder().build().address, DEFAULT_ADDRESS);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CoordinatorOptionsBuilder {
+    options: CoordinatorOptions,
+}
+
+impl CoordinatorOptionsBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn address(&mut self, address: SocketAddr) -> &mut Self {
+        self.options.address = address;
+        self
+    }
+
+    pub fn limits(&mut self, limits: ParseLimits) -> &mut Self {
+        self.options.limits = limits;
+        self
+    }
+
+    pub fn strict(&mut self, strict: bool) -> &mut Self {
+        self.options.strict = strict;
+        self
+    }
+
+    pub fn lenient(&mut self, lenient: bool) -> &mut Self {
+        self.options.lenient = lenient;
+        self
+    }
+
+    pub fn receive_buffer(&mut self, bytes: usize) -> &mut Self {
+        self.options.receive_buffer = Some(bytes);
+        self
+    }
+
+    pub fn expected_nodes(&mut self, count: usize) -> &mut Self {
+        self.options.expected_nodes = Some(count);
+        self
+    }
+
+    pub fn registration_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.options.registration_timeout = Some(timeout);
+        self
+    }
+
+    pub fn balance(&mut self, balance: bool) -> &mut Self {
+        self.options.balance = balance;
+        self
+    }
+
+    pub fn keep_vertex_ids(&mut self, keep: bool) -> &mut Self {
+        self.options.keep_vertex_ids = keep;
+        self
+    }
+
+    pub fn log_format(&mut self, format: LogFormat) -> &mut Self {
+        self.options.log_format = format;
+        self
+    }
+
+    pub fn plan(&mut self, path: impl Into<PathBuf>) -> &mut Self {
+        self.options.plan = Some(path.into());
+        self
+    }
+
+    pub fn verbose(&mut self, verbose: bool) -> &mut Self {
+        self.options.verbose = verbose;
+        self
+    }
+
+    pub fn listening(&mut self, listening: Sender<SocketAddr>) -> &mut Self {
+        self.options.listening = Some(listening);
+        self
+    }
+
+    pub fn witness(&mut self, idle: Duration) -> &mut Self {
+        self.options.witness = Some(idle);
+        self
+    }
+
+    pub fn resident(&mut self, idle: Duration) -> &mut Self {
+        self.options.resident = Some(idle);
+        self
+    }
+
+    pub fn build(&self) -> CoordinatorOptions {
+        self.options.clone()
+    }
+}
+
+/// Outcome of a coordinator run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TopologyReport {
+    /// Statistics of the graph the nodes have been set up on.
+    pub stats: GraphStats,
+    /// Connections of each vertex of that graph.
+    pub plan: TopologyPlan,
+    /// Node given each identifier, along with the number of directed connections.
+    pub summary: SetupSummary,
+    /// Critical sections the nodes told about, when witnessed.
+    pub witness: Option<Witness>,
+}
+
+impl TopologyReport {
+    /// Returns whether every expected node has registered and been configured, without any
+    /// violation of mutual exclusion being witnessed.
+    pub fn is_success(&self) -> bool {
+        self.summary.is_success()
+            && self
+                .witness
+                .as_ref()
+                .is_none_or(|witness| witness.violations.is_empty())
+    }
+}
+
+/// Reads each graph file as the options tell and merges it into the graph, as the coordinator
+/// binary does before [`run`].
+///
+/// Duplicate edges are reported and ignored, unless the options are strict.
+///
+/// # Examples
+///
+/// ```
+/// # use renraku_coordinator::{run::load, CoordinatorOptions, Graph};
+/// use std::{env, fs};
+///
+/// let path = env::temp_dir().join(format!("renraku-load-{}.dimacs", std::process::id()));
+/// fs::write(&path, "p edge 3 3\ne 1 2\ne 2 3\ne 1 2\n").unwrap();
+///
+/// let graph = load(Graph::default(), &[&path], &CoordinatorOptions::default()).unwrap();
+/// assert_eq!(graph.edges.len(), 2);
+///
+/// let strict = CoordinatorOptions::builder().strict(true).build();
+/// assert!(load(Graph::default(), &[&path], &strict).is_err());
+/// # fs::remove_file(path).unwrap();
+/// ```
+pub fn load(
+    mut graph: Graph,
+    paths: &[impl AsRef<Path>],
+    options: &CoordinatorOptions,
+) -> Result<Graph> {
+    for path in paths.iter().map(AsRef::as_ref) {
+        let parsed = if options.lenient {
+            Graph::open_lenient(path, options.limits)?
+        } else {
+            Graph::open_with_limits(path, options.limits)?
+        };
+        let parsed = if options.strict {
+            parsed.strict()?
+        } else {
+            if parsed.duplicate_edges > 0 {
+                options.log_format.say(format_args!(
+                    "⚠️ {} duplicate edges of {} have been ignored",
+                    parsed.duplicate_edges,
+                    path.display()
+                ));
+            }
+            parsed.graph
+        };
+        graph.merge(parsed)?;
+    }
+    Ok(graph)
+}
+
+/// Sets a system up on the graph, listening for the nodes on the address of the options, as
+/// the coordinator binary does once it has read its graph files.
+///
+/// Returns once every expected node has been configured, or once the registration timed
+/// out, which the report tells apart. When witnessing, returns once the nodes stopped telling
+/// about their critical sections. When resident, returns once no node said hello late for a
+/// while, the summary then telling about the nodes that joined as well.
+///
+/// Fails before listening when the options expect no node, or more nodes than the graph
+/// has vertices.
+pub fn run(mut graph: Graph, options: CoordinatorOptions) -> Result<TopologyReport> {
+    if options.witness.is_some() && options.resident.is_some() {
+        return Err(eyre!(
+            "A coordinator cannot both witness and admit late nodes"
+        ));
+    }
+    if let Some(expected) = options.expected_nodes {
+        // Extra nodes would be members without any vertex, hence without any neighbour
+        let vertices = graph.vertices.len();
+        if expected == 0 || expected > vertices {
+            return Err(eyre!(
+                "Expecting {expected} nodes on a graph of {vertices} vertices, at least 1 and at most {vertices} can be"
+            ));
+        }
+        graph = prune_unregistered(&graph, expected);
+    }
+    let stats = graph.stats();
+    let format = options.log_format;
+    format.say(format_args!("📊 Loaded graph: {stats:?}"));
+    let histogram = format_histogram(&graph.degree_histogram());
+    if options.verbose && !histogram.is_empty() {
+        format.say(histogram.trim_end());
+    }
+    let plan = graph.plan();
+    if let Some(path) = &options.plan {
+        fs::write(path, serde_json::to_string_pretty(&plan)?)?;
+        format.say(format_args!(
+            "🗺️ Planned {} directed connections into {}",
+            plan.connections(),
+            path.display()
+        ));
+    }
+
+    let socket = bind(options.address, options.receive_buffer)?;
+    let expected = options.expected_nodes.unwrap_or(graph.vertices.len());
+    let local = socket.local_addr()?;
+    SetupEvent::Listening {
+        expected,
+        address: local,
+    }
+    .report(format)?;
+    if let Some(listening) = &options.listening {
+        // Nobody waiting for the address is no reason to stop
+        let _ = listening.send(local);
+    }
+
+    let mut witness = None;
+    let mut summary = serve(
+        &socket,
+        &graph,
+        expected,
+        options.registration_timeout,
+        options.balance,
+        options.keep_vertex_ids,
+        format,
+    )?;
+    if summary.is_success() {
+        format.say(format_args!("✅ Setup complete: {summary}"));
+        if let Some(idle) = options.witness {
+            format.say("👁️ Witnessing the critical sections");
+            witness = Some(watch(&socket, idle, format)?);
+        }
+        if let Some(idle) = options.resident {
+            format.say("🚪 Admitting the nodes joining late");
+            admit(&socket, &mut graph, &mut summary, idle, format)?;
+        }
+    } else {
+        format.say(format_args!(
+            "❌ Nodes failed to register in time: {summary}"
+        ));
+    }
+    Ok(TopologyReport {
+        stats,
+        plan,
+        summary,
+        witness,
+    })
+}
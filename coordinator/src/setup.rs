@@ -0,0 +1,721 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    io::{self, ErrorKind},
+    net::{SocketAddr, ToSocketAddrs, UdpSocket},
+    thread,
+    time::{Duration, Instant},
+};
+
+use color_eyre::Result;
+use renraku_shared::{
+    control::{ControlMessage, WireAddr},
+    Connection, NodeId,
+};
+use serde::Serialize;
+use socket2::{Domain, Protocol, Socket, Type};
+use thiserror::Error;
+
+use crate::{graph::Graph, quorum::grid_quorum};
+
+#[derive(Error, Debug)]
+pub enum SetupError {
+    #[error("Node {0:?} is referenced by an edge but no node has registered a listener for it")]
+    MissingListener(NodeId),
+}
+
+/// Restricts the graph to its `registered` lowest vertices, dropping every edge towards
+/// a node that has not registered.
+///
+/// Graphs numbered from `1` without gaps keep the vertices `1` to `registered`, while the
+/// others keep the vertices their nodes are given with `keep_vertex_ids`.
+///
+/// # Examples
+///
+/// ```
+/// # use renraku_coordinator::{graph::Graph, setup::prune_unregistered};
+/// # use renraku_shared::NodeId;
+///
+/// let graph: Graph = "p edge 5 4\ne 1 2\ne 2 3\ne 3 4\ne 4 5\n".parse().unwrap();
+/// let pruned = prune_unregistered(&graph, 3);
+///
+/// assert_eq!(pruned.vertices.len(), 3);
+/// assert_eq!(pruned.edges.len(), 2);
+///
+/// let sparse: Graph = "p edge 8 3\ne 2 4\ne 4 6\ne 6 8\n".parse().unwrap();
+/// let pruned = prune_unregistered(&sparse, 3);
+/// assert_eq!(pruned.sorted_vertices(), [2, 4, 6].map(NodeId));
+/// assert_eq!(pruned.edges.len(), 2);
+/// ```
+pub fn prune_unregistered(graph: &Graph, registered: usize) -> Graph {
+    graph.subgraph(
+        &graph
+            .sorted_vertices()
+            .into_iter()
+            .take(registered)
+            .collect(),
+    )
+}
+
+/// Computes the listener address of each node the given node has to connect to.
+///
+/// `listeners` holds the listener address of each registered node. An edge towards a node
+/// that has not registered is an error.
+///
+/// # Examples
+///
+/// ```
+/// # use renraku_coordinator::{graph::Graph, setup::outgoing_addresses};
+/// # use renraku_shared::NodeId;
+/// use std::{collections::HashMap, net::SocketAddr};
+///
+/// let graph: Graph = "p edge 3 2\ne 1 2\ne 1 3\n".parse().unwrap();
+/// let listeners: HashMap<NodeId, SocketAddr> = HashMap::from([
+///     (NodeId(1), "127.0.0.1:4001".parse().unwrap()),
+///     (NodeId(2), "127.0.0.1:4002".parse().unwrap()),
+/// ]);
+///
+/// assert!(outgoing_addresses(&graph, &NodeId(2), &listeners).unwrap().is_empty());
+/// assert!(outgoing_addresses(&graph, &NodeId(1), &listeners).is_err());
+/// ```
+pub fn outgoing_addresses(
+    graph: &Graph,
+    id: &NodeId,
+    listeners: &HashMap<NodeId, SocketAddr>,
+) -> Result<Vec<SocketAddr>, SetupError> {
+    graph.plan().outgoing_addresses(id, listeners)
+}
+
+/// Binds the socket the coordinator receives hellos on.
+///
+/// When `receive_buffer` is set, the receive buffer of the socket is resized accordingly
+/// (the operating system may round or cap the value).
+///
+/// An IPv6 socket is made dual-stack whatever the platform defaults to (`IPV6_V6ONLY`), so
+/// that nodes registering over IPv4 are heard as well when bound to `[::]`.
+///
+/// # Examples
+///
+/// ```
+/// # use renraku_coordinator::setup::bind;
+/// use std::net::UdpSocket;
+///
+/// let socket = bind("localhost:0", Some(1 << 20)).unwrap();
+/// let client = UdpSocket::bind("localhost:0").unwrap();
+/// for port in 0..200u16 {
+///     client.send_to(&bincode::serialize(&port).unwrap(), socket.local_addr().unwrap()).unwrap();
+/// }
+///
+/// let mut buf = [0; 1024];
+/// for port in 0..200u16 {
+///     socket.recv(&mut buf).unwrap();
+///     assert_eq!(bincode::deserialize::<u16>(&buf).unwrap(), port);
+/// }
+/// ```
+pub fn bind(address: impl ToSocketAddrs, receive_buffer: Option<usize>) -> io::Result<UdpSocket> {
+    let address = address
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no address to bind to"))?;
+    let socket = Socket::new(
+        Domain::for_address(address),
+        Type::DGRAM,
+        Some(Protocol::UDP),
+    )?;
+    if let Some(size) = receive_buffer {
+        socket.set_recv_buffer_size(size)?;
+    }
+    if address.is_ipv6() {
+        socket.set_only_v6(false)?;
+    }
+    socket.bind(&address.into())?;
+    Ok(socket.into())
+}
+
+/// How the coordinator reports the progress of the setup on its standard output.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq)]
+pub enum LogFormat {
+    /// Lines meant to be read by a human.
+    #[default]
+    Human,
+    /// A JSON object per [`SetupEvent`], one per line, anything else going to the standard
+    /// error.
+    Json,
+}
+
+impl LogFormat {
+    /// Prints a line meant for a human, which would break the JSON lines of the standard
+    /// output if it went there.
+    pub fn say(self, line: impl fmt::Display) {
+        match self {
+            LogFormat::Human => println!("{line}"),
+            LogFormat::Json => eprintln!("{line}"),
+        }
+    }
+}
+
+/// Progress of the setup, printed as a JSON line when logging in [`LogFormat::Json`].
+///
+/// # Examples
+///
+/// ```
+/// # use renraku_coordinator::setup::SetupEvent;
+///
+/// let event = SetupEvent::NodeReady {
+///     id: 2,
+///     source: "127.0.0.1:50000".parse().unwrap(),
+///     listener: "127.0.0.1:4002".parse().unwrap(),
+///     incoming: 1,
+///     outgoing: 0,
+/// };
+/// assert_eq!(
+///     serde_json::to_string(&event).unwrap(),
+///     r#"{"event":"node_ready","id":2,"source":"127.0.0.1:50000","listener":"127.0.0.1:4002","incoming":1,"outgoing":0}"#
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum SetupEvent {
+    /// The coordinator waits for the nodes to register.
+    Listening {
+        /// Number of nodes it waits for.
+        expected: usize,
+        /// Address the nodes say hello to.
+        address: SocketAddr,
+    },
+    /// A node has been sent its configuration.
+    NodeReady {
+        id: usize,
+        /// Address the node registered from.
+        source: SocketAddr,
+        /// Address the node listens on for its neighbours.
+        listener: SocketAddr,
+        /// Number of neighbours that will connect to the node.
+        incoming: usize,
+        /// Number of neighbours the node connects to.
+        outgoing: usize,
+    },
+}
+
+impl SetupEvent {
+    /// Prints the event on the standard output, in the given format.
+    pub fn report(&self, format: LogFormat) -> Result<()> {
+        match format {
+            LogFormat::Human => println!("{self}"),
+            LogFormat::Json => println!("{}", serde_json::to_string(self)?),
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for SetupEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SetupEvent::Listening { expected, address } => {
+                write!(f, "👂 Waiting for {expected} nodes on {address}")
+            }
+            SetupEvent::NodeReady {
+                id,
+                incoming,
+                outgoing,
+                ..
+            } => write!(
+                f,
+                "🥳 Node #{id} is now ready ! He will receive {incoming} connections and connect to {outgoing} neighbours"
+            ),
+        }
+    }
+}
+
+/// Waits for `expected` nodes to register on the socket, then sends each of them its
+/// configuration.
+///
+/// The connections are planned with [`Graph::plan`] before any node registers. Nodes are
+/// given the identifier they asked for when it is free, and the others in the order their
+/// hello is received. When `timeout`
+/// elapses before every node has registered, no node is configured and the returned summary
+/// lists the missing ones.
+///
+/// When `balance` is set, identifiers are rather given with [`assign_ids`], according to the
+/// capacity advertised by each node. Identifiers range from `1` to `expected`, unless
+/// `keep_vertex_ids` is set: nodes are then given the vertices of the graph as they are
+/// numbered in its file, even when some numbers are skipped. Progress is printed in the
+/// given `format`.
+pub fn serve(
+    socket: &UdpSocket,
+    graph: &Graph,
+    expected: usize,
+    timeout: Option<Duration>,
+    balance: bool,
+    keep_vertex_ids: bool,
+    format: LogFormat,
+) -> Result<SetupSummary> {
+    let mut addresses = Vec::<SocketAddr>::new();
+    let mut registered = Vec::<SocketAddr>::new();
+    let mut capacities = Vec::<usize>::new();
+    let mut desired = Vec::<Option<NodeId>>::new();
+    let plan = graph.plan();
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    while addresses.len() < expected {
+        if let Some(deadline) = deadline {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                break;
+            }
+            socket.set_read_timeout(Some(remaining))?;
+        }
+        let mut buf = [0; 1024];
+        let (received, addr) = match socket.recv_from(&mut buf) {
+            Ok(received) => received,
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => break,
+            Err(e) => return Err(e.into()),
+        };
+        // A single buggy client must not abort the setup of every other node
+        let message = match ControlMessage::decode(&buf[..received]) {
+            Ok(message) => message,
+            Err(e) => {
+                format.say(format_args!(
+                    "⚠️ Ignoring a malformed hello from {addr}: {e}"
+                ));
+                continue;
+            }
+        };
+        let ControlMessage::Hello {
+            listen_port: port,
+            capacity,
+            desired_id,
+            advertise,
+        } = message
+        else {
+            continue;
+        };
+
+        socket.send_to(&ControlMessage::Registered.encode()?, addr)?;
+        addresses.push(addr);
+        capacities.push(capacity);
+        desired.push(desired_id);
+
+        let addr = listener_address(addr, port, advertise);
+        registered.push(addr);
+        if format == LogFormat::Human {
+            println!(
+                "👋 A new client has arrived, he is listening on: {:?}",
+                addr
+            );
+        }
+    }
+
+    let vertices = if keep_vertex_ids {
+        vertex_ids(graph, expected)
+    } else {
+        (1..=expected).map(NodeId).collect()
+    };
+    if addresses.len() < expected {
+        // The vertices registered nodes asked for are taken, whoever would have got the others
+        let taken = claim_desired_ids(&desired, vertices.clone());
+        return Ok(SetupSummary {
+            nodes: addresses.len(),
+            connections: 0,
+            missing: vertices
+                .into_iter()
+                .filter(|v| !taken.contains(v))
+                .collect(),
+            members: Vec::new(),
+        });
+    }
+
+    let ids: Vec<NodeId> = if balance {
+        assign_ids(graph, vertices, &capacities)
+    } else {
+        vertices
+    };
+    let ids = claim_desired_ids(&desired, ids);
+    for ((addr, desired), id) in addresses.iter().zip(desired.iter()).zip(ids.iter()) {
+        match desired {
+            Some(desired) if desired != id => format.say(format_args!(
+                "⚠️ The node at {addr} asked for {desired:?}, which is taken or out of the graph, it is given {id:?}"
+            )),
+            _ => {}
+        }
+    }
+    let listeners: HashMap<NodeId, SocketAddr> = ids.iter().cloned().zip(registered).collect();
+    let mut members = ids.clone();
+    members.sort();
+
+    // Every node is checked to be reachable before any of them is configured
+    let outgoing = ids
+        .iter()
+        .map(|id| plan.outgoing_addresses(id, &listeners))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut summary = SetupSummary {
+        nodes: addresses.len(),
+        connections: outgoing.iter().map(Vec::len).sum(),
+        missing: Vec::new(),
+        members: Vec::with_capacity(addresses.len()),
+    };
+    let mut datagrams = Vec::with_capacity(addresses.len());
+    let mut events = Vec::with_capacity(addresses.len());
+    for ((addr, id), outgoing_addresses) in addresses.iter().zip(ids).zip(outgoing) {
+        let incoming_connections = plan.incoming_count(&id);
+        datagrams.push((
+            *addr,
+            configuration(
+                graph,
+                &id,
+                &members,
+                incoming_connections,
+                &outgoing_addresses,
+            )?,
+        ));
+        events.push(SetupEvent::NodeReady {
+            id: id.0,
+            source: *addr,
+            listener: listeners[&id],
+            incoming: incoming_connections,
+            outgoing: outgoing_addresses.len(),
+        });
+        summary.members.push(Member {
+            listener: listeners[&id],
+            source: *addr,
+            id,
+        });
+    }
+    summary.members.sort_by_key(|m| m.id.clone());
+
+    send_concurrently(socket, &datagrams)?;
+    for event in events.iter() {
+        event.report(format)?;
+    }
+
+    Ok(summary)
+}
+
+/// Encodes the datagrams configuring the node, which accepts `incoming` connections and
+/// connects to the listeners at `outgoing`.
+fn configuration(
+    graph: &Graph,
+    id: &NodeId,
+    members: &[NodeId],
+    incoming: usize,
+    outgoing: &[SocketAddr],
+) -> bincode::Result<Vec<Vec<u8>>> {
+    // First sends each of the program their ids
+    let messages = [
+        ControlMessage::Assign {
+            id: id.clone(),
+            members: members.to_vec(),
+        },
+        // Then we count the number of connections they will receive
+        ControlMessage::IncomingCount(incoming),
+        // Then we send the address of each of the programs they have to connect to
+        ControlMessage::Outgoing(outgoing.iter().copied().map(Into::into).collect()),
+        // Then the voting quorum of the node for quorum-based algorithms
+        ControlMessage::Quorum(grid_quorum(members, id)),
+        // Then the role of the node when the graph tags it with one
+        ControlMessage::Role(graph.roles.get(id).cloned()),
+        // Then the label of its links the graph labels
+        ControlMessage::Labels(graph.neighbour_labels(id)),
+        // Then where the node stands in the directed topology
+        ControlMessage::Placement(graph.placement(id)),
+        // Finally how many neighbours it must end up with, whatever got lost on the way
+        ControlMessage::NeighbourCount(graph.degree(id)),
+    ];
+    messages.iter().map(ControlMessage::encode).collect()
+}
+
+/// Keeps admitting the nodes saying hello once the system is set up, until none did for
+/// `idle`.
+///
+/// A node joining is given the identifier it asked for, or the one following the highest
+/// member when it asked for none. It becomes a neighbour of every member, as algorithms
+/// asking every other node for the critical section require: it is configured to accept a
+/// connection from each of them, and each of them is told to connect to it with
+/// [`ControlMessage::Join`]. The graph and the summary are updated accordingly.
+///
+/// A node asking for the identifier of a member is that member restarting. It is given its
+/// vertex back, and configured to accept a connection from each of its neighbours, which are
+/// told where it now listens the same way.
+pub fn admit(
+    socket: &UdpSocket,
+    graph: &mut Graph,
+    summary: &mut SetupSummary,
+    idle: Duration,
+    format: LogFormat,
+) -> Result<()> {
+    socket.set_read_timeout(Some(idle))?;
+    loop {
+        let mut buf = [0; 1024];
+        let (received, addr) = match socket.recv_from(&mut buf) {
+            Ok(received) => received,
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => break,
+            Err(e) => return Err(e.into()),
+        };
+        let message = match ControlMessage::decode(&buf[..received]) {
+            Ok(message) => message,
+            Err(e) => {
+                format.say(format_args!(
+                    "⚠️ Ignoring a malformed hello from {addr}: {e}"
+                ));
+                continue;
+            }
+        };
+        let ControlMessage::Hello {
+            listen_port: port,
+            desired_id,
+            advertise,
+            ..
+        } = message
+        else {
+            continue;
+        };
+        socket.send_to(&ControlMessage::Registered.encode()?, addr)?;
+
+        let listener = listener_address(addr, port, advertise);
+        let (id, neighbours) = match summary
+            .members
+            .iter_mut()
+            .find(|m| desired_id.as_ref() == Some(&m.id))
+        {
+            Some(member) => {
+                format.say(format_args!(
+                    "🔁 Node #{} restarted, listening on {listener}",
+                    member.id.0
+                ));
+                member.source = addr;
+                member.listener = listener;
+                let id = member.id.clone();
+                let adjacent = graph.adjacency().remove(&id).unwrap_or_default();
+                let neighbours: Vec<Member> = summary
+                    .members
+                    .iter()
+                    .filter(|m| adjacent.contains(&m.id))
+                    .cloned()
+                    .collect();
+                (id, neighbours)
+            }
+            None => {
+                let neighbours: Vec<Member> = summary.members.clone();
+                let id = desired_id.unwrap_or_else(|| {
+                    NodeId(neighbours.iter().map(|m| m.id.0).max().unwrap_or(0) + 1)
+                });
+                graph.vertices.insert(id.clone());
+                for neighbour in neighbours.iter() {
+                    // Members dial the joining node, the only one whose listener is waiting
+                    graph
+                        .edges
+                        .insert(Connection(neighbour.id.clone(), id.clone()));
+                }
+                summary.members.push(Member {
+                    id: id.clone(),
+                    source: addr,
+                    listener,
+                });
+                summary.members.sort_by_key(|m| m.id.clone());
+                summary.nodes += 1;
+                summary.connections += neighbours.len();
+                (id, neighbours)
+            }
+        };
+        let members: Vec<NodeId> = summary.members.iter().map(|m| m.id.clone()).collect();
+
+        for datagram in configuration(graph, &id, &members, neighbours.len(), &[])? {
+            socket.send_to(&datagram, addr)?;
+        }
+        let join = ControlMessage::Join {
+            id: id.clone(),
+            address: listener.into(),
+        }
+        .encode()?;
+        for neighbour in neighbours.iter() {
+            socket.send_to(&join, neighbour.source)?;
+        }
+        SetupEvent::NodeReady {
+            id: id.0,
+            source: addr,
+            listener,
+            incoming: neighbours.len(),
+            outgoing: 0,
+        }
+        .report(format)?;
+    }
+    Ok(())
+}
+
+/// Largest number of threads sending their configuration to the nodes.
+const SETUP_THREADS: usize = 8;
+
+/// Sends the datagrams of each node, the nodes being spread over up to [`SETUP_THREADS`]
+/// threads.
+///
+/// The datagrams of a node are all sent by the same thread, in order. Each of them being
+/// sent with a single call, threads sharing the socket never interleave their datagrams.
+fn send_concurrently(socket: &UdpSocket, nodes: &[(SocketAddr, Vec<Vec<u8>>)]) -> io::Result<()> {
+    if nodes.is_empty() {
+        return Ok(());
+    }
+    let chunk = nodes.len().div_ceil(SETUP_THREADS);
+    thread::scope(|scope| {
+        let workers: Vec<_> = nodes
+            .chunks(chunk)
+            .map(|nodes| {
+                scope.spawn(move || {
+                    for (addr, datagrams) in nodes {
+                        for datagram in datagrams {
+                            socket.send_to(datagram, addr)?;
+                        }
+                    }
+                    Ok(())
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .try_for_each(|worker| worker.join().unwrap())
+    })
+}
+
+/// Gives one of the `vertices` to each registered node, the vertices with the highest degree
+/// going to the nodes that advertised the highest capacity.
+///
+/// `capacities` holds the capacity of each node in registration order, and the identifier of
+/// each of them is returned in the same order. Ties are broken by registration order and
+/// identifier, so that the assignment is deterministic.
+///
+/// # Examples
+///
+/// ```
+/// # use renraku_coordinator::{graph::Graph, setup::assign_ids};
+/// # use renraku_shared::NodeId;
+///
+/// // A star centered on 3
+/// let graph: Graph = "p edge 4 3\ne 3 1\ne 3 2\ne 3 4\n".parse().unwrap();
+/// let vertices = (1..=4).map(NodeId).collect();
+/// let ids = assign_ids(&graph, vertices, &[1, 1, 8, 2]);
+///
+/// assert_eq!(ids[2], NodeId(3));
+/// assert_eq!(ids, vec![NodeId(2), NodeId(4), NodeId(3), NodeId(1)]);
+/// ```
+pub fn assign_ids(graph: &Graph, mut vertices: Vec<NodeId>, capacities: &[usize]) -> Vec<NodeId> {
+    vertices.sort_by_key(|v| std::cmp::Reverse(graph.degree(v)));
+    let mut nodes: Vec<usize> = (0..capacities.len()).collect();
+    nodes.sort_by_key(|n| std::cmp::Reverse(capacities[*n]));
+
+    let mut ids: Vec<(usize, NodeId)> = nodes.into_iter().zip(vertices).collect();
+    ids.sort_by_key(|(node, _)| *node);
+    ids.into_iter().map(|(_, vertex)| vertex).collect()
+}
+
+/// Gives each node the identifier it asked for, when it is one of `ids` and no node that
+/// registered before asked for it.
+///
+/// `desired` holds the identifier each node asked for in registration order, and `ids` the
+/// identifiers they would be given otherwise. The nodes that did not get theirs take the
+/// identifiers left, in the order of `ids`.
+///
+/// # Examples
+///
+/// ```
+/// # use renraku_coordinator::setup::claim_desired_ids;
+/// # use renraku_shared::NodeId;
+///
+/// let ids = (1..=4).map(NodeId).collect();
+/// let desired = [None, Some(NodeId(1)), Some(NodeId(1)), Some(NodeId(9))];
+///
+/// assert_eq!(
+///     claim_desired_ids(&desired, ids),
+///     vec![NodeId(2), NodeId(1), NodeId(3), NodeId(4)]
+/// );
+/// ```
+pub fn claim_desired_ids(desired: &[Option<NodeId>], ids: Vec<NodeId>) -> Vec<NodeId> {
+    let mut left: Vec<Option<NodeId>> = ids.iter().cloned().map(Some).collect();
+    let mut claimed: Vec<Option<NodeId>> = desired
+        .iter()
+        .map(|desired| {
+            let desired = desired.as_ref()?;
+            left.iter_mut()
+                .find(|id| id.as_ref() == Some(desired))?
+                .take()
+        })
+        .collect();
+    let mut left = left.into_iter().flatten();
+    for id in claimed.iter_mut().filter(|id| id.is_none()) {
+        *id = left.next();
+    }
+    claimed.into_iter().flatten().collect()
+}
+
+/// Returns the identifiers of `count` nodes: the vertices of the graph as they are numbered in
+/// its file, then the ones following the highest vertex for the nodes left without a vertex.
+fn vertex_ids(graph: &Graph, count: usize) -> Vec<NodeId> {
+    let mut ids = graph.sorted_vertices();
+    let next = ids.last().map_or(1, |id| id.0 + 1);
+    ids.extend((next..).map(NodeId).take(count.saturating_sub(ids.len())));
+    ids.truncate(count);
+    ids
+}
+
+/// Address the neighbours of a node connect to: the one it advertises in its hello if any,
+/// otherwise the port it listens on at the address the hello came from.
+fn listener_address(source: SocketAddr, port: u16, advertise: Option<WireAddr>) -> SocketAddr {
+    match advertise {
+        Some(advertised) => advertised.into(),
+        // Nodes registering over IPv4 on a dual-stack socket are heard from mapped addresses,
+        // which their IPv4 neighbours could not connect to
+        None => SocketAddr::new(source.ip().to_canonical(), port),
+    }
+}
+
+/// Outcome of the setup, printed by the coordinator before exiting.
+///
+/// # Examples
+///
+/// ```
+/// # use renraku_coordinator::setup::SetupSummary;
+/// # use renraku_shared::NodeId;
+///
+/// let complete = SetupSummary { nodes: 3, connections: 2, missing: vec![], members: vec![] };
+/// let partial = SetupSummary { nodes: 3, connections: 0, missing: vec![NodeId(3)], members: vec![] };
+///
+/// assert!(complete.is_success());
+/// assert!(!partial.is_success());
+/// assert_eq!(partial.to_string(), "3 nodes, 0 directed connections, missing: [3]");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct SetupSummary {
+    pub nodes: usize,
+    pub connections: usize,
+    /// Vertices of the graph whose node has not registered in time.
+    pub missing: Vec<NodeId>,
+    /// Each configured node, sorted by identifier.
+    pub members: Vec<Member>,
+}
+
+/// A node the coordinator configured.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Member {
+    pub id: NodeId,
+    /// Address the node registered from, on which it is sent control messages.
+    pub source: SocketAddr,
+    /// Address the node listens on for its neighbours.
+    pub listener: SocketAddr,
+}
+
+impl SetupSummary {
+    /// Returns whether every expected node has registered and been configured.
+    pub fn is_success(&self) -> bool {
+        self.missing.is_empty()
+    }
+}
+
+impl fmt::Display for SetupSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} nodes, {} directed connections, missing: {:?}",
+            self.nodes,
+            self.connections,
+            self.missing.iter().map(|n| n.0).collect::<Vec<_>>()
+        )
+    }
+}
@@ -0,0 +1,212 @@
+use std::{collections::BTreeMap, io::ErrorKind, net::UdpSocket, time::Duration};
+
+use color_eyre::Result;
+use renraku_shared::{clock::VectorClock, control::ControlMessage, NodeId};
+
+use crate::setup::LogFormat;
+
+/// Tracks the critical sections of each resource from what the nodes tell the coordinator, as
+/// a runtime oracle of mutual exclusion.
+///
+/// Notifications travel over UDP: they may arrive out of order, twice, or not at all. They are
+/// thus only gathered by [`Witness::observe`], then ordered by the vector clock they are
+/// stamped with in [`Witness::close`]. Two critical sections overlap unless the exit of one
+/// causally precedes the entry of the other, whatever the order the coordinator heard of them.
+///
+/// # Examples
+///
+/// ```
+/// # use renraku_coordinator::witness::{Violation, Witness};
+/// # use renraku_shared::{clock::VectorClock, control::ControlMessage, NodeId};
+///
+/// let entered = |id, clock: [usize; 3]| ControlMessage::Entered {
+///     id: NodeId(id),
+///     resource: "default".into(),
+///     clock: VectorClock(clock.to_vec()),
+/// };
+/// let left = |id, clock: [usize; 3]| ControlMessage::Left {
+///     id: NodeId(id),
+///     resource: "default".into(),
+///     clock: VectorClock(clock.to_vec()),
+/// };
+/// let mut witness = Witness::default();
+///
+/// // Node 2 enters once it got the permission node 1 sent after leaving, whose exit is heard of
+/// // first and the entry of node 2 twice
+/// witness.observe(left(1, [2, 0, 0]));
+/// witness.observe(entered(1, [1, 0, 0]));
+/// witness.observe(entered(2, [3, 2, 0]));
+/// witness.observe(entered(2, [3, 2, 0]));
+/// // Node 3 heard of node 1 leaving, not of node 2
+/// witness.observe(entered(3, [3, 0, 1]));
+/// witness.close();
+///
+/// assert_eq!(witness.entries, 3);
+/// assert_eq!(
+///     witness.violations,
+///     vec![Violation { resource: "default".into(), inside: vec![NodeId(2), NodeId(3)] }]
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Witness {
+    entered: BTreeMap<String, Vec<(NodeId, VectorClock)>>,
+    left: BTreeMap<String, Vec<(NodeId, VectorClock)>>,
+    /// Number of times a node entered a critical section.
+    pub entries: usize,
+    /// Each pair of nodes whose critical sections of the same resource overlapped, once
+    /// [closed](Witness::close).
+    pub violations: Vec<Violation>,
+}
+
+/// Several nodes within the same critical section at once.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    pub resource: String,
+    /// Nodes within the critical section, sorted by identifier.
+    pub inside: Vec<NodeId>,
+}
+
+/// A stay of a node within a critical section, between the clocks it entered and left at,
+/// if the coordinator heard of it leaving.
+struct Section<'a> {
+    id: &'a NodeId,
+    entered: &'a VectorClock,
+    left: Option<&'a VectorClock>,
+}
+
+impl Section<'_> {
+    /// Returns whether the node left this section before the other one was entered.
+    ///
+    /// A node whose exit got lost is taken to have left before any section entered causally
+    /// after its own entry, which it otherwise could not have allowed.
+    fn precedes(&self, other: &Section) -> bool {
+        self.left
+            .unwrap_or(self.entered)
+            .happens_before(other.entered)
+    }
+}
+
+impl Witness {
+    /// Takes what a node told into account.
+    ///
+    /// Messages other than [`ControlMessage::Entered`] and [`ControlMessage::Left`] are
+    /// ignored, as are the notifications already heard of.
+    pub fn observe(&mut self, message: ControlMessage) {
+        let (notifications, id, clock) = match message {
+            ControlMessage::Entered {
+                id,
+                resource,
+                clock,
+            } => (self.entered.entry(resource).or_default(), id, clock),
+            ControlMessage::Left {
+                id,
+                resource,
+                clock,
+            } => (self.left.entry(resource).or_default(), id, clock),
+            _ => return,
+        };
+        let notification = (id, clock);
+        if !notifications.contains(&notification) {
+            notifications.push(notification);
+        }
+        self.entries = self.entered.values().map(Vec::len).sum();
+    }
+
+    /// Pairs the entries and exits heard of so far, then records each pair of critical
+    /// sections that overlapped as a violation.
+    pub fn close(&mut self) {
+        self.violations = self
+            .entered
+            .keys()
+            .flat_map(|resource| {
+                let sections = self.sections(resource);
+                sections
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(i, first)| {
+                        sections[i + 1..].iter().map(move |second| (first, second))
+                    })
+                    .filter(|(first, second)| {
+                        first.id != second.id && !first.precedes(second) && !second.precedes(first)
+                    })
+                    .map(|(first, second)| {
+                        let mut inside = vec![first.id.clone(), second.id.clone()];
+                        inside.sort_by_key(|id| id.0);
+                        Violation {
+                            resource: resource.clone(),
+                            inside,
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+    }
+
+    /// Critical sections of the resource, each entry being paired with the first exit of the
+    /// same node that follows it, unless the node entered again in between.
+    fn sections(&self, resource: &str) -> Vec<Section<'_>> {
+        let exits = self
+            .left
+            .get(resource)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let entries = self
+            .entered
+            .get(resource)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let mut sections: Vec<Section> = entries
+            .iter()
+            .map(|(id, entered)| {
+                let next = entries
+                    .iter()
+                    .filter(|(other, clock)| other == id && entered.happens_before(clock))
+                    .map(|(_, clock)| clock.get(id))
+                    .min();
+                let left = exits
+                    .iter()
+                    .filter(|(other, clock)| other == id && entered.happens_before(clock))
+                    .filter(|(_, clock)| next.is_none_or(|next| clock.get(id) < next))
+                    .min_by_key(|(_, clock)| clock.get(id))
+                    .map(|(_, clock)| clock);
+                Section { id, entered, left }
+            })
+            .collect();
+        sections.sort_by_key(|section| (section.id.0, section.entered.get(section.id)));
+        sections
+    }
+}
+
+/// Watches the critical sections the nodes tell the socket about, until none of them has
+/// told anything for `idle`, printing what it witnessed in the given format.
+pub fn watch(socket: &UdpSocket, idle: Duration, format: LogFormat) -> Result<Witness> {
+    let mut witness = Witness::default();
+    socket.set_read_timeout(Some(idle))?;
+    loop {
+        let mut buf = [0; 1024];
+        let (received, addr) = match socket.recv_from(&mut buf) {
+            Ok(received) => received,
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => break,
+            Err(e) => return Err(e.into()),
+        };
+        match ControlMessage::decode(&buf[..received]) {
+            Ok(message) => witness.observe(message),
+            Err(e) => format.say(format_args!(
+                "⚠️ Ignoring a malformed notification from {addr}: {e}"
+            )),
+        }
+    }
+    socket.set_read_timeout(None)?;
+    witness.close();
+    for Violation { resource, inside } in &witness.violations {
+        format.say(format_args!(
+            "❗ Mutual exclusion violated on {resource}: {inside:?} were inside at once"
+        ));
+    }
+    format.say(format_args!(
+        "👁️ Witnessed {} entries, {} violations",
+        witness.entries,
+        witness.violations.len()
+    ));
+    Ok(witness)
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use std::{
+    net::{IpAddr, SocketAddr, UdpSocket},
+    thread,
+};
+
+use common::arguments;
+use renraku_coordinator::{
+    setup::{serve, LogFormat},
+    Graph,
+};
+use renraku_node::configure;
+use renraku_shared::NodeId;
+
+#[test]
+fn advertised_address_is_recorded_as_the_listener() {
+    let graph: Graph = "p edge 2 1\ne 1 2\n".parse().unwrap();
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let controller = socket.local_addr().unwrap().to_string();
+    // Node 1 only dials node 2, so that nobody connects to this address, mapped to nothing
+    let advertised: SocketAddr = "203.0.113.7:30443".parse().unwrap();
+
+    let coordinator = thread::spawn(move || {
+        serve(&socket, &graph, 2, None, false, false, LogFormat::Human).unwrap()
+    });
+    let nodes: Vec<_> = [(1, Some(advertised)), (2, None)]
+        .into_iter()
+        .map(|(desired, advertise_addr)| {
+            let mut args = arguments(&controller);
+            args.desired_id = Some(desired);
+            args.advertise_addr = advertise_addr;
+            thread::spawn(move || configure(args).unwrap())
+        })
+        .collect();
+    for node in nodes {
+        node.join().unwrap();
+    }
+
+    let summary = coordinator.join().unwrap();
+    assert!(summary.is_success());
+    let listener = |id: usize| {
+        let member = summary.members.iter().find(|m| m.id == NodeId(id));
+        member.unwrap().listener
+    };
+    assert_eq!(listener(1), advertised);
+    // Nodes advertising nothing are still recorded at the port they listen on
+    assert_eq!(listener(2).ip(), IpAddr::from([127, 0, 0, 1]));
+    assert_ne!(listener(2), advertised);
+}
@@ -0,0 +1,136 @@
+#![allow(dead_code)]
+
+use std::{
+    io::{BufRead, BufReader},
+    net::{SocketAddr, UdpSocket},
+    process::{Child, ChildStdout, Command, Stdio},
+    thread,
+};
+
+use renraku_coordinator::{
+    setup::{serve, LogFormat},
+    Graph,
+};
+use renraku_node::{configure, NodeArguments, NodeConfig};
+use renraku_shared::control::ControlMessage;
+
+/// Runs the coordinator on an ephemeral port and configures a node for each vertex of the
+/// graph against it, everything within the current process.
+///
+/// The configurations are returned sorted by identifier.
+pub fn launch(graph: &str) -> Vec<NodeConfig> {
+    let mut configs: Vec<NodeConfig> = launch_with(graph, |config| config);
+    configs.sort_by_key(|c| c.id.0);
+    configs
+}
+
+/// Same as [`launch`], each node running `then` as soon as it is configured, without waiting
+/// for the others.
+pub fn launch_with<T: Send + 'static>(graph: &str, then: fn(NodeConfig) -> T) -> Vec<T> {
+    let graph: Graph = graph.parse().unwrap();
+    let expected = graph.vertices.len();
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let controller = socket.local_addr().unwrap().to_string();
+
+    let coordinator = thread::spawn(move || {
+        serve(
+            &socket,
+            &graph,
+            expected,
+            None,
+            false,
+            false,
+            LogFormat::Human,
+        )
+        .unwrap()
+    });
+    let nodes: Vec<_> = (0..expected)
+        .map(|_| {
+            let args = arguments(&controller);
+            thread::spawn(move || then(configure(args).unwrap()))
+        })
+        .collect();
+
+    let results = nodes.into_iter().map(|n| n.join().unwrap()).collect();
+    assert!(coordinator.join().unwrap().is_success());
+    results
+}
+
+/// Arguments of a node registering to the given controller, with default settings.
+pub fn arguments(controller: &str) -> NodeArguments {
+    NodeArguments {
+        controller: controller.to_string(),
+        ..NodeArguments::default()
+    }
+}
+
+/// Runs the coordinator binary on a path of 3 nodes, `registering` of them sending their
+/// hello, returning whether it succeeded along with what it printed.
+pub fn run(registering: u16, args: &[&str]) -> (bool, Vec<String>) {
+    let graph = std::env::temp_dir().join(format!(
+        "renraku-run-{}-{}-{}.dimacs",
+        std::process::id(),
+        registering,
+        args.join("")
+    ));
+    std::fs::write(&graph, "p edge 3 2\ne 1 2\ne 2 3\n").unwrap();
+
+    let graph_args = ["--graph", graph.to_str().unwrap()];
+    let (mut coordinator, address, mut lines, stdout) = spawn(
+        &[
+            &graph_args,
+            ["--registration-timeout", "1"].as_slice(),
+            args,
+        ]
+        .concat(),
+    );
+
+    let nodes: Vec<UdpSocket> = (0..registering)
+        .map(|_| UdpSocket::bind("127.0.0.1:0").unwrap())
+        .collect();
+    for (port, node) in nodes.iter().enumerate() {
+        let hello = ControlMessage::Hello {
+            listen_port: 4000 + port as u16,
+            capacity: 1,
+            desired_id: None,
+            advertise: None,
+        };
+        node.send_to(&hello.encode().unwrap(), address).unwrap();
+    }
+
+    lines.extend(stdout.lines().map(Result::unwrap));
+    let status = coordinator.wait().unwrap();
+    std::fs::remove_file(&graph).unwrap();
+    (status.success(), lines)
+}
+
+/// Starts the coordinator binary on an ephemeral port, returning it once it listens, along
+/// with its address, what it printed until then and the rest of its output.
+pub fn spawn(args: &[&str]) -> (Child, SocketAddr, Vec<String>, BufReader<ChildStdout>) {
+    let mut coordinator = Command::new(env!("CARGO_BIN_EXE_renraku_coordinator"))
+        .args(["--address", "127.0.0.1:0"])
+        .args(args)
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    // Nodes only say hello once the coordinator is listening
+    let mut stdout = BufReader::new(coordinator.stdout.take().unwrap());
+    let mut lines = Vec::new();
+    let address: SocketAddr = loop {
+        let mut line = String::new();
+        assert_ne!(stdout.read_line(&mut line).unwrap(), 0);
+        let address = match serde_json::from_str::<serde_json::Value>(line.trim()) {
+            Ok(event) => event["address"].as_str().map(|a| a.parse().unwrap()),
+            Err(_) => line
+                .trim()
+                .split_once(" on ")
+                .map(|(_, a)| a.parse().unwrap()),
+        };
+        lines.push(line.trim().to_string());
+        if let Some(address) = address {
+            break address;
+        }
+    };
+    (coordinator, address, lines, stdout)
+}
@@ -0,0 +1,31 @@
+mod common;
+
+use std::{collections::HashSet, io::BufRead, thread};
+
+use common::{arguments, spawn};
+use renraku_node::configure;
+
+#[test]
+fn complete_flag_connects_every_node_to_every_other() {
+    let (mut coordinator, address, lines, stdout) = spawn(&["--complete", "4"]);
+    assert!(lines[0].contains("vertices: 4, edges: 6"), "{lines:?}");
+
+    let controller = address.to_string();
+    let nodes: Vec<_> = (0..4)
+        .map(|_| {
+            let args = arguments(&controller);
+            thread::spawn(move || configure(args).unwrap())
+        })
+        .collect();
+    for node in nodes {
+        let config = node.join().unwrap();
+        let neighbours: HashSet<usize> = config.neighbours.keys().map(|n| n.0).collect();
+        let expected: HashSet<usize> = (1..=4).filter(|n| *n != config.id.0).collect();
+        assert_eq!(neighbours, expected, "neighbours of {:?}", config.id);
+    }
+
+    let lines: Vec<String> = stdout.lines().map(Result::unwrap).collect();
+    assert!(coordinator.wait().unwrap().success(), "{lines:?}");
+    let summary = "4 nodes, 6 directed connections";
+    assert!(lines.iter().any(|l| l.contains(summary)), "{lines:?}");
+}
@@ -0,0 +1,58 @@
+use std::{
+    net::{SocketAddr, UdpSocket},
+    thread,
+};
+
+use renraku_coordinator::{
+    setup::{bind, serve, LogFormat},
+    Graph,
+};
+use renraku_shared::control::ControlMessage;
+
+#[test]
+fn ipv4_and_ipv6_hellos_reach_a_wildcard_coordinator() {
+    // Platforms without IPv6 cannot run this scenario
+    let Ok(socket) = bind("[::]:0", None) else {
+        return;
+    };
+    let Ok(v6) = UdpSocket::bind("[::1]:0") else {
+        return;
+    };
+    let v4 = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let port = socket.local_addr().unwrap().port();
+
+    let graph: Graph = "p edge 2 1\ne 1 2\n".parse().unwrap();
+    let coordinator = thread::spawn(move || {
+        serve(&socket, &graph, 2, None, false, false, LogFormat::Human).unwrap()
+    });
+    for (client, controller, listen_port) in [(&v4, "127.0.0.1", 4001), (&v6, "::1", 4002)] {
+        let hello = ControlMessage::Hello {
+            listen_port,
+            capacity: 1,
+            desired_id: None,
+            advertise: None,
+        };
+        let controller = SocketAddr::new(controller.parse().unwrap(), port);
+        client
+            .send_to(&hello.encode().unwrap(), controller)
+            .unwrap();
+        // Waits for the acknowledgement, for the ids to follow the order of the hellos
+        let mut buf = [0; 1024];
+        let received = client.recv(&mut buf).unwrap();
+        assert_eq!(
+            ControlMessage::decode(&buf[..received]).unwrap(),
+            ControlMessage::Registered
+        );
+    }
+
+    let summary = coordinator.join().unwrap();
+    assert!(summary.is_success());
+    let listeners: Vec<SocketAddr> = summary.members.iter().map(|m| m.listener).collect();
+    assert_eq!(
+        listeners,
+        vec![
+            "127.0.0.1:4001".parse().unwrap(),
+            "[::1]:4002".parse().unwrap()
+        ]
+    );
+}
@@ -0,0 +1,13 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn succeeds_when_every_node_registers() {
+    assert!(run(3, &[]).0);
+}
+
+#[test]
+fn fails_when_a_node_does_not_register() {
+    assert!(!run(2, &[]).0);
+}
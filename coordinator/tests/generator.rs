@@ -0,0 +1,102 @@
+use std::collections::BTreeMap;
+
+use renraku_coordinator::Graph;
+use renraku_shared::{Connection, NodeId};
+
+#[test]
+fn complete_graph_joins_every_pair() {
+    for n in [0, 1, 2, 5, 10] {
+        let graph = Graph::complete(n);
+        assert_eq!(graph.vertices.len(), n);
+        assert_eq!(graph.edges.len(), n * n.saturating_sub(1) / 2);
+    }
+}
+
+#[test]
+fn ring_has_as_many_edges_as_vertices() {
+    assert_eq!(Graph::ring(1).edges.len(), 0);
+    assert_eq!(Graph::ring(2).edges.len(), 1);
+    for n in [3, 4, 17] {
+        let graph = Graph::ring(n);
+        assert_eq!(graph.edges.len(), n);
+        assert!(graph.has_cycle());
+        assert_eq!(graph.connected_components(), 1);
+    }
+}
+
+#[test]
+fn star_is_a_tree_around_its_center() {
+    let graph = Graph::star(6);
+    assert_eq!(graph.edges.len(), 5);
+    assert!(graph.is_tree());
+    assert_eq!(graph.stats().max_degree, 5);
+}
+
+#[test]
+fn grid_joins_horizontal_and_vertical_neighbours() {
+    let graph = Graph::grid(4, 3);
+    assert_eq!(graph.vertices.len(), 12);
+    assert_eq!(graph.edges.len(), 3 * 3 + 4 * 2);
+    assert_eq!(Graph::grid(5, 1).edges.len(), 4);
+    assert_eq!(Graph::grid(0, 3).edges.len(), 0);
+}
+
+#[test]
+fn erdos_renyi_graph_depends_on_the_seed_only() {
+    assert_eq!(Graph::random_erdos_renyi(20, 0.0, 7).edges.len(), 0);
+    assert_eq!(Graph::random_erdos_renyi(20, 1.0, 7).edges.len(), 190);
+
+    let graph = Graph::random_erdos_renyi(20, 0.5, 7);
+    assert_eq!(graph, Graph::random_erdos_renyi(20, 0.5, 7));
+    assert_eq!(graph.vertices.len(), 20);
+    assert!(!graph.edges.is_empty() && graph.edges.len() < 190);
+}
+
+#[test]
+fn erdos_renyi_probability_is_brought_back_within_bounds() {
+    assert_eq!(Graph::random_erdos_renyi(20, f64::NAN, 7).edges.len(), 0);
+    assert_eq!(Graph::random_erdos_renyi(20, -1.0, 7).edges.len(), 0);
+    assert_eq!(
+        Graph::random_erdos_renyi(20, f64::INFINITY, 7).edges.len(),
+        190
+    );
+}
+
+#[test]
+fn star_has_one_hub_and_leaves_of_degree_one() {
+    for leaves in [1, 2, 9] {
+        let histogram = Graph::star(leaves + 1).degree_histogram();
+        let expected = if leaves == 1 {
+            // Both ends of a single edge have the same degree
+            BTreeMap::from([(1, 2)])
+        } else {
+            BTreeMap::from([(1, leaves), (leaves, 1)])
+        };
+        assert_eq!(histogram, expected, "star of {leaves} leaves");
+    }
+}
+
+#[test]
+fn cycle_search_skips_edges_towards_unlisted_vertices() {
+    let mut graph = Graph::ring(3);
+    graph.vertices.remove(&NodeId(3));
+    // The edges 2-3 and 3-1 are left dangling, so that only 1-2 is left among the vertices
+    assert!(!graph.has_cycle());
+}
+
+#[test]
+fn degree_statistics_count_duplicate_edges_like_the_histogram() {
+    let mut graph = Graph::star(3);
+    // The hub is now joined twice to 2
+    assert!(graph.edges.insert(Connection(NodeId(2), NodeId(1))));
+
+    assert_eq!(graph.degree(&NodeId(1)), 3);
+    assert_eq!(
+        graph.degree_histogram(),
+        BTreeMap::from([(1, 1), (2, 1), (3, 1)])
+    );
+    let stats = graph.stats();
+    assert_eq!(stats.min_degree, 1);
+    assert_eq!(stats.max_degree, 3);
+    assert!((stats.average_degree - 2.0).abs() < f64::EPSILON);
+}
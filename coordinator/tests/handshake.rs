@@ -0,0 +1,35 @@
+mod common;
+
+use common::launch_with;
+use renraku_node::NodeConfig;
+use renraku_shared::{frame::frame, NodeId};
+
+/// Greets every neighbour as soon as the node is configured, then reads their greetings.
+fn greet(config: NodeConfig) -> Vec<(usize, usize, Vec<u8>)> {
+    for neighbour in config.neighbours.values() {
+        let greeting = format!("hello from {}", config.id.0);
+        neighbour
+            .send(&frame(greeting.as_bytes()).unwrap())
+            .unwrap();
+    }
+    config
+        .neighbours
+        .iter()
+        .map(|(NodeId(peer), neighbour)| (config.id.0, peer, neighbour.receive_frame().unwrap()))
+        .collect()
+}
+
+#[test]
+fn first_message_after_setup_is_read_intact() {
+    let received = launch_with("p edge 4 5\ne 1 2\ne 1 3\ne 2 3\ne 2 4\ne 3 4\n", greet);
+
+    let received: Vec<_> = received.into_iter().flatten().collect();
+    assert_eq!(received.len(), 10);
+    for (id, peer, greeting) in received {
+        assert_eq!(
+            String::from_utf8(greeting).unwrap(),
+            format!("hello from {peer}"),
+            "first message received by {id}"
+        );
+    }
+}
@@ -0,0 +1,41 @@
+mod common;
+
+use std::collections::HashMap;
+
+use common::launch;
+use renraku_coordinator::graph::{Graph, GraphParsingError};
+use renraku_shared::{Connection, NodeId};
+
+#[test]
+fn edge_labels_are_relayed_to_both_ends() {
+    let configs = launch("p edge 3 3\ne 1 2 label=fast\ne 3 2 label=slow\ne 1 3\n");
+
+    let labels: Vec<&HashMap<NodeId, String>> = configs.iter().map(|c| &c.labels).collect();
+    assert_eq!(
+        labels,
+        vec![
+            &HashMap::from([(NodeId(2), "fast".to_string())]),
+            &HashMap::from([
+                (NodeId(1), "fast".to_string()),
+                (NodeId(3), "slow".to_string())
+            ]),
+            &HashMap::from([(NodeId(2), "slow".to_string())]),
+        ]
+    );
+}
+
+#[test]
+fn repeated_edge_with_another_label_is_rejected() {
+    let parsed = "p edge 2 2\ne 1 2 label=fast\ne 2 1 label=slow\n".parse::<Graph>();
+    assert!(matches!(
+        parsed,
+        Err(GraphParsingError::ConflictingLabels { first, second, .. })
+            if first == "fast" && second == "slow"
+    ));
+
+    // Repeating the same label, or none, keeps it
+    let graph: Graph = "p edge 2 2\ne 1 2 label=fast\ne 2 1\ne 1 2 label=fast\n"
+        .parse()
+        .unwrap();
+    assert_eq!(graph.labels[&Connection::new(NodeId(1), NodeId(2))], "fast");
+}
@@ -0,0 +1,35 @@
+mod common;
+
+use common::run;
+use serde_json::Value;
+
+#[test]
+fn json_log_reports_each_ready_node() {
+    let (success, lines) = run(3, &["--log-format", "json"]);
+    assert!(success);
+
+    // Anything meant for a human goes to the standard error
+    let events: Vec<Value> = lines
+        .iter()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(events[0]["event"], "listening");
+    assert_eq!(events[0]["expected"], 3);
+    let mut events = events[1..].to_vec();
+    events.sort_by_key(|e| e["id"].as_u64());
+    assert_eq!(events.len(), 3);
+    for (i, event) in events.iter().enumerate() {
+        assert_eq!(event["event"], "node_ready");
+        assert_eq!(event["id"], i + 1);
+        assert_eq!(event["listener"], format!("127.0.0.1:{}", 4000 + i));
+        assert!(event["source"].as_str().unwrap().starts_with("127.0.0.1:"));
+    }
+    // On a path, the middle node accepts a connection and connects to the last one
+    assert_eq!(
+        (
+            events[1]["incoming"].as_u64(),
+            events[1]["outgoing"].as_u64()
+        ),
+        (Some(1), Some(1))
+    );
+}
@@ -0,0 +1,48 @@
+mod common;
+
+use std::{net::UdpSocket, thread};
+
+use common::arguments;
+use renraku_coordinator::{
+    setup::{serve, LogFormat},
+    Graph,
+};
+use renraku_node::configure;
+use renraku_shared::{control::ControlMessage, frame::frame};
+
+#[test]
+fn malformed_hellos_are_skipped() {
+    let graph: Graph = "p edge 2 1\ne 1 2\n".parse().unwrap();
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let controller = socket.local_addr().unwrap();
+    let coordinator = thread::spawn(move || {
+        serve(&socket, &graph, 2, None, false, false, LogFormat::Human).unwrap()
+    });
+
+    // Garbage, then a well-framed payload that is no control message
+    let malformed = [b"\xff\xfe garbage".to_vec(), frame(&[0xff; 8]).unwrap()];
+    assert!(malformed.iter().all(|m| ControlMessage::decode(m).is_err()));
+    let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+    for datagram in malformed.iter() {
+        client.send_to(datagram, controller).unwrap();
+    }
+
+    let nodes: Vec<_> = (0..2)
+        .map(|_| {
+            let args = arguments(&controller.to_string());
+            thread::spawn(move || configure(args).unwrap())
+        })
+        .collect();
+    let mut ids: Vec<usize> = nodes.into_iter().map(|n| n.join().unwrap().id.0).collect();
+    ids.sort();
+
+    let summary = coordinator.join().unwrap();
+    assert!(summary.is_success());
+    assert_eq!(summary.nodes, 2);
+    assert_eq!(ids, vec![1, 2]);
+
+    // The faulty client has not been registered
+    client.set_nonblocking(true).unwrap();
+    let mut buf = [0; 64];
+    assert!(client.recv_from(&mut buf).is_err());
+}
@@ -0,0 +1,52 @@
+use std::collections::HashSet;
+
+use renraku_coordinator::{graph::MergeError, Graph};
+use renraku_shared::{Connection, NodeId};
+
+#[test]
+fn overlapping_graphs_are_unioned() {
+    // A base ring of 4 vertices, layered with chords and a 5th vertex
+    let mut graph: Graph = "p edge 4 4\nn 1 hub\ne 1 2\ne 2 3\ne 3 4\ne 4 1\n"
+        .parse()
+        .unwrap();
+    let extra: Graph = "p edge 5 4\nn 1 hub\nn 5 leaf\ne 1 3\ne 2 4\ne 4 1\ne 4 5\n"
+        .parse()
+        .unwrap();
+    graph.merge(extra).unwrap();
+
+    assert_eq!(graph.vertices, (1..=5).map(NodeId).collect::<HashSet<_>>());
+    let edges: HashSet<(usize, usize)> = graph
+        .edges
+        .iter()
+        .map(|Connection(a, b)| (a.0, b.0))
+        .collect();
+    assert_eq!(
+        edges,
+        HashSet::from([(1, 2), (2, 3), (3, 4), (1, 4), (1, 3), (2, 4), (4, 5)])
+    );
+    assert_eq!(graph.roles[&NodeId(1)], "hub");
+    assert_eq!(graph.roles[&NodeId(5)], "leaf");
+}
+
+#[test]
+fn conflicting_graphs_are_not_merged() {
+    let mut graph: Graph = "p edge 2 1\nn 2 consumer\ne 1 2\n".parse().unwrap();
+    let original = graph.clone();
+
+    let retagged: Graph = "p edge 3 1\nn 2 producer\ne 2 3\n".parse().unwrap();
+    assert!(matches!(
+        graph.merge(retagged),
+        Err(MergeError::ConflictingRoles {
+            vertex: NodeId(2),
+            ..
+        })
+    ));
+    let mut reversed = Graph::default();
+    reversed.vertices.extend([NodeId(1), NodeId(2)]);
+    reversed.edges.insert(Connection(NodeId(2), NodeId(1)));
+    assert!(matches!(
+        graph.merge(reversed),
+        Err(MergeError::OppositeEdges(NodeId(2), NodeId(1)))
+    ));
+    assert_eq!(graph, original);
+}
@@ -0,0 +1,207 @@
+use std::io::{self, BufReader, Read, Write};
+
+use flate2::{write::GzEncoder, Compression};
+use renraku_coordinator::graph::{
+    Graph, GraphBuildError, GraphParsingError, MatrixError, MatrixKind, ParseLimits,
+};
+use renraku_shared::{Connection, NodeId, Placement};
+
+#[test]
+fn non_utf8_byte_is_reported_with_its_position() {
+    let mut file = b"p edge 2 1\nc caf".to_vec();
+    file.push(0xE9);
+    file.extend(b"\ne 1 2\n");
+
+    assert!(matches!(
+        Graph::from_reader(file.as_slice(), ParseLimits::default()),
+        Err(GraphParsingError::NonUtf8 { line: 2, column: 5 })
+    ));
+}
+
+#[test]
+fn overly_long_line_is_rejected_before_being_read_whole() {
+    let limits = ParseLimits {
+        max_line_bytes: Some(64),
+        max_lines: None,
+        max_vertex_id: None,
+    };
+    // An endless line, as a corrupted or malicious file could hold
+    let file = BufReader::new(b"p edge 2 1\n".chain(io::repeat(b'e')));
+
+    assert!(matches!(
+        Graph::from_reader(file, limits),
+        Err(GraphParsingError::LineTooLong { line: 2, max: 64 })
+    ));
+}
+
+#[test]
+fn limits_apply_to_graph_files() {
+    let path = std::env::temp_dir().join(format!("renraku-limits-{}.csv", std::process::id()));
+    let mut file = std::fs::File::create(&path).unwrap();
+    for v in 1..=10 {
+        writeln!(file, "{},{}", v, v + 1).unwrap();
+    }
+
+    let limits = ParseLimits {
+        max_line_bytes: None,
+        max_lines: Some(5),
+        max_vertex_id: None,
+    };
+    assert!(Graph::open_with_limits(&path, limits).is_err());
+    assert_eq!(Graph::open(&path).unwrap().graph.edges.len(), 10);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn gzipped_graph_files_are_decompressed() {
+    let dir = std::env::temp_dir();
+    let gzip = |content: &[u8]| {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(content).unwrap();
+        encoder.finish().unwrap()
+    };
+    let files = [
+        ("dimacs.gz", gzip(b"p edge 3 2\ne 1 2\ne 2 3\n")),
+        ("edges.csv.gz", gzip(b"1,2\n2,3\n")),
+        // Compressed files are recognized by their header as well
+        ("header.col", gzip(b"p edge 3 2\ne 1 2\ne 2 3\n")),
+        ("plain.col", b"p edge 3 2\ne 1 2\ne 2 3\n".to_vec()),
+    ];
+
+    for (name, content) in files {
+        let path = dir.join(format!("renraku-{}-{name}", std::process::id()));
+        std::fs::write(&path, content).unwrap();
+        let graph = Graph::open(&path).unwrap().graph;
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            graph.sorted_edges(),
+            vec![
+                Connection(NodeId(1), NodeId(2)),
+                Connection(NodeId(2), NodeId(3))
+            ],
+            "edges of {name}"
+        );
+    }
+}
+
+#[test]
+fn problem_line_after_the_edges_is_accepted_leniently() {
+    let file = "c edges first\ne 1 2\ne 2 3 label=slow\nn 3 sink\np edge 4 3\ne 3 4\n";
+
+    assert!(matches!(
+        Graph::from_reader(file.as_bytes(), ParseLimits::default()),
+        Err(GraphParsingError::Uninitialized)
+    ));
+    let graph = Graph::lenient_from_reader(file.as_bytes(), ParseLimits::default())
+        .unwrap()
+        .graph;
+    let expected: Graph = "p edge 4 3\ne 1 2\ne 2 3 label=slow\nn 3 sink\ne 3 4\n"
+        .parse()
+        .unwrap();
+    assert_eq!(graph, expected);
+}
+
+#[test]
+fn graph_without_problem_line_is_inferred_from_its_edges() {
+    let file = "e 1 3\ne 3 5\ne 5 1\ne 1 3\n";
+
+    let parsed = Graph::lenient_from_reader(file.as_bytes(), ParseLimits::default()).unwrap();
+    assert_eq!(
+        parsed.graph.sorted_vertices(),
+        vec![NodeId(1), NodeId(3), NodeId(5)]
+    );
+    assert_eq!(parsed.graph.edges.len(), 3);
+    assert_eq!(parsed.duplicate_edges, 1);
+    // Limits still apply
+    let limits = ParseLimits {
+        max_vertex_id: Some(4),
+        ..ParseLimits::default()
+    };
+    assert!(matches!(
+        Graph::lenient_from_reader(file.as_bytes(), limits),
+        Err(GraphParsingError::VertexOutOfRange { vertex: 5, max: 4 })
+    ));
+
+    let path = std::env::temp_dir().join(format!("renraku-lenient-{}.col", std::process::id()));
+    std::fs::write(&path, file).unwrap();
+    assert!(Graph::open(&path).is_err());
+    let opened = Graph::open_lenient(&path, ParseLimits::default()).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(opened.graph, parsed.graph);
+}
+
+#[test]
+fn vertex_above_the_maximum_id_is_rejected() {
+    let limits = ParseLimits {
+        max_vertex_id: Some(8),
+        ..ParseLimits::default()
+    };
+
+    assert!(Graph::from_reader("p edge 8 1\ne 1 8\n".as_bytes(), limits).is_ok());
+    assert!(matches!(
+        Graph::from_reader("p edge 3 2\ne 1 2\ne 2 4096\n".as_bytes(), limits),
+        Err(GraphParsingError::VertexOutOfRange {
+            vertex: 4096,
+            max: 8
+        })
+    ));
+    assert!(matches!(
+        Graph::csv_from_reader("src,dst\n9,1\n".as_bytes(), limits),
+        Err(GraphParsingError::VertexOutOfRange { vertex: 9, max: 8 })
+    ));
+    assert!(matches!(
+        Graph::from_reader("p edge 2 1\nn 12 producer\ne 1 2\n".as_bytes(), limits),
+        Err(GraphParsingError::VertexOutOfRange { vertex: 12, max: 8 })
+    ));
+}
+
+#[test]
+fn symmetric_matrix_makes_undirected_edges() {
+    // A path 1 - 2 - 3, plus an isolated 4
+    let rows = vec![
+        vec![0, 1, 0, 0],
+        vec![1, 0, 1, 0],
+        vec![0, 1, 0, 0],
+        vec![0, 0, 0, 0],
+    ];
+    let graph = Graph::from_adjacency_matrix(&rows, MatrixKind::Symmetric).unwrap();
+
+    assert_eq!(
+        graph.sorted_vertices(),
+        (1..=4).map(NodeId).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        graph.sorted_edges(),
+        vec![
+            Connection(NodeId(1), NodeId(2)),
+            Connection(NodeId(2), NodeId(3)),
+        ]
+    );
+    // Read as directed, each pair of vertices would be connected both ways
+    assert!(matches!(
+        Graph::from_adjacency_matrix(&rows, MatrixKind::Directed),
+        Err(MatrixError::Build(GraphBuildError::DuplicateEdge(_)))
+    ));
+}
+
+#[test]
+fn asymmetric_matrix_keeps_the_direction_of_its_edges() {
+    // 3 connects to 1 and 2, 2 connects to 1
+    let rows = vec![vec![0, 0, 0], vec![1, 0, 0], vec![1, 1, 0]];
+
+    assert!(matches!(
+        Graph::from_adjacency_matrix(&rows, MatrixKind::Symmetric),
+        Err(MatrixError::Asymmetric(1, 2))
+    ));
+    let graph = Graph::from_adjacency_matrix(&rows, MatrixKind::Directed).unwrap();
+    assert_eq!(
+        graph.sorted_edges(),
+        vec![
+            Connection(NodeId(2), NodeId(1)),
+            Connection(NodeId(3), NodeId(1)),
+            Connection(NodeId(3), NodeId(2)),
+        ]
+    );
+    assert_eq!(graph.placement(&NodeId(3)), Placement::Source);
+    assert_eq!(graph.placement(&NodeId(1)), Placement::Sink);
+}
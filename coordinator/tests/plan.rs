@@ -0,0 +1,155 @@
+mod common;
+
+use std::{collections::BTreeSet, net::UdpSocket, sync::Arc, thread, time::Duration};
+
+use common::arguments;
+use renraku_coordinator::{
+    setup::{serve, LogFormat},
+    Graph,
+};
+use renraku_node::{configure, Neighbour};
+use renraku_shared::{control::ControlMessage, NodeId};
+
+#[test]
+fn plan_matches_the_connections_made_during_the_setup() {
+    let dimacs = "p edge 6 7\ne 1 2\ne 3 2\ne 2 4\ne 5 4\ne 4 6\ne 6 1\ne 3 5\n";
+    let graph: Graph = dimacs.parse().unwrap();
+    let plan = graph.plan();
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let controller = socket.local_addr().unwrap().to_string();
+
+    let coordinator = thread::spawn(move || {
+        serve(&socket, &graph, 6, None, false, false, LogFormat::Human).unwrap()
+    });
+    let nodes: Vec<_> = (0..6)
+        .map(|_| {
+            let args = arguments(&controller);
+            thread::spawn(move || configure(args).unwrap())
+        })
+        .collect();
+    let configs: Vec<_> = nodes.into_iter().map(|n| n.join().unwrap()).collect();
+    let summary = coordinator.join().unwrap();
+    assert!(summary.is_success());
+    let listener = |peer: &NodeId| {
+        summary
+            .members
+            .iter()
+            .find(|m| &m.id == peer)
+            .unwrap()
+            .listener
+    };
+
+    assert_eq!(plan.vertices.len(), configs.len());
+    for config in configs.iter() {
+        // Streams this node dialed lead to the listener of the neighbour
+        let dialed = |(peer, n): &(NodeId, Arc<Neighbour>)| {
+            n.channel().peer_addr().unwrap() == listener(peer)
+        };
+        let outgoing: BTreeSet<NodeId> = config
+            .neighbours
+            .iter()
+            .filter(dialed)
+            .map(|(peer, _)| peer)
+            .collect();
+        let incoming: BTreeSet<NodeId> = config
+            .neighbours
+            .keys()
+            .filter(|peer| !outgoing.contains(peer))
+            .collect();
+        let planned = &plan.vertices[&config.id];
+        assert_eq!(planned.outgoing, outgoing, "outgoing of {:?}", config.id);
+        assert_eq!(planned.incoming, incoming, "incoming of {:?}", config.id);
+    }
+}
+
+#[test]
+fn nodes_are_given_the_id_they_asked_for() {
+    let graph: Graph = "p edge 4 3\ne 1 2\ne 2 3\ne 3 4\n".parse().unwrap();
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let controller = socket.local_addr().unwrap().to_string();
+
+    let coordinator = thread::spawn(move || {
+        serve(&socket, &graph, 4, None, false, false, LogFormat::Human).unwrap()
+    });
+    let nodes: Vec<_> = (1..=4)
+        .map(|desired| {
+            let mut args = arguments(&controller);
+            args.desired_id = Some(desired);
+            thread::spawn(move || (desired, configure(args).unwrap()))
+        })
+        .collect();
+
+    for node in nodes {
+        let (desired, config) = node.join().unwrap();
+        assert_eq!(config.id, NodeId(desired));
+    }
+    assert!(coordinator.join().unwrap().is_success());
+}
+
+#[test]
+fn sparse_vertex_ids_are_kept_when_asked() {
+    let graph: Graph = "p edge 6 2\ne 2 4\ne 4 6\n".parse().unwrap();
+    let members = vec![NodeId(2), NodeId(4), NodeId(6)];
+
+    for desired in [[Some(6), Some(2), Some(4)], [None; 3]] {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let controller = socket.local_addr().unwrap().to_string();
+        let graph = graph.clone();
+        let coordinator = thread::spawn(move || {
+            serve(&socket, &graph, 3, None, false, true, LogFormat::Human).unwrap()
+        });
+        let nodes: Vec<_> = desired
+            .into_iter()
+            .map(|desired| {
+                let mut args = arguments(&controller);
+                args.desired_id = desired;
+                thread::spawn(move || (desired, configure(args).unwrap()))
+            })
+            .collect();
+
+        let mut ids = Vec::new();
+        for node in nodes {
+            let (desired, config) = node.join().unwrap();
+            if let Some(desired) = desired {
+                assert_eq!(config.id, NodeId(desired));
+            }
+            // Nodes are told about the vertices that exist, and only those
+            assert_eq!(config.node_count, 3);
+            assert_eq!(config.members, members);
+            let neighbours: BTreeSet<NodeId> = config.neighbours.keys().collect();
+            let expected: BTreeSet<NodeId> = match config.id {
+                NodeId(4) => BTreeSet::from([NodeId(2), NodeId(6)]),
+                _ => BTreeSet::from([NodeId(4)]),
+            };
+            assert_eq!(neighbours, expected, "neighbours of {:?}", config.id);
+            ids.push(config.id.clone());
+        }
+        assert!(coordinator.join().unwrap().is_success());
+        ids.sort();
+        assert_eq!(ids, members);
+    }
+}
+
+#[test]
+fn missing_nodes_are_the_vertices_nobody_took() {
+    let graph: Graph = "p edge 6 2\ne 2 4\ne 4 6\n".parse().unwrap();
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let controller = socket.local_addr().unwrap().to_string();
+    let timeout = Some(Duration::from_millis(500));
+
+    let coordinator = thread::spawn(move || {
+        serve(&socket, &graph, 3, timeout, false, true, LogFormat::Human).unwrap()
+    });
+    // A single node registers, asking for the middle vertex
+    let node = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let hello = ControlMessage::Hello {
+        listen_port: 4000,
+        capacity: 1,
+        desired_id: Some(NodeId(4)),
+        advertise: None,
+    };
+    node.send_to(&hello.encode().unwrap(), &controller).unwrap();
+
+    let summary = coordinator.join().unwrap();
+    assert_eq!(summary.missing, vec![NodeId(2), NodeId(6)]);
+}
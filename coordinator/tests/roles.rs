@@ -0,0 +1,23 @@
+mod common;
+
+use common::launch;
+use renraku_coordinator::Graph;
+
+#[test]
+fn roles_are_relayed_to_their_node() {
+    let configs = launch("p edge 3 2\nn 1 producer\nn 2 consumer\ne 1 2\ne 1 3\n");
+
+    let roles: Vec<Option<&str>> = configs.iter().map(|c| c.role.as_deref()).collect();
+    assert_eq!(roles, vec![Some("producer"), Some("consumer"), None]);
+}
+
+#[test]
+fn role_of_a_vertex_that_is_not_a_number_is_rejected() {
+    let error = "p edge 2 1\nn first producer\ne 1 2\n"
+        .parse::<Graph>()
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("expected a vertex number"),
+        "{error}"
+    );
+}
@@ -0,0 +1,97 @@
+mod common;
+
+use std::{sync::mpsc, thread, time::Duration};
+
+use renraku_coordinator::{graph::GraphBuilder, run, setup::LogFormat, CoordinatorOptions, Graph};
+use renraku_node::configure;
+use renraku_shared::NodeId;
+
+#[test]
+fn run_sets_up_a_graph_built_in_process() {
+    let mut builder = GraphBuilder::new();
+    for v in 1..=3 {
+        builder.add_vertex(NodeId(v));
+    }
+    let graph = builder
+        .add_edge(NodeId(1), NodeId(2))
+        .add_edge(NodeId(2), NodeId(3))
+        .build()
+        .unwrap();
+
+    let (listening, address) = mpsc::channel();
+    let options = CoordinatorOptions {
+        address: "127.0.0.1:0".parse().unwrap(),
+        listening: Some(listening),
+        ..CoordinatorOptions::default()
+    };
+    let coordinator = thread::spawn(move || run(graph, options).unwrap());
+    let controller = address.recv().unwrap().to_string();
+    let nodes: Vec<_> = (0..3)
+        .map(|_| {
+            let args = common::arguments(&controller);
+            thread::spawn(move || configure(args).unwrap())
+        })
+        .collect();
+    let mut ids: Vec<usize> = nodes.into_iter().map(|n| n.join().unwrap().id.0).collect();
+    ids.sort();
+
+    let report = coordinator.join().unwrap();
+    assert!(report.is_success());
+    assert_eq!(ids, vec![1, 2, 3]);
+    assert_eq!(report.stats.vertices, 3);
+    assert_eq!(report.plan.connections(), 2);
+    assert_eq!(report.summary.connections, 2);
+    let assigned: Vec<usize> = report.summary.members.iter().map(|m| m.id.0).collect();
+    assert_eq!(assigned, vec![1, 2, 3]);
+}
+
+#[test]
+fn run_takes_options_from_the_builder() {
+    let (listening, address) = mpsc::channel();
+    let options = CoordinatorOptions::builder()
+        .address("127.0.0.1:0".parse().unwrap())
+        .expected_nodes(2)
+        .registration_timeout(Duration::from_secs(10))
+        .log_format(LogFormat::Json)
+        .listening(listening)
+        .build();
+    let coordinator = thread::spawn(move || run(Graph::complete(3), options).unwrap());
+    let controller = address.recv().unwrap().to_string();
+    let nodes: Vec<_> = (0..2)
+        .map(|_| {
+            let args = common::arguments(&controller);
+            thread::spawn(move || configure(args).unwrap())
+        })
+        .collect();
+    for node in nodes {
+        assert_eq!(node.join().unwrap().node_count, 2);
+    }
+
+    let report = coordinator.join().unwrap();
+    assert!(report.is_success());
+    // Only the expected nodes are waited for
+    assert_eq!(report.stats.vertices, 2);
+    assert_eq!(report.summary.connections, 1);
+}
+
+#[test]
+fn run_rejects_expecting_no_node_or_more_nodes_than_vertices() {
+    for expected in [0, 4] {
+        let (listening, address) = mpsc::channel();
+        let options = CoordinatorOptions::builder()
+            .address("127.0.0.1:0".parse().unwrap())
+            .expected_nodes(expected)
+            .listening(listening)
+            .build();
+
+        let error = run(Graph::complete(3), options).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains(&format!("Expecting {expected} nodes")),
+            "{error}"
+        );
+        // Rejected before listening for any node
+        assert!(address.try_recv().is_err());
+    }
+}
@@ -0,0 +1,88 @@
+mod common;
+
+use std::collections::HashSet;
+
+use common::launch;
+use renraku_shared::Placement;
+
+#[test]
+fn triangle_nodes_are_connected_to_each_other() {
+    let configs = launch("p edge 3 3\ne 1 2\ne 2 3\ne 1 3\n");
+
+    assert_eq!(configs.len(), 3);
+    for config in configs.iter() {
+        assert_eq!(config.node_count, 3);
+        let neighbours: HashSet<usize> = config.neighbours.keys().map(|n| n.0).collect();
+        let expected: HashSet<usize> = (1..=3).filter(|n| *n != config.id.0).collect();
+        assert_eq!(neighbours, expected, "neighbours of {:?}", config.id);
+    }
+}
+
+#[test]
+fn neighbour_streams_disable_nagle_algorithm() {
+    let configs = launch("p edge 3 2\ne 1 2\ne 2 3\n");
+
+    for config in configs.iter() {
+        for (peer, neighbour) in config.neighbours.iter() {
+            assert!(
+                neighbour.channel().nodelay().unwrap(),
+                "stream from {:?} to {:?}",
+                config.id,
+                peer
+            );
+        }
+    }
+}
+
+#[test]
+fn nodes_of_a_directed_path_know_their_placement() {
+    // Each edge goes from its lowest vertex to its highest: 1 -> 2 -> 3
+    let configs = launch("p edge 3 2\ne 2 3\ne 1 2\n");
+
+    let placements: Vec<Option<Placement>> = configs.iter().map(|c| c.placement).collect();
+    assert_eq!(
+        placements,
+        vec![
+            Some(Placement::Source),
+            Some(Placement::Internal),
+            Some(Placement::Sink),
+        ]
+    );
+}
+
+#[test]
+fn pure_sink_node_is_connected_to_every_node_dialing_it() {
+    // Every edge goes towards the highest vertex, the last node only accepting connections
+    let configs = launch(
+        "p edge 4 3
+e 1 4
+e 2 4
+e 3 4
+",
+    );
+
+    let sink = &configs[3];
+    assert_eq!(sink.placement, Some(Placement::Sink));
+    let neighbours: HashSet<usize> = sink.neighbours.keys().map(|n| n.0).collect();
+    assert_eq!(neighbours, HashSet::from([1, 2, 3]));
+}
+
+#[test]
+fn every_node_of_a_large_ring_receives_its_own_setup() {
+    let n = 40;
+    let mut graph = format!("p edge {n} {n}\n");
+    for v in 1..=n {
+        graph.push_str(&format!("e {} {}\n", v, v % n + 1));
+    }
+    let configs = launch(&graph);
+
+    let ids: Vec<usize> = configs.iter().map(|c| c.id.0).collect();
+    assert_eq!(ids, (1..=n).collect::<Vec<_>>());
+    for config in configs.iter() {
+        let id = config.id.0;
+        let expected = HashSet::from([(id + n - 2) % n + 1, id % n + 1]);
+        let neighbours: HashSet<usize> = config.neighbours.keys().map(|n| n.0).collect();
+        assert_eq!(neighbours, expected, "neighbours of {:?}", config.id);
+        assert_eq!(config.node_count, n);
+    }
+}
@@ -0,0 +1,240 @@
+use std::collections::{BTreeSet, HashSet};
+
+use renraku_node::runtime::{self, Exclusion};
+use renraku_shared::NodeId;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum Message {
+    /// Adds a request for the critical section to the queue of every node.
+    Request { date: usize, requester: NodeId },
+    /// Acknowledges a request, telling the requester no older request is on its way.
+    Ack { date: usize, acknowledger: NodeId },
+    /// The releaser left the critical section, its request can be removed from the queue.
+    Release { date: usize, releaser: NodeId },
+}
+
+impl runtime::Message for Message {
+    fn kind(&self) -> &'static str {
+        match self {
+            Message::Request { .. } => "Request",
+            Message::Ack { .. } => "Ack",
+            Message::Release { .. } => "Release",
+        }
+    }
+
+    fn sender(&self) -> &NodeId {
+        match self {
+            Message::Request { requester, .. } => requester,
+            Message::Ack { acknowledger, .. } => acknowledger,
+            Message::Release { releaser, .. } => releaser,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum State {
+    Idling,
+    Asking,
+    CriticalSection,
+}
+
+/// A request for the critical section, ordered by date then by requester.
+///
+/// The lowest request has the highest priority.
+type Priority = (usize, usize);
+
+pub type Outgoing = runtime::Outgoing<Message>;
+
+/// State of a node running Lamport's mutual exclusion algorithm.
+///
+/// Every node keeps a copy of the queue of pending requests, ordered by `(timestamp, node)`.
+/// A node enters the critical section once its own request heads the queue and every other
+/// node acknowledged it, which takes `3 * (N - 1)` messages per critical section. Channels
+/// must be FIFO, and every node must be a neighbour of every other.
+#[derive(Debug, Clone)]
+pub struct LamportMutex {
+    pub id: NodeId,
+    /// Every node of the system, this one included.
+    pub members: Vec<NodeId>,
+    pub state: State,
+    pub timestamp: usize,
+    /// Pending requests of every node, including ours.
+    pub queue: BTreeSet<Priority>,
+    /// Our pending request, if any.
+    pub request: Option<Priority>,
+    /// Nodes that acknowledged our pending request.
+    pub acks: HashSet<NodeId>,
+}
+
+impl LamportMutex {
+    pub fn new(id: NodeId, members: Vec<NodeId>) -> Self {
+        Self {
+            id,
+            members,
+            state: State::Idling,
+            timestamp: 0,
+            queue: BTreeSet::new(),
+            request: None,
+            acks: HashSet::new(),
+        }
+    }
+
+    /// Queues a request for the critical section, and broadcasts it to every other node.
+    ///
+    /// A node alone in the system enters the critical section right away, nobody having to
+    /// acknowledge its request.
+    pub fn ask(&mut self) -> Outgoing {
+        self.timestamp += 1;
+        let request = (self.timestamp, self.id.0);
+        self.state = State::Asking;
+        self.request = Some(request);
+        self.queue.insert(request);
+        self.acks.clear();
+        debug!("❓ Broadcasting a request dated {}", self.timestamp);
+
+        self.try_enter();
+
+        let message = Message::Request {
+            date: self.timestamp,
+            requester: self.id.clone(),
+        };
+        self.broadcast(message)
+    }
+
+    /// Leaves the critical section, removing our request from the queue of every node.
+    pub fn free(&mut self) -> Outgoing {
+        self.timestamp += 1;
+        self.state = State::Idling;
+        if let Some(request) = self.request.take() {
+            self.queue.remove(&request);
+        }
+
+        let message = Message::Release {
+            date: self.timestamp,
+            releaser: self.id.clone(),
+        };
+        self.broadcast(message)
+    }
+
+    pub fn handle(&mut self, message: Message) -> Outgoing {
+        let mut messages = Vec::new();
+        match message {
+            Message::Request { date, requester } => {
+                self.timestamp = self.timestamp.max(date) + 1;
+                self.queue.insert((date, requester.0));
+                messages.push((
+                    requester,
+                    Message::Ack {
+                        date: self.timestamp,
+                        acknowledger: self.id.clone(),
+                    },
+                ));
+            }
+            Message::Ack { date, acknowledger } => {
+                self.timestamp = self.timestamp.max(date) + 1;
+                if self.state == State::Asking {
+                    self.acks.insert(acknowledger);
+                }
+            }
+            Message::Release { date, releaser } => {
+                self.timestamp = self.timestamp.max(date) + 1;
+                self.queue.retain(|r| r.1 != releaser.0);
+            }
+        }
+        self.try_enter();
+        Outgoing {
+            clock: self.timestamp,
+            messages,
+        }
+    }
+
+    /// Adds a node that joined the system once it was set up to the members, telling it about
+    /// our pending request if any.
+    ///
+    /// A request still waiting for acknowledgements now waits for the one of the joining node
+    /// as well. A request already granted is sent dated `0`, so that it heads the queue of
+    /// the joining node, whose clock may lag behind, until we leave the critical section.
+    ///
+    /// A member joining again restarted, forgetting about its own request and our own: the
+    /// request it left in our queue is dropped, and ours waits for it to acknowledge it again.
+    pub fn join(&mut self, peer: NodeId) -> Outgoing {
+        if self.members.contains(&peer) {
+            self.queue.retain(|r| r.1 != peer.0);
+            self.acks.remove(&peer);
+        } else {
+            self.members.push(peer.clone());
+            self.members.sort();
+        }
+        let messages = match (&self.state, self.request) {
+            (State::Asking, Some((date, _))) => vec![(peer, self.request_dated(date))],
+            (State::CriticalSection, Some(_)) => vec![(peer, self.request_dated(0))],
+            _ => Vec::new(),
+        };
+        Outgoing {
+            clock: self.timestamp,
+            messages,
+        }
+    }
+
+    fn request_dated(&self, date: usize) -> Message {
+        Message::Request {
+            date,
+            requester: self.id.clone(),
+        }
+    }
+
+    /// Enters the critical section if our request heads the queue and has been acknowledged.
+    fn try_enter(&mut self) {
+        if self.state == State::Asking
+            && self.acks.len() + 1 == self.members.len()
+            && self.queue.first() == self.request.as_ref()
+        {
+            debug!("🔓 Our request heads the queue and has been acknowledged by everyone");
+            self.state = State::CriticalSection;
+        }
+    }
+
+    fn broadcast(&self, message: Message) -> Outgoing {
+        Outgoing {
+            clock: self.timestamp,
+            messages: self
+                .members
+                .iter()
+                .filter(|n| **n != self.id)
+                .map(|n| (n.clone(), message.clone()))
+                .collect(),
+        }
+    }
+}
+
+impl Exclusion for LamportMutex {
+    type Message = Message;
+
+    const SUPPORTS_JOIN: bool = true;
+
+    fn ask(&mut self) -> Outgoing {
+        LamportMutex::ask(self)
+    }
+
+    fn free(&mut self) -> Outgoing {
+        LamportMutex::free(self)
+    }
+
+    fn handle(&mut self, message: Message) -> Outgoing {
+        LamportMutex::handle(self, message)
+    }
+
+    fn clock(&self) -> usize {
+        self.timestamp
+    }
+
+    fn join(&mut self, peer: NodeId) -> Outgoing {
+        LamportMutex::join(self, peer)
+    }
+
+    fn in_critical_section(&self) -> bool {
+        self.state == State::CriticalSection
+    }
+}
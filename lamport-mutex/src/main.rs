@@ -0,0 +1,34 @@
+use clap::Parser;
+use color_eyre::eyre::{bail, Result};
+use renraku_lamport_mutex::algorithm::LamportMutex;
+use renraku_node::runtime::{self, Arguments};
+use tracing::Level;
+
+fn main() -> Result<()> {
+    color_eyre::install()?;
+    tracing_subscriber::fmt()
+        .with_max_level(Level::DEBUG)
+        .init();
+
+    // Node configuration
+    let arguments = Arguments::try_parse()?;
+    let probe = arguments.node.controller_probe;
+    let configuration = renraku_node::configure(arguments.node)?;
+    if probe {
+        println!("🔍 {}", configuration.describe());
+        return Ok(());
+    }
+    if let Some(missing) = configuration
+        .others()
+        .find(|n| !configuration.neighbours.contains_key(n))
+    {
+        bail!(
+            "{:?} is not a neighbour, Lamport's algorithm requires a complete graph",
+            missing
+        );
+    }
+
+    // Begins
+    let state = LamportMutex::new(configuration.id.clone(), configuration.members.clone());
+    Ok(runtime::run(state, configuration, &arguments.cycle)?)
+}
@@ -0,0 +1,173 @@
+use std::{
+    sync::{mpsc, Arc, Condvar, Mutex},
+    thread::{self, sleep, JoinHandle},
+    time::Duration,
+};
+
+use renraku_coordinator::{run, CoordinatorOptions, Graph, TopologyReport};
+use renraku_lamport_mutex::algorithm::{LamportMutex, State};
+use renraku_node::{
+    configure,
+    runtime::{dispatch, receive_thread},
+    NodeArguments, NodeConfig,
+};
+use renraku_shared::NodeId;
+
+/// A node configured by the controller, whose neighbours are handled by its receive thread.
+#[derive(Clone)]
+struct Running {
+    mutex: Arc<Mutex<LamportMutex>>,
+    permission: Arc<Condvar>,
+    config: Arc<NodeConfig>,
+}
+
+impl Running {
+    fn start(args: NodeArguments) -> Self {
+        let config = configure(args).unwrap();
+        config.start_writers();
+        let state = LamportMutex::new(config.id.clone(), config.members.clone());
+        let running = Running {
+            mutex: Arc::new(Mutex::new(state)),
+            permission: Arc::new(Condvar::new()),
+            config: Arc::new(config),
+        };
+        let t = (
+            running.mutex.clone(),
+            running.permission.clone(),
+            running.config.clone(),
+        );
+        thread::spawn(move || receive_thread(t.0, t.1, t.2));
+        running
+    }
+
+    fn enter(&self) {
+        let outgoing = self.mutex.lock().unwrap().ask();
+        dispatch(outgoing, &self.config).unwrap();
+        let (_state, timeout) = self
+            .permission
+            .wait_timeout_while(self.mutex.lock().unwrap(), Duration::from_secs(10), |v| {
+                v.state != State::CriticalSection
+            })
+            .unwrap();
+        assert!(!timeout.timed_out(), "{:?} never entered", self.config.id);
+    }
+
+    fn leave(&self) {
+        let outgoing = self.mutex.lock().unwrap().free();
+        dispatch(outgoing, &self.config).unwrap();
+    }
+
+    fn state(&self) -> State {
+        self.mutex.lock().unwrap().state.clone()
+    }
+}
+
+fn arguments(controller: &str) -> NodeArguments {
+    NodeArguments {
+        controller: controller.to_string(),
+        rejoin: true,
+        ..NodeArguments::default()
+    }
+}
+
+/// Runs a resident coordinator on a complete graph of `count` nodes, returning the address
+/// nodes register on along with the coordinator, then runs a node for each vertex.
+fn launch(count: usize) -> (String, JoinHandle<TopologyReport>, Vec<Running>) {
+    let (listening, address) = mpsc::channel();
+    let options = CoordinatorOptions {
+        address: "127.0.0.1:0".parse().unwrap(),
+        listening: Some(listening),
+        resident: Some(Duration::from_secs(2)),
+        ..CoordinatorOptions::default()
+    };
+    let coordinator = thread::spawn(move || run(Graph::complete(count), options).unwrap());
+    let controller = address.recv().unwrap().to_string();
+
+    let nodes: Vec<_> = (0..count)
+        .map(|_| {
+            let args = arguments(&controller);
+            thread::spawn(move || Running::start(args))
+        })
+        .collect();
+    let mut nodes: Vec<Running> = nodes.into_iter().map(|n| n.join().unwrap()).collect();
+    nodes.sort_by_key(|n| n.config.id.clone());
+    (controller, coordinator, nodes)
+}
+
+#[test]
+fn late_node_joins_and_enters_the_critical_section() {
+    let (controller, coordinator, nodes) = launch(3);
+
+    // The first node holds the critical section while the last one joins
+    nodes[0].enter();
+    let late = Running::start(arguments(&controller));
+    assert_eq!(late.config.id, NodeId(4));
+    assert_eq!(late.config.members, (1..=4).map(NodeId).collect::<Vec<_>>());
+    assert_eq!(late.config.neighbours.len(), 3);
+
+    let asking = thread::spawn(move || {
+        late.enter();
+        late
+    });
+    sleep(Duration::from_millis(300));
+    assert_eq!(nodes[0].state(), State::CriticalSection);
+    assert!(
+        !asking.is_finished(),
+        "the late node entered alongside the first one"
+    );
+    nodes[0].leave();
+    let late = asking.join().unwrap();
+    late.leave();
+
+    // The others admitted it and take it into account from then on
+    for node in nodes.iter() {
+        assert_eq!(node.config.neighbours.len(), 3);
+        node.enter();
+        node.leave();
+    }
+
+    let report = coordinator.join().unwrap();
+    assert!(report.is_success());
+    assert_eq!(report.summary.nodes, 4);
+    assert_eq!(report.summary.connections, 6);
+}
+
+#[test]
+fn restarted_node_rejoins_its_neighbours() {
+    let (controller, coordinator, nodes) = launch(3);
+    nodes[2].enter();
+    nodes[2].leave();
+
+    // The streams of the third node are killed, as if its process was
+    for neighbour in nodes[2].config.neighbours.values() {
+        neighbour.shutdown().unwrap();
+    }
+    let first = nodes[0].clone();
+    let asking = thread::spawn(move || first.enter());
+    sleep(Duration::from_millis(300));
+    assert!(
+        !asking.is_finished(),
+        "entered without the third node acknowledging"
+    );
+
+    let restarted = Running::start(NodeArguments {
+        desired_id: Some(3),
+        ..arguments(&controller)
+    });
+    assert_eq!(restarted.config.id, NodeId(3));
+    assert_eq!(restarted.config.neighbours.len(), 2);
+    asking.join().unwrap();
+    nodes[0].leave();
+    restarted.enter();
+    restarted.leave();
+    for node in nodes[..2].iter() {
+        assert_eq!(node.config.neighbours.len(), 2);
+        node.enter();
+        node.leave();
+    }
+
+    let report = coordinator.join().unwrap();
+    assert!(report.is_success());
+    assert_eq!(report.summary.nodes, 3);
+    assert_eq!(report.summary.connections, 3);
+}
@@ -0,0 +1,119 @@
+use std::collections::{HashMap, VecDeque};
+
+use renraku_lamport_mutex::algorithm::{LamportMutex, Message, Outgoing, State};
+use renraku_node::runtime::Message as _;
+use renraku_shared::NodeId;
+
+const NODES: usize = 4;
+const CYCLES: usize = 20;
+
+/// FIFO channels between each pair of nodes, counting the messages sent of each kind.
+#[derive(Default)]
+struct Network {
+    channels: HashMap<(usize, usize), VecDeque<Message>>,
+    sent: HashMap<&'static str, usize>,
+}
+
+impl Network {
+    fn send(&mut self, from: usize, outgoing: Outgoing) {
+        for (to, message) in outgoing.messages {
+            *self.sent.entry(message.kind()).or_default() += 1;
+            self.channels
+                .entry((from, to.0))
+                .or_default()
+                .push_back(message);
+        }
+    }
+
+    fn pending(&self) -> Vec<(usize, usize)> {
+        let mut pending: Vec<(usize, usize)> = self
+            .channels
+            .iter()
+            .filter(|(_, c)| !c.is_empty())
+            .map(|(k, _)| *k)
+            .collect();
+        pending.sort();
+        pending
+    }
+}
+
+#[test]
+fn at_most_one_node_in_critical_section() {
+    let mut nodes: Vec<LamportMutex> = (1..=NODES)
+        .map(|i| LamportMutex::new(NodeId(i), (1..=NODES).map(NodeId).collect()))
+        .collect();
+    let mut network = Network::default();
+    let mut entries = [0; NODES];
+    let mut held_for = [0; NODES];
+    let mut seed: u64 = 42;
+
+    for _ in 0..100_000 {
+        if entries.iter().all(|e| *e == CYCLES) && network.pending().is_empty() {
+            break;
+        }
+        seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+
+        for (i, node) in nodes.iter_mut().enumerate() {
+            let outgoing = match node.state {
+                State::Idling if entries[i] < CYCLES => node.ask(),
+                State::CriticalSection => {
+                    held_for[i] += 1;
+                    if held_for[i] < 3 {
+                        continue;
+                    }
+                    held_for[i] = 0;
+                    entries[i] += 1;
+                    node.free()
+                }
+                _ => continue,
+            };
+            network.send(i + 1, outgoing);
+        }
+
+        // Delivers the head of a pseudo-randomly chosen non-empty channel
+        let pending = network.pending();
+        if pending.is_empty() {
+            continue;
+        }
+        let (from, to) = pending[(seed >> 33) as usize % pending.len()];
+        let message = network
+            .channels
+            .get_mut(&(from, to))
+            .unwrap()
+            .pop_front()
+            .unwrap();
+        let outgoing = nodes[to - 1].handle(message);
+        network.send(to, outgoing);
+
+        let inside = nodes
+            .iter()
+            .filter(|n| n.state == State::CriticalSection)
+            .count();
+        assert!(inside <= 1, "{inside} nodes are in the critical section");
+    }
+
+    assert_eq!(entries, [CYCLES; NODES]);
+    // A request, an acknowledgement and a release exchanged with each of the other nodes for
+    // every critical section, nothing more
+    let per_kind = (NODES - 1) * NODES * CYCLES;
+    assert_eq!(
+        network.sent,
+        HashMap::from([
+            ("Request", per_kind),
+            ("Ack", per_kind),
+            ("Release", per_kind)
+        ])
+    );
+}
+
+#[test]
+fn lone_node_enters_as_soon_as_it_asks() {
+    let mut node = LamportMutex::new(NodeId(1), vec![NodeId(1)]);
+
+    assert!(node.ask().messages.is_empty());
+    assert_eq!(node.state, State::CriticalSection);
+    assert!(node.free().messages.is_empty());
+    assert_eq!(node.state, State::Idling);
+}
@@ -0,0 +1,297 @@
+use std::collections::{BTreeSet, HashSet, VecDeque};
+
+use renraku_node::runtime::{self, Exclusion};
+use renraku_shared::NodeId;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum Message {
+    /// Asks a member of the quorum for its vote.
+    Request { date: usize, requester: NodeId },
+    /// The vote of a member of the quorum has been granted.
+    Grant { voter: NodeId },
+    /// The requester left the critical section, its vote can be given to someone else.
+    Release { releaser: NodeId },
+    /// The vote is already held by a request with a higher priority.
+    Fail { voter: NodeId },
+    /// A request with a higher priority is waiting for a vote we hold.
+    Inquire { voter: NodeId },
+    /// Gives back a vote to a voter that inquired about it.
+    Yield { requester: NodeId },
+}
+
+impl runtime::Message for Message {
+    fn kind(&self) -> &'static str {
+        match self {
+            Message::Request { .. } => "Request",
+            Message::Grant { .. } => "Grant",
+            Message::Release { .. } => "Release",
+            Message::Fail { .. } => "Fail",
+            Message::Inquire { .. } => "Inquire",
+            Message::Yield { .. } => "Yield",
+        }
+    }
+
+    fn sender(&self) -> &NodeId {
+        match self {
+            Message::Request { requester, .. } | Message::Yield { requester } => requester,
+            Message::Grant { voter } | Message::Fail { voter } | Message::Inquire { voter } => {
+                voter
+            }
+            Message::Release { releaser } => releaser,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum State {
+    Idling,
+    Asking,
+    CriticalSection,
+}
+
+/// A request for the critical section, ordered by date then by requester.
+///
+/// The lowest request has the highest priority.
+type Priority = (usize, usize);
+
+/// Messages produced by the algorithm, along with their recipient.
+pub type Outgoing = Vec<(NodeId, Message)>;
+
+/// State of a node running Maekawa's algorithm.
+///
+/// A node is both a requester, collecting the votes of its quorum, and a voter, which
+/// gives its only vote to one request at a time. Deadlocks are avoided using the
+/// `Fail`, `Inquire` and `Yield` messages: a voter that locked its vote for a request
+/// inquires about it as soon as a request with a higher priority comes in, and the
+/// requester yields the vote back if it knows it cannot obtain every vote anyway.
+///
+/// The messages returned may be addressed to the node itself, since it belongs to its own
+/// quorum. They are handled right away when running the node, see [`Exclusion`].
+#[derive(Debug, Clone)]
+pub struct Maekawa {
+    pub id: NodeId,
+    pub quorum: HashSet<NodeId>,
+    pub state: State,
+    pub timestamp: usize,
+    /// Members of the quorum that granted us their vote.
+    pub grants: HashSet<NodeId>,
+    /// Whether a member of the quorum refused its vote to our pending request.
+    pub failed: bool,
+    /// Voters that inquired about their vote while we did not know whether to yield it.
+    pub inquiries: Vec<NodeId>,
+    /// Request currently holding our vote.
+    pub vote: Option<Priority>,
+    /// Whether we already inquired the holder of our vote.
+    pub inquired: bool,
+    /// Requests waiting for our vote.
+    pub waiting: BTreeSet<Priority>,
+}
+
+impl Maekawa {
+    pub fn new(id: NodeId, quorum: impl IntoIterator<Item = NodeId>) -> Self {
+        Self {
+            id,
+            quorum: quorum.into_iter().collect(),
+            state: State::Idling,
+            timestamp: 0,
+            grants: HashSet::new(),
+            failed: false,
+            inquiries: Vec::new(),
+            vote: None,
+            inquired: false,
+            waiting: BTreeSet::new(),
+        }
+    }
+
+    /// Asks every member of the quorum for its vote.
+    pub fn ask(&mut self) -> Outgoing {
+        self.timestamp += 1;
+        self.state = State::Asking;
+        self.grants.clear();
+        self.failed = false;
+        self.inquiries.clear();
+        debug!("❓ Asking for the votes of {:?}", self.quorum);
+
+        self.quorum
+            .iter()
+            .map(|n| {
+                (
+                    n.clone(),
+                    Message::Request {
+                        date: self.timestamp,
+                        requester: self.id.clone(),
+                    },
+                )
+            })
+            .collect()
+    }
+
+    /// Leaves the critical section, releasing the vote of every member of the quorum.
+    pub fn free(&mut self) -> Outgoing {
+        self.state = State::Idling;
+        self.grants.clear();
+
+        self.quorum
+            .iter()
+            .map(|n| {
+                (
+                    n.clone(),
+                    Message::Release {
+                        releaser: self.id.clone(),
+                    },
+                )
+            })
+            .collect()
+    }
+
+    pub fn handle(&mut self, message: Message) -> Outgoing {
+        let mut outgoing = Outgoing::new();
+        match message {
+            Message::Request { date, requester } => {
+                self.timestamp = self.timestamp.max(date) + 1;
+                let request = (date, requester.0);
+                match self.vote {
+                    None => {
+                        self.vote = Some(request);
+                        outgoing.push((requester, self.grant()));
+                    }
+                    Some(vote) => {
+                        let best = self.waiting.first().copied();
+                        self.waiting.insert(request);
+                        if request < vote && best.is_none_or(|b| request < b) {
+                            // The previous best waiting request will not get our vote soon
+                            if let Some(best) = best {
+                                outgoing.push((NodeId(best.1), self.fail()));
+                            }
+                            if !self.inquired {
+                                self.inquired = true;
+                                outgoing.push((
+                                    NodeId(vote.1),
+                                    Message::Inquire {
+                                        voter: self.id.clone(),
+                                    },
+                                ));
+                            }
+                        } else {
+                            outgoing.push((requester, self.fail()));
+                        }
+                    }
+                }
+            }
+            Message::Grant { voter } => {
+                self.grants.insert(voter);
+                if self.state == State::Asking && self.grants == self.quorum {
+                    debug!("🗳️ Every vote of the quorum has been granted");
+                    self.state = State::CriticalSection;
+                    self.inquiries.clear();
+                }
+            }
+            Message::Fail { .. } => {
+                if self.state == State::Asking {
+                    self.failed = true;
+                    let inquiries = std::mem::take(&mut self.inquiries);
+                    for voter in inquiries {
+                        outgoing.push(self.give_back(voter));
+                    }
+                }
+            }
+            Message::Inquire { voter } => {
+                // Stale inquiries are ignored, as the vote will be released anyway
+                if self.state == State::Asking && self.grants.contains(&voter) {
+                    if self.failed {
+                        outgoing.push(self.give_back(voter));
+                    } else {
+                        self.inquiries.push(voter);
+                    }
+                }
+            }
+            Message::Yield { requester } => {
+                if self.vote.is_some_and(|v| v.1 == requester.0) {
+                    self.waiting.extend(self.vote.take());
+                    outgoing.extend(self.vote_next());
+                }
+            }
+            Message::Release { .. } => {
+                self.vote = None;
+                outgoing.extend(self.vote_next());
+            }
+        }
+        outgoing
+    }
+
+    fn grant(&self) -> Message {
+        Message::Grant {
+            voter: self.id.clone(),
+        }
+    }
+
+    fn fail(&self) -> Message {
+        Message::Fail {
+            voter: self.id.clone(),
+        }
+    }
+
+    fn give_back(&mut self, voter: NodeId) -> (NodeId, Message) {
+        debug!("🔙 Yielding the vote of {:?}", voter);
+        self.grants.remove(&voter);
+        (
+            voter,
+            Message::Yield {
+                requester: self.id.clone(),
+            },
+        )
+    }
+
+    /// Gives our vote to the waiting request with the highest priority.
+    fn vote_next(&mut self) -> Option<(NodeId, Message)> {
+        self.inquired = false;
+        self.vote = self.waiting.pop_first();
+        self.vote.map(|v| (NodeId(v.1), self.grant()))
+    }
+
+    /// Handles the messages addressed to the node itself, returning the ones to send.
+    fn deliver_locally(&mut self, outgoing: Outgoing) -> runtime::Outgoing<Message> {
+        let mut pending = VecDeque::from(outgoing);
+        let mut messages = Vec::new();
+        while let Some((recipient, message)) = pending.pop_front() {
+            if recipient == self.id {
+                pending.extend(Maekawa::handle(self, message));
+            } else {
+                messages.push((recipient, message));
+            }
+        }
+        runtime::Outgoing {
+            clock: self.timestamp,
+            messages,
+        }
+    }
+}
+
+impl Exclusion for Maekawa {
+    type Message = Message;
+
+    fn ask(&mut self) -> runtime::Outgoing<Message> {
+        let outgoing = Maekawa::ask(self);
+        self.deliver_locally(outgoing)
+    }
+
+    fn free(&mut self) -> runtime::Outgoing<Message> {
+        let outgoing = Maekawa::free(self);
+        self.deliver_locally(outgoing)
+    }
+
+    fn handle(&mut self, message: Message) -> runtime::Outgoing<Message> {
+        let outgoing = Maekawa::handle(self, message);
+        self.deliver_locally(outgoing)
+    }
+
+    fn clock(&self) -> usize {
+        self.timestamp
+    }
+
+    fn in_critical_section(&self) -> bool {
+        self.state == State::CriticalSection
+    }
+}
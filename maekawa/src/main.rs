@@ -0,0 +1,35 @@
+use clap::Parser;
+use color_eyre::eyre::{bail, Result};
+use renraku_maekawa::algorithm::Maekawa;
+use renraku_node::runtime::{self, Arguments};
+use tracing::Level;
+
+fn main() -> Result<()> {
+    color_eyre::install()?;
+    tracing_subscriber::fmt()
+        .with_max_level(Level::DEBUG)
+        .init();
+
+    // Node configuration
+    let arguments = Arguments::try_parse()?;
+    let probe = arguments.node.controller_probe;
+    let configuration = renraku_node::configure(arguments.node)?;
+    if probe {
+        println!("🔍 {}", configuration.describe());
+        return Ok(());
+    }
+    if let Some(missing) = configuration
+        .quorum
+        .iter()
+        .find(|n| **n != configuration.id && !configuration.neighbours.contains_key(n))
+    {
+        bail!(
+            "{:?} belongs to the quorum but is not a neighbour, the graph must connect each quorum",
+            missing
+        );
+    }
+
+    // Begins
+    let state = Maekawa::new(configuration.id.clone(), configuration.quorum.clone());
+    Ok(runtime::run(state, configuration, &arguments.cycle)?)
+}
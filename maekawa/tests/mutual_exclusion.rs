@@ -0,0 +1,81 @@
+use std::collections::{HashMap, VecDeque};
+
+use renraku_maekawa::algorithm::{Maekawa, Message, State};
+use renraku_shared::NodeId;
+
+/// Quorums of four nodes laid out in a 2x2 grid.
+fn quorums() -> Vec<Vec<usize>> {
+    vec![vec![1, 2, 3], vec![1, 2, 4], vec![1, 3, 4], vec![2, 3, 4]]
+}
+
+#[test]
+fn at_most_one_node_in_critical_section() {
+    let mut nodes: Vec<Maekawa> = quorums()
+        .into_iter()
+        .enumerate()
+        .map(|(i, q)| Maekawa::new(NodeId(i + 1), q.into_iter().map(NodeId)))
+        .collect();
+    // FIFO channels between each pair of nodes, including a node and itself
+    let mut channels: HashMap<(usize, usize), VecDeque<Message>> = HashMap::new();
+    let mut entries = [0; 4];
+    let mut held_for = [0; 4];
+    let mut seed: u64 = 42;
+
+    for _ in 0..100_000 {
+        if entries.iter().all(|e| *e >= 20) {
+            break;
+        }
+        seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+
+        for (i, node) in nodes.iter_mut().enumerate() {
+            let outgoing = match node.state {
+                State::Idling if entries[i] < 20 => node.ask(),
+                State::CriticalSection => {
+                    held_for[i] += 1;
+                    if held_for[i] < 3 {
+                        continue;
+                    }
+                    held_for[i] = 0;
+                    entries[i] += 1;
+                    node.free()
+                }
+                _ => continue,
+            };
+            for (to, message) in outgoing {
+                channels
+                    .entry((i + 1, to.0))
+                    .or_default()
+                    .push_back(message);
+            }
+        }
+
+        // Delivers the head of a pseudo-randomly chosen non-empty channel
+        let mut pending: Vec<(usize, usize)> = channels
+            .iter()
+            .filter(|(_, c)| !c.is_empty())
+            .map(|(k, _)| *k)
+            .collect();
+        pending.sort();
+        if pending.is_empty() {
+            continue;
+        }
+        let (from, to) = pending[(seed >> 33) as usize % pending.len()];
+        let message = channels.get_mut(&(from, to)).unwrap().pop_front().unwrap();
+        for (next, message) in nodes[to - 1].handle(message) {
+            channels.entry((to, next.0)).or_default().push_back(message);
+        }
+
+        let inside = nodes
+            .iter()
+            .filter(|n| n.state == State::CriticalSection)
+            .count();
+        assert!(inside <= 1, "{inside} nodes are in the critical section");
+    }
+
+    assert!(
+        entries.iter().all(|e| *e >= 20),
+        "every node should have entered the critical section: {entries:?}"
+    );
+}
@@ -0,0 +1,282 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt,
+};
+
+use renraku_shared::{clock::VectorClock, NodeId};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    channel::Channel,
+    error::NodeError,
+    event::Direction,
+    runtime::{self, dispatch, Message as _, Outgoing},
+    NodeConfig,
+};
+
+/// A broadcast message, ordered by date then by sender.
+///
+/// The lowest one is the first to be delivered.
+pub type Order = (usize, usize);
+
+/// Messages of the total-order broadcast, carrying payloads of type `T`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum Message<T> {
+    /// A payload broadcast by its sender, dated with its Lamport clock.
+    Broadcast {
+        date: usize,
+        sender: NodeId,
+        payload: T,
+        /// Vector clock of the sender when sending, if messages are stamped.
+        clock: Option<VectorClock>,
+    },
+    /// Tells every node the acknowledger received the broadcast `message`.
+    Ack {
+        date: usize,
+        acknowledger: NodeId,
+        message: Order,
+        /// Vector clock of the acknowledger when sending, if messages are stamped.
+        clock: Option<VectorClock>,
+    },
+}
+
+impl<T: Serialize + DeserializeOwned> runtime::Message for Message<T> {
+    fn kind(&self) -> &'static str {
+        match self {
+            Message::Broadcast { .. } => "Broadcast",
+            Message::Ack { .. } => "Ack",
+        }
+    }
+
+    fn sender(&self) -> &NodeId {
+        match self {
+            Message::Broadcast { sender, .. } => sender,
+            Message::Ack { acknowledger, .. } => acknowledger,
+        }
+    }
+
+    fn stamp(&mut self, stamp: Option<VectorClock>) {
+        match self {
+            Message::Broadcast { clock, .. } | Message::Ack { clock, .. } => *clock = stamp,
+        }
+    }
+}
+
+impl<T> Message<T> {
+    fn date(&self) -> usize {
+        match self {
+            Message::Broadcast { date, .. } | Message::Ack { date, .. } => *date,
+        }
+    }
+
+    fn clock(&self) -> Option<&VectorClock> {
+        match self {
+            Message::Broadcast { clock, .. } | Message::Ack { clock, .. } => clock.as_ref(),
+        }
+    }
+}
+
+/// Code run by a [`TotalOrderBroadcast`] for each payload delivered, given its sender.
+pub type Deliver<T> = Box<dyn FnMut(&NodeId, T) + Send>;
+
+/// State of a node taking part in a total-order (atomic) broadcast, built on Lamport clocks.
+///
+/// A node broadcasts a payload dated with its clock, and every node receiving it
+/// acknowledges it to every other node. Payloads are delivered in `(date, sender)` order,
+/// each one once every node received it: the channels being FIFO, no payload dated earlier
+/// can then be on its way. Every node thus delivers the same payloads in the same order.
+/// Every node must be a neighbour of every other.
+///
+/// Running on a node, [`TotalOrderBroadcast::send`] and [`TotalOrderBroadcast::receive`]
+/// persist the Lamport clock and stamp the messages as any other algorithm does.
+///
+/// # Examples
+///
+/// ```
+/// # use renraku_node::broadcast::TotalOrderBroadcast;
+/// # use renraku_shared::NodeId;
+/// use std::sync::{Arc, Mutex};
+///
+/// let delivered = Arc::new(Mutex::new(Vec::new()));
+/// let log = delivered.clone();
+/// let members = vec![NodeId(1), NodeId(2)];
+/// let mut first = TotalOrderBroadcast::new(NodeId(1), members.clone(), move |sender, payload| {
+///     log.lock().unwrap().push((sender.clone(), payload))
+/// });
+/// let mut second = TotalOrderBroadcast::new(NodeId(2), members, |_, _: String| {});
+///
+/// let (_, broadcast) = first.broadcast("hello".to_string()).messages.remove(0);
+/// assert!(delivered.lock().unwrap().is_empty());
+/// for (_, ack) in second.handle(broadcast).messages {
+///     first.handle(ack);
+/// }
+/// assert_eq!(*delivered.lock().unwrap(), vec![(NodeId(1), "hello".to_string())]);
+/// ```
+pub struct TotalOrderBroadcast<T> {
+    pub id: NodeId,
+    /// Every node of the system, this one included.
+    pub members: Vec<NodeId>,
+    pub timestamp: usize,
+    /// Payloads received but not delivered yet.
+    pub pending: BTreeMap<Order, (NodeId, T)>,
+    /// Nodes known to have received each undelivered message, its sender included.
+    acks: HashMap<Order, HashSet<NodeId>>,
+    deliver: Deliver<T>,
+}
+
+impl<T: fmt::Debug> fmt::Debug for TotalOrderBroadcast<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TotalOrderBroadcast")
+            .field("id", &self.id)
+            .field("members", &self.members)
+            .field("timestamp", &self.timestamp)
+            .field("pending", &self.pending)
+            .field("acks", &self.acks)
+            .finish()
+    }
+}
+
+impl<T: Clone> TotalOrderBroadcast<T> {
+    /// A node of the system made of the `members`, handing each payload to `deliver` in the
+    /// total order.
+    pub fn new(
+        id: NodeId,
+        members: Vec<NodeId>,
+        deliver: impl FnMut(&NodeId, T) + Send + 'static,
+    ) -> Self {
+        Self {
+            id,
+            members,
+            timestamp: 0,
+            pending: BTreeMap::new(),
+            acks: HashMap::new(),
+            deliver: Box::new(deliver),
+        }
+    }
+
+    /// Broadcasts the payload to every other node, returning the messages to send.
+    ///
+    /// The payload is delivered to this node as well, once its turn comes.
+    pub fn broadcast(&mut self, payload: T) -> Outgoing<Message<T>> {
+        self.timestamp += 1;
+        let order = (self.timestamp, self.id.0);
+        self.pending
+            .insert(order, (self.id.clone(), payload.clone()));
+        self.acknowledge(order, self.id.clone());
+        let message = Message::Broadcast {
+            date: self.timestamp,
+            sender: self.id.clone(),
+            payload,
+            clock: None,
+        };
+        let messages = self.to_others(message);
+        self.try_deliver();
+        Outgoing {
+            clock: self.timestamp,
+            messages,
+        }
+    }
+
+    pub fn handle(&mut self, message: Message<T>) -> Outgoing<Message<T>> {
+        self.timestamp = self.timestamp.max(message.date()) + 1;
+        let messages = match message {
+            Message::Broadcast {
+                date,
+                sender,
+                payload,
+                ..
+            } => {
+                let order = (date, sender.0);
+                self.pending.insert(order, (sender.clone(), payload));
+                self.acknowledge(order, sender);
+                self.acknowledge(order, self.id.clone());
+                self.to_others(Message::Ack {
+                    date: self.timestamp,
+                    acknowledger: self.id.clone(),
+                    message: order,
+                    clock: None,
+                })
+            }
+            Message::Ack {
+                acknowledger,
+                message,
+                ..
+            } => {
+                self.acknowledge(message, acknowledger);
+                Vec::new()
+            }
+        };
+        self.try_deliver();
+        Outgoing {
+            clock: self.timestamp,
+            messages,
+        }
+    }
+
+    fn acknowledge(&mut self, order: Order, node: NodeId) {
+        self.acks.entry(order).or_default().insert(node);
+    }
+
+    /// Delivers the pending payloads, in order, as long as the first one has been received
+    /// by every node.
+    fn try_deliver(&mut self) {
+        while let Some(entry) = self.pending.first_entry() {
+            let order = *entry.key();
+            if self.acks.get(&order).map_or(0, HashSet::len) < self.members.len() {
+                break;
+            }
+            let (sender, payload) = entry.remove();
+            self.acks.remove(&order);
+            (self.deliver)(&sender, payload);
+        }
+    }
+
+    fn to_others(&self, message: Message<T>) -> Vec<(NodeId, Message<T>)> {
+        self.members
+            .iter()
+            .filter(|n| **n != self.id)
+            .map(|n| (n.clone(), message.clone()))
+            .collect()
+    }
+}
+
+impl<T: Clone + Serialize + DeserializeOwned> TotalOrderBroadcast<T> {
+    /// This node of the system, resuming the Lamport clock persisted by a previous run if any.
+    pub fn on<C: Channel>(
+        config: &NodeConfig<C>,
+        deliver: impl FnMut(&NodeId, T) + Send + 'static,
+    ) -> Result<Self, NodeError> {
+        let mut node = Self::new(config.id.clone(), config.members.clone(), deliver);
+        node.timestamp = config.load_clock()?;
+        Ok(node)
+    }
+
+    /// Broadcasts the payload to the neighbours, see [`TotalOrderBroadcast::broadcast`].
+    pub fn send<C: Channel>(
+        &mut self,
+        payload: T,
+        config: &NodeConfig<C>,
+    ) -> Result<(), NodeError> {
+        let outgoing = self.broadcast(payload);
+        config.save_clock(self.timestamp)?;
+        dispatch(outgoing, config)
+    }
+
+    /// Handles a message received from a neighbour, then sends the acknowledgements.
+    pub fn receive<C: Channel>(
+        &mut self,
+        message: Message<T>,
+        config: &NodeConfig<C>,
+    ) -> Result<(), NodeError> {
+        config.observe(message.clock());
+        config.record(
+            message.sender(),
+            Direction::Received,
+            message.kind(),
+            self.timestamp,
+        )?;
+        let outgoing = self.handle(message);
+        config.save_clock(self.timestamp)?;
+        dispatch(outgoing, config)
+    }
+}
@@ -0,0 +1,255 @@
+use std::{
+    collections::VecDeque,
+    fmt::Debug,
+    io::{self, Read, Write},
+    net::{Shutdown, TcpStream},
+    os::fd::{AsRawFd, RawFd},
+    sync::{Arc, Condvar, Mutex},
+    time::Duration,
+};
+
+use crate::handshake::PeerAddr;
+
+/// A bidirectional byte stream between two nodes.
+///
+/// [`Stream`] is the transport used between real nodes, while [`MemoryChannel`] allows
+/// algorithms to be driven within a single process, without any socket.
+pub trait Channel: Read + Write + Debug + Send + Sync + Sized {
+    /// Creates a new handle to the same underlying channel.
+    fn try_clone(&self) -> io::Result<Self>;
+
+    /// Closes both directions of the channel, for every handle to it.
+    ///
+    /// Nothing needs closing by default.
+    fn shutdown(&self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Bounds how long a write may block, forever when `None`.
+    ///
+    /// Writes never block by default.
+    fn set_write_timeout(&self, _timeout: Option<Duration>) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Bounds how long a read may block, forever when `None`.
+    ///
+    /// Reads are never bounded by default.
+    fn set_read_timeout(&self, _timeout: Option<Duration>) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Channel for TcpStream {
+    fn try_clone(&self) -> io::Result<Self> {
+        TcpStream::try_clone(self)
+    }
+
+    fn shutdown(&self) -> io::Result<()> {
+        TcpStream::shutdown(self, Shutdown::Both)
+    }
+
+    fn set_write_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        TcpStream::set_write_timeout(self, timeout)
+    }
+
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        TcpStream::set_read_timeout(self, timeout)
+    }
+}
+
+/// The stream to a neighbour, whichever transport [`configure`](crate::configure) connected
+/// it over.
+#[derive(Debug)]
+pub enum Stream {
+    Tcp(TcpStream),
+    /// A UNIX domain socket, for neighbours sharing the host of the node.
+    #[cfg(all(unix, feature = "unix-socket"))]
+    Unix(std::os::unix::net::UnixStream),
+}
+
+impl Stream {
+    /// Disables Nagle's algorithm on TCP streams, UNIX domain sockets having none.
+    pub fn set_nodelay(&self, nodelay: bool) -> io::Result<()> {
+        match self {
+            Stream::Tcp(stream) => stream.set_nodelay(nodelay),
+            #[cfg(all(unix, feature = "unix-socket"))]
+            Stream::Unix(_) => Ok(()),
+        }
+    }
+
+    /// Whether Nagle's algorithm is disabled, as it always is for UNIX domain sockets.
+    pub fn nodelay(&self) -> io::Result<bool> {
+        match self {
+            Stream::Tcp(stream) => stream.nodelay(),
+            #[cfg(all(unix, feature = "unix-socket"))]
+            Stream::Unix(_) => Ok(true),
+        }
+    }
+
+    /// Address of the neighbour at the other end of the stream.
+    pub fn peer_addr(&self) -> io::Result<PeerAddr> {
+        match self {
+            Stream::Tcp(stream) => stream.peer_addr().map(Into::into),
+            #[cfg(all(unix, feature = "unix-socket"))]
+            // Sockets connecting to a listener are seldom bound to a path of their own, the
+            // stream being then told by the path of the listener
+            Stream::Unix(stream) => {
+                let (peer, local) = (stream.peer_addr()?, stream.local_addr()?);
+                let path = peer.as_pathname().or(local.as_pathname());
+                Ok(PeerAddr::Unix(path.map(Into::into).unwrap_or_default()))
+            }
+        }
+    }
+}
+
+impl From<TcpStream> for Stream {
+    fn from(stream: TcpStream) -> Self {
+        Stream::Tcp(stream)
+    }
+}
+
+#[cfg(all(unix, feature = "unix-socket"))]
+impl From<std::os::unix::net::UnixStream> for Stream {
+    fn from(stream: std::os::unix::net::UnixStream) -> Self {
+        Stream::Unix(stream)
+    }
+}
+
+/// Forwards a call to the stream, whichever its transport.
+macro_rules! on_stream {
+    ($stream:expr, $s:ident => $call:expr) => {
+        match $stream {
+            Stream::Tcp($s) => $call,
+            #[cfg(all(unix, feature = "unix-socket"))]
+            Stream::Unix($s) => $call,
+        }
+    };
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        on_stream!(self, s => s.read(buf))
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        on_stream!(self, s => s.write(buf))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        on_stream!(self, s => s.flush())
+    }
+}
+
+impl AsRawFd for Stream {
+    fn as_raw_fd(&self) -> RawFd {
+        on_stream!(self, s => s.as_raw_fd())
+    }
+}
+
+impl Channel for Stream {
+    fn try_clone(&self) -> io::Result<Self> {
+        on_stream!(self, s => Channel::try_clone(s).map(Into::into))
+    }
+
+    fn shutdown(&self) -> io::Result<()> {
+        on_stream!(self, s => Channel::shutdown(s))
+    }
+
+    fn set_write_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        on_stream!(self, s => Channel::set_write_timeout(s, timeout))
+    }
+
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        on_stream!(self, s => Channel::set_read_timeout(s, timeout))
+    }
+}
+
+/// One direction of a [`MemoryChannel`].
+#[derive(Debug, Default)]
+struct Pipe {
+    bytes: Mutex<VecDeque<u8>>,
+    available: Condvar,
+}
+
+/// An in-process [`Channel`], backed by a pair of shared buffers.
+///
+/// Reads block until some bytes have been written by the other end.
+///
+/// # Examples
+///
+/// ```
+/// # use renraku_node::channel::MemoryChannel;
+/// use std::io::{Read, Write};
+///
+/// let (mut a, mut b) = MemoryChannel::pair();
+/// a.write_all(b"ping").unwrap();
+///
+/// let mut buf = [0; 4];
+/// b.read_exact(&mut buf).unwrap();
+/// assert_eq!(&buf, b"ping");
+/// ```
+#[derive(Debug, Clone)]
+pub struct MemoryChannel {
+    incoming: Arc<Pipe>,
+    outgoing: Arc<Pipe>,
+}
+
+impl MemoryChannel {
+    /// Creates both ends of a channel.
+    pub fn pair() -> (Self, Self) {
+        let (first, second) = (Arc::new(Pipe::default()), Arc::new(Pipe::default()));
+        (
+            Self {
+                incoming: first.clone(),
+                outgoing: second.clone(),
+            },
+            Self {
+                incoming: second,
+                outgoing: first,
+            },
+        )
+    }
+
+    /// Number of bytes written by the other end that have not been read yet.
+    pub fn pending(&self) -> usize {
+        self.incoming.bytes.lock().unwrap().len()
+    }
+}
+
+impl Read for MemoryChannel {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let mut bytes = self.incoming.bytes.lock().unwrap();
+        while bytes.is_empty() {
+            bytes = self.incoming.available.wait(bytes).unwrap();
+        }
+        let read = buf.len().min(bytes.len());
+        for (slot, byte) in buf.iter_mut().zip(bytes.drain(..read)) {
+            *slot = byte;
+        }
+        Ok(read)
+    }
+}
+
+impl Write for MemoryChannel {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.outgoing.bytes.lock().unwrap().extend(buf);
+        self.outgoing.available.notify_all();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Channel for MemoryChannel {
+    fn try_clone(&self) -> io::Result<Self> {
+        Ok(self.clone())
+    }
+}
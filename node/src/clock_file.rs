@@ -0,0 +1,60 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::error::NodeError;
+
+/// File persisting the Lamport clock of a node, so that a restarted node does not reuse
+/// timestamps it already issued.
+///
+/// # Examples
+///
+/// ```
+/// # use renraku_node::clock_file::ClockFile;
+///
+/// let path = std::env::temp_dir().join(format!("renraku-clock-doc-{}", std::process::id()));
+/// let file = ClockFile::new(&path);
+/// assert_eq!(file.load().unwrap(), 0);
+/// file.save(42).unwrap();
+/// assert_eq!(ClockFile::new(&path).load().unwrap(), 42);
+/// std::fs::remove_file(&path).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct ClockFile {
+    path: PathBuf,
+}
+
+impl ClockFile {
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Reads the persisted clock, `0` if none has been persisted yet.
+    pub fn load(&self) -> Result<usize, NodeError> {
+        let error = |e| NodeError::ClockFile(self.path.clone(), e);
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(error(e)),
+        };
+        content.trim().parse().map_err(|_| {
+            error(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{content:?} is not a clock"),
+            ))
+        })
+    }
+
+    /// Persists the clock, replacing the previous value at once so that a crash never leaves
+    /// a partially written file.
+    pub fn save(&self, clock: usize) -> Result<(), NodeError> {
+        let error = |e| NodeError::ClockFile(self.path.clone(), e);
+        let mut temporary = self.path.clone().into_os_string();
+        temporary.push(".tmp");
+        fs::write(&temporary, clock.to_string()).map_err(error)?;
+        fs::rename(&temporary, &self.path).map_err(error)
+    }
+}
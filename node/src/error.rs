@@ -0,0 +1,108 @@
+use std::{io, net::SocketAddr, path::PathBuf, time::Duration};
+
+use renraku_shared::{codec::CodecError, control::ControlMessage, NodeId};
+use thiserror::Error;
+
+use crate::handshake::{self, PeerAddr};
+
+/// Errors raised while setting up a node or exchanging with its neighbours.
+#[derive(Error, Debug)]
+pub enum NodeError {
+    #[error("Binding the {0}")]
+    Bind(&'static str, #[source] io::Error),
+    #[error("Opening event log {0:?}")]
+    EventLog(PathBuf, #[source] io::Error),
+    #[error("Accessing the clock file {0:?}")]
+    ClockFile(PathBuf, #[source] io::Error),
+    #[error("Accessing the recording in {0:?}")]
+    Recording(PathBuf, #[source] io::Error),
+    #[error("{step} controller {controller}")]
+    Controller {
+        step: &'static str,
+        controller: String,
+        #[source]
+        source: io::Error,
+    },
+    #[error("None of the {addresses} addresses of controller {controller} acknowledged the hello")]
+    Unregistered {
+        controller: String,
+        addresses: usize,
+    },
+    #[error("Deserializing the {0}")]
+    Deserialize(&'static str, #[source] bincode::Error),
+    #[error("Decoding the {0}")]
+    Decode(&'static str, #[source] CodecError),
+    #[error("Encoding a message")]
+    Encode(#[source] CodecError),
+    #[error("Expected {expected} from the controller, received {received:?}")]
+    UnexpectedControl {
+        expected: &'static str,
+        received: Box<ControlMessage>,
+    },
+    #[error("This node has {neighbours} neighbours, which exceeds the maximum of {max} (see --max-neighbours)")]
+    FanOut { neighbours: usize, max: usize },
+    #[error("Accepting connection {0}/{1}")]
+    Accept(usize, usize, #[source] io::Error),
+    #[error("Connecting to neighbour at {0}")]
+    PeerConnect(PeerAddr, #[source] io::Error),
+    #[error("Exchanging ids with neighbour {0}")]
+    Handshake(PeerAddr, #[source] bincode::Error),
+    #[error("Expected the id of the neighbour at {addr}, received {received:?}")]
+    UnexpectedHandshake {
+        addr: PeerAddr,
+        received: Box<handshake::Message>,
+    },
+    #[error("Expected the neighbour at {addr} to acknowledge {expected:?}, received {received:?}")]
+    Unacknowledged {
+        addr: PeerAddr,
+        expected: NodeId,
+        received: Box<handshake::Message>,
+    },
+    #[error("Expected {expected:?} at {addr}, {actual:?} answered")]
+    UnexpectedPeer {
+        addr: PeerAddr,
+        expected: NodeId,
+        actual: NodeId,
+    },
+    #[error("The neighbour at {0} claims the id of this node, {1:?}")]
+    SelfConnection(PeerAddr, NodeId),
+    #[error("The coordinator announced {expected} neighbours, the node is connected to {actual}")]
+    NeighbourCount { expected: usize, actual: usize },
+    #[error("{0:?} is not a neighbour")]
+    UnknownNeighbour(NodeId),
+    #[error("Received a message of {length} bytes, while at most {max} are accepted (see --max-message-bytes)")]
+    MessageTooLarge { length: usize, max: usize },
+    #[error("The neighbour has not read anything for {0:?}, giving up sending to it (see --send-timeout-ms)")]
+    SendTimeout(Duration),
+    #[error("Selecting among the streams of {0:?}, {1} times in a row")]
+    Select(Vec<NodeId>, usize, #[source] io::Error),
+    #[error("Receiving from {0:?}")]
+    Receive(NodeId, #[source] Box<NodeError>),
+    #[error("A write to the neighbour failed earlier, it is no longer written to: {0}")]
+    Broken(String),
+    #[error("Metrics cannot be served on {0}, the node was built without the metrics feature")]
+    MetricsDisabled(SocketAddr),
+    #[error("Neighbours cannot be reached within {0:?}, the node was built without the unix-socket feature")]
+    UnixSocketDisabled(PathBuf),
+    #[error("Serializing a message")]
+    Serialize(#[from] bincode::Error),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+impl NodeError {
+    /// Returns whether the error tells the stream of a neighbour closed or was reset, rather
+    /// than the neighbour sending something unexpected over it.
+    pub fn is_disconnection(&self) -> bool {
+        matches!(
+            self,
+            NodeError::Io(e) if matches!(
+                e.kind(),
+                io::ErrorKind::UnexpectedEof
+                    | io::ErrorKind::ConnectionReset
+                    | io::ErrorKind::ConnectionAborted
+                    | io::ErrorKind::BrokenPipe
+            )
+        )
+    }
+}
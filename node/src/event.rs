@@ -0,0 +1,90 @@
+use std::{
+    fmt,
+    fs::OpenOptions,
+    io::{self, BufWriter, Write},
+    path::Path,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use renraku_shared::NodeId;
+use serde::{Deserialize, Serialize};
+
+use crate::error::NodeError;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Sent,
+    Received,
+}
+
+/// A message that went through a node, as written in the event log.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Event {
+    /// Microseconds elapsed since the UNIX epoch.
+    pub timestamp: u128,
+    pub node: usize,
+    pub peer: usize,
+    pub direction: Direction,
+    pub message: String,
+    /// Lamport clock of the node when the message went through it.
+    pub clock: usize,
+}
+
+/// Appends an NDJSON line for each message sent or received by a node.
+///
+/// Merging the logs of every node reconstructs the whole timeline of the system.
+pub struct EventLog {
+    writer: Mutex<Box<dyn Write + Send>>,
+}
+
+impl EventLog {
+    /// Opens the log file, appending to it if it already exists.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, NodeError> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| NodeError::EventLog(path.as_ref().to_path_buf(), e))?;
+        Ok(Self::new(BufWriter::new(file)))
+    }
+
+    pub fn new(writer: impl Write + Send + 'static) -> Self {
+        Self {
+            writer: Mutex::new(Box::new(writer)),
+        }
+    }
+
+    pub fn record(
+        &self,
+        node: &NodeId,
+        peer: &NodeId,
+        direction: Direction,
+        message: &str,
+        clock: usize,
+    ) -> Result<(), NodeError> {
+        let event = Event {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_micros(),
+            node: node.0,
+            peer: peer.0,
+            direction,
+            message: message.to_string(),
+            clock,
+        };
+        let mut writer = self.writer.lock().unwrap();
+        serde_json::to_writer(&mut *writer, &event).map_err(io::Error::from)?;
+        writer.write_all(b"\n")?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+impl fmt::Debug for EventLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventLog").finish_non_exhaustive()
+    }
+}
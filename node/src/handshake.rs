@@ -0,0 +1,142 @@
+use std::{
+    fmt,
+    io::{Read, Write},
+    net::SocketAddr,
+    path::PathBuf,
+    time::Duration,
+};
+
+use renraku_shared::{
+    frame::{read_frame, write_frame},
+    NodeId,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{channel::Channel, error::NodeError};
+
+/// How long a neighbour is waited for at each step of the handshake, so that a peer that
+/// stops answering halfway does not hang the setup.
+pub const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Messages exchanged with a neighbour while connecting to it, before the algorithm starts.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum Message {
+    /// Identifier of the sending node.
+    Id(NodeId),
+    /// Confirms the sending node stored the connection under this identifier, that of the
+    /// recipient.
+    Ack(NodeId),
+}
+
+/// Address a neighbour is reached at, whatever the transport.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PeerAddr {
+    Inet(SocketAddr),
+    /// Path of a UNIX domain socket.
+    Unix(PathBuf),
+}
+
+impl fmt::Display for PeerAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PeerAddr::Inet(addr) => write!(f, "{addr}"),
+            PeerAddr::Unix(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+impl From<SocketAddr> for PeerAddr {
+    fn from(addr: SocketAddr) -> Self {
+        PeerAddr::Inet(addr)
+    }
+}
+
+impl PartialEq<SocketAddr> for PeerAddr {
+    fn eq(&self, other: &SocketAddr) -> bool {
+        matches!(self, PeerAddr::Inet(addr) if addr == other)
+    }
+}
+
+fn send<S: Write>(stream: &mut S, message: &Message) -> bincode::Result<()> {
+    write_frame(stream, &bincode::serialize(message)?)?;
+    Ok(stream.flush()?)
+}
+
+fn receive<S: Read>(stream: &mut S) -> bincode::Result<Message> {
+    bincode::deserialize(&read_frame(stream)?)
+}
+
+/// Exchanges identifiers with the neighbour at `addr`, returning its own.
+///
+/// The dialing side sends its identifier first and the other answers with its own. Each side
+/// then acknowledges the identifier it stored the other under, the dialing side first, so that
+/// neither starts the algorithm before both agree on the mapping. Messages are framed, the
+/// first message of the algorithm starting right after them.
+///
+/// Each message is waited for at most [`HANDSHAKE_TIMEOUT`], reads being unbounded again
+/// once the handshake succeeded.
+///
+/// # Examples
+///
+/// ```
+/// # use renraku_node::{channel::MemoryChannel, handshake::exchange};
+/// # use renraku_shared::NodeId;
+/// use std::{net::SocketAddr, thread};
+///
+/// let (mut dialing, mut accepting) = MemoryChannel::pair();
+/// let addr: SocketAddr = "127.0.0.1:4000".parse().unwrap();
+/// let acceptor = thread::spawn(move || exchange(&mut accepting, addr, &NodeId(2), false));
+/// assert_eq!(exchange(&mut dialing, addr, &NodeId(1), true).unwrap(), NodeId(2));
+/// assert_eq!(acceptor.join().unwrap().unwrap(), NodeId(1));
+/// ```
+pub fn exchange<S: Channel>(
+    stream: &mut S,
+    addr: impl Into<PeerAddr>,
+    id: &NodeId,
+    dialing: bool,
+) -> Result<NodeId, NodeError> {
+    let addr = addr.into();
+    let error = |e| NodeError::Handshake(addr.clone(), e);
+    stream
+        .set_read_timeout(Some(HANDSHAKE_TIMEOUT))
+        .map_err(|e| error(e.into()))?;
+    if dialing {
+        send(stream, &Message::Id(id.clone())).map_err(error)?;
+    }
+    let peer = match receive(stream).map_err(error)? {
+        Message::Id(peer) => peer,
+        other => {
+            return Err(NodeError::UnexpectedHandshake {
+                addr,
+                received: Box::new(other),
+            })
+        }
+    };
+    // A node connected to itself would send messages to itself
+    if &peer == id {
+        return Err(NodeError::SelfConnection(addr, peer));
+    }
+    if !dialing {
+        send(stream, &Message::Id(id.clone())).map_err(error)?;
+    }
+
+    let ack = Message::Ack(peer.clone());
+    if dialing {
+        send(stream, &ack).map_err(error)?;
+    }
+    match receive(stream).map_err(error)? {
+        Message::Ack(acked) if &acked == id => {}
+        other => {
+            return Err(NodeError::Unacknowledged {
+                addr,
+                expected: id.clone(),
+                received: Box::new(other),
+            })
+        }
+    }
+    if !dialing {
+        send(stream, &ack).map_err(error)?;
+    }
+    stream.set_read_timeout(None).map_err(|e| error(e.into()))?;
+    Ok(peer)
+}
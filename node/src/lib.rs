@@ -1,11 +1,47 @@
 use std::{
     collections::HashMap,
-    io::{Read, Write},
-    net::{SocketAddr, TcpListener, TcpStream, UdpSocket},
+    io::{self, BufReader, BufWriter, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs, UdpSocket},
+    path::PathBuf,
+    sync::{
+        mpsc::{self, SyncSender},
+        Arc, Mutex, OnceLock, RwLock,
+    },
+    thread::{self, sleep, JoinHandle},
+    time::Duration,
 };
 
-use color_eyre::eyre::Result;
-use renraku_shared::NodeId;
+use channel::{Channel, Stream};
+use clock_file::ClockFile;
+use event::{Direction, EventLog};
+use handshake::PeerAddr;
+use metrics::Metrics;
+use record::{LocalEvent, Recorder};
+use renraku_shared::{
+    clock::VectorClock,
+    codec::{Codec, Format},
+    control::ControlMessage,
+    frame::{frame, peek_frame_len, read_frame_len, HEADER_LEN},
+    NodeId, Placement,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use tracing::{debug, info, warn};
+
+pub mod broadcast;
+pub mod channel;
+pub mod clock_file;
+pub mod error;
+pub mod event;
+pub mod handshake;
+pub mod metrics;
+pub mod record;
+pub mod runtime;
+pub mod termination;
+pub mod trace;
+#[cfg(all(unix, feature = "unix-socket"))]
+pub mod unix;
+
+pub use error::NodeError;
 
 /// Represents the arguments required to configure a node.
 ///
@@ -15,66 +51,1400 @@ use renraku_shared::NodeId;
 /// # Examples
 ///
 /// ```
-/// # use renraku_node::NodeArguments;
+/// # use renraku_node::{NodeArguments, DEFAULT_MAX_MESSAGE_BYTES};
 ///
 /// let args = NodeArguments {
-///     controller: "localhost:3000".to_string(),
+///     controller: "coordinator.local:3000".to_string(),
+///     capacity: 4,
+///     ..NodeArguments::default()
 /// };
+/// assert_eq!(args.max_message_bytes, DEFAULT_MAX_MESSAGE_BYTES);
 /// ```
 #[derive(clap::Parser, Debug, Clone)]
 #[command(author, version, about, long_about = None)]
 pub struct NodeArguments {
-    #[arg(short, long, default_value_t = String::from("localhost:3000"))]
+    /// Address of the controller, as `host:port`, the host being resolved when registering.
+    #[arg(short, long, default_value_t = String::from("localhost:3000"), value_parser = parse_controller)]
     pub controller: String,
+    /// Maximum number of neighbours this node accepts, each one holding a socket.
+    #[arg(long, default_value_t = 256)]
+    pub max_neighbours: usize,
+    /// Appends an NDJSON record of every message sent or received to this file.
+    #[arg(long, value_name = "FILE")]
+    pub event_log: Option<PathBuf>,
+    /// Capacity of the machine relative to the other nodes, the controller giving the most
+    /// connected vertices to the nodes with the highest capacity when balancing.
+    #[arg(long, default_value_t = 1)]
+    pub capacity: usize,
+    /// Asks the controller for this identifier, so that the node runs the same vertex of the
+    /// graph from one run to the next. The controller falls back to another one when it is
+    /// taken or not part of the graph.
+    #[arg(long, value_name = "ID")]
+    pub desired_id: Option<usize>,
+    /// Largest message accepted from the controller or a neighbour, bigger ones being rejected
+    /// before anything is allocated for them.
+    #[arg(long, default_value_t = DEFAULT_MAX_MESSAGE_BYTES)]
+    pub max_message_bytes: usize,
+    /// How long each address of the controller is given to acknowledge the hello, before
+    /// trying the next one.
+    #[arg(long, default_value_t = 1000)]
+    pub controller_timeout_ms: u64,
+    /// Gives up sending a message to a neighbour that has not read anything for this long,
+    /// rather than blocking until it does.
+    #[arg(long, value_name = "MS")]
+    pub send_timeout_ms: Option<u64>,
+    /// How long each attempt to connect to a neighbour is given, the connection being tried
+    /// again a few times in case the listener of the neighbour is not ready yet.
+    #[arg(long, default_value_t = 1000)]
+    pub peer_connect_timeout_ms: u64,
+    /// Address the neighbours are told to connect to, rather than the one the node listens
+    /// on, for nodes behind a NAT or a port mapping such as those of Docker or Kubernetes.
+    #[arg(long, value_name = "ADDR", env = "RENRAKU_ADVERTISE_ADDR")]
+    pub advertise_addr: Option<SocketAddr>,
+    /// Keeps Nagle's algorithm enabled on the streams to the neighbours, rather than sending
+    /// each message right away (`TCP_NODELAY`).
+    #[arg(long)]
+    pub nagle: bool,
+    /// Stamps the messages of the algorithm with a vector clock, for causal ordering.
+    #[arg(long)]
+    pub vector_clock: bool,
+    /// Records every frame received from the neighbours into this directory, to be replayed.
+    #[arg(long, value_name = "DIR")]
+    pub record_dir: Option<PathBuf>,
+    /// Persists the Lamport clock of the node into this file whenever it moves forward, and
+    /// starts from the persisted value, so that a restarted node keeps issuing fresh timestamps.
+    #[arg(long, value_name = "FILE")]
+    pub clock_file: Option<PathBuf>,
+    /// Leaves the system once the critical section has been entered and left this many times,
+    /// rather than running forever. The node still answers its neighbours until each of them
+    /// left as well.
+    #[arg(long, value_name = "N")]
+    pub max_cycles: Option<usize>,
+    /// Only connects to the controller and the neighbours, prints the configuration then exits.
+    #[arg(long)]
+    pub controller_probe: bool,
+    /// Serves the metrics of the node in the Prometheus text format over HTTP on this address.
+    /// Only available when built with the `metrics` feature.
+    #[arg(long, value_name = "ADDR")]
+    pub metrics_addr: Option<SocketAddr>,
+    /// Tells the controller whenever this node enters or leaves a critical section, for it to
+    /// check that mutual exclusion holds. Implies `--vector-clock`, which orders what nodes
+    /// tell it.
+    #[arg(long)]
+    pub witness: bool,
+    /// Keeps running once the stream of a neighbour closed rather than stopping, waiting for
+    /// the neighbour to restart with the same `--id`: a controller started with `--resident`
+    /// tells where it listens again. Messages meanwhile sent to it are lost. Only nodes
+    /// running Lamport's or Ricart-Agrawala's algorithm connect to it again.
+    #[arg(long)]
+    pub rejoin: bool,
+    /// Connects to the neighbours over UNIX domain sockets within this directory rather than
+    /// over TCP, sparing the loopback overhead to nodes sharing a host. Only available when
+    /// built with the `unix-socket` feature.
+    #[arg(long, value_name = "DIR")]
+    pub socket_dir: Option<PathBuf>,
+    /// Codec of the messages exchanged with the neighbours, `bincode` or `json`, which every
+    /// node must share. The controller and the handshake always speak bincode.
+    #[arg(long, default_value_t = Format::Bincode)]
+    pub codec: Format,
+}
+
+impl Default for NodeArguments {
+    /// The arguments of a node started without any, as parsed from the command line.
+    fn default() -> Self {
+        <Self as clap::Parser>::parse_from(["node"])
+    }
+}
+
+/// Largest message accepted from a neighbour, unless configured otherwise.
+pub const DEFAULT_MAX_MESSAGE_BYTES: usize = 1 << 20;
+
+/// How many messages may be queued for the writer thread of a neighbour, queuing more
+/// waiting for it to write some.
+pub const OUTBOX_CAPACITY: usize = 1024;
+
+/// A connection to a neighbouring node.
+///
+/// Writes go through a [`BufWriter`] so a logical message is written with as few
+/// syscalls as possible, and reads through a [`BufReader`] so that frames sent together are
+/// read at once. Once [`Neighbour::start_writer`] has been called, messages given to
+/// [`Neighbour::enqueue`] are written by a dedicated thread, in the order they were queued.
+///
+/// Once a write failed, the channel is closed and the neighbour no longer written to: every
+/// following send fails with [`NodeError::Broken`].
+#[derive(Debug)]
+pub struct Neighbour<C: Channel = Stream> {
+    channel: C,
+    reader: Mutex<BufReader<C>>,
+    writer: Arc<Mutex<BufWriter<C>>>,
+    outbox: Mutex<Option<SyncSender<Vec<u8>>>>,
+    /// Thread writing the queued messages, once started.
+    writer_thread: Mutex<Option<JoinHandle<()>>>,
+    max_message_bytes: usize,
+    /// Codec the messages exchanged with the neighbour are encoded with.
+    codec: Format,
+    /// How long a write to the neighbour may block, forever when unset.
+    send_timeout: Option<Duration>,
+    /// Where the frames received from the neighbour are recorded, along with its identifier.
+    recording: OnceLock<(Arc<Recorder>, NodeId)>,
+    /// Why writing to the neighbour failed, if it did.
+    broken: Arc<Mutex<Option<String>>>,
+}
+
+impl<C: Channel> Neighbour<C> {
+    pub fn new(channel: C) -> Result<Self, NodeError> {
+        let reader = Mutex::new(BufReader::new(channel.try_clone()?));
+        let writer = Arc::new(Mutex::new(BufWriter::new(channel.try_clone()?)));
+        Ok(Self {
+            channel,
+            reader,
+            writer,
+            outbox: Mutex::new(None),
+            writer_thread: Mutex::new(None),
+            max_message_bytes: DEFAULT_MAX_MESSAGE_BYTES,
+            codec: Format::default(),
+            send_timeout: None,
+            recording: OnceLock::new(),
+            broken: Arc::default(),
+        })
+    }
+
+    /// Sets the largest message accepted from the neighbour.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use renraku_node::{channel::MemoryChannel, Neighbour, NodeError};
+    /// use std::io::Write;
+    ///
+    /// let (local, mut remote) = MemoryChannel::pair();
+    /// let neighbour = Neighbour::new(local).unwrap().with_max_message_bytes(64);
+    ///
+    /// // A length prefix announcing 4 GiB is rejected without waiting for the payload
+    /// remote.write_all(&u32::MAX.to_le_bytes()).unwrap();
+    /// assert!(matches!(
+    ///     neighbour.receive_frame(),
+    ///     Err(NodeError::MessageTooLarge { length: 4294967295, max: 64 })
+    /// ));
+    /// ```
+    pub fn with_max_message_bytes(mut self, max: usize) -> Self {
+        self.max_message_bytes = max;
+        self
+    }
+
+    /// Encodes the messages exchanged with the neighbour with the codec, which it must use as
+    /// well.
+    pub fn with_codec(mut self, codec: Format) -> Self {
+        self.codec = codec;
+        self
+    }
+
+    /// Gives up writing to the neighbour once a write blocked for `timeout`, returning
+    /// [`NodeError::SendTimeout`], rather than blocking forever when it does not read.
+    pub fn with_send_timeout(mut self, timeout: Option<Duration>) -> Result<Self, NodeError> {
+        self.channel().set_write_timeout(timeout)?;
+        self.send_timeout = timeout;
+        Ok(self)
+    }
+
+    /// Records each frame received from the neighbour, identified as `peer`.
+    pub fn recorded_by(self, recorder: Arc<Recorder>, peer: NodeId) -> Self {
+        self.record_into(recorder, peer);
+        self
+    }
+
+    /// Records each frame received from the neighbour from now on, unless it is recorded
+    /// already.
+    fn record_into(&self, recorder: Arc<Recorder>, peer: NodeId) {
+        // A neighbour is recorded once, by the recorder of its node
+        let _ = self.recording.set((recorder, peer));
+    }
+
+    /// The underlying channel, for instance to wait for it to be readable.
+    pub fn channel(&self) -> &C {
+        &self.channel
+    }
+
+    /// Writes the whole buffer to the neighbour, then flushes it.
+    pub fn send(&self, bytes: &[u8]) -> Result<(), NodeError> {
+        self.check_broken()?;
+        let mut writer = self.writer.lock().unwrap();
+        writer
+            .write_all(bytes)
+            .and_then(|_| writer.flush())
+            .map_err(|e| {
+                give_up(
+                    &self.broken,
+                    writer.get_ref(),
+                    send_error(self.send_timeout, e),
+                )
+            })
+    }
+
+    /// Returns whether a write to the neighbour failed, nothing being written to it anymore.
+    pub fn is_broken(&self) -> bool {
+        self.broken.lock().unwrap().is_some()
+    }
+
+    fn check_broken(&self) -> Result<(), NodeError> {
+        match self.broken.lock().unwrap().as_ref() {
+            Some(reason) => Err(NodeError::Broken(reason.clone())),
+            None => Ok(()),
+        }
+    }
+
+    /// Starts a thread writing the messages queued with [`Neighbour::enqueue`], so that queuing
+    /// them does not wait for the neighbour to read, unless [`OUTBOX_CAPACITY`] messages are
+    /// already queued.
+    ///
+    /// The thread stops once the neighbour is dropped, or as soon as a write fails.
+    pub fn start_writer(&self)
+    where
+        C: 'static,
+    {
+        let (sender, receiver) = mpsc::sync_channel::<Vec<u8>>(OUTBOX_CAPACITY);
+        let writer = self.writer.clone();
+        let timeout = self.send_timeout;
+        let broken = self.broken.clone();
+        let handle = thread::spawn(move || {
+            for bytes in receiver {
+                let mut writer = writer.lock().unwrap();
+                if let Err(e) = writer.write_all(&bytes).and_then(|_| writer.flush()) {
+                    give_up(&broken, writer.get_ref(), send_error(timeout, e));
+                    // Closing the queue fails the messages queued from now on
+                    return;
+                }
+            }
+        });
+        *self.outbox.lock().unwrap() = Some(sender);
+        *self.writer_thread.lock().unwrap() = Some(handle);
+    }
+
+    /// Queues the bytes for the writer thread, or writes them right away when it has not been
+    /// started.
+    ///
+    /// Fails with [`NodeError::Broken`] once the writer thread failed to write.
+    pub fn enqueue(&self, bytes: Vec<u8>) -> Result<(), NodeError> {
+        self.check_broken()?;
+        let outbox = self.outbox.lock().unwrap().clone();
+        match outbox {
+            Some(outbox) => outbox.send(bytes).or_else(|_| {
+                self.check_broken()?;
+                Err(
+                    io::Error::new(io::ErrorKind::BrokenPipe, "the writer thread has stopped")
+                        .into(),
+                )
+            }),
+            None => self.send(&bytes),
+        }
+    }
+
+    /// Writes out whatever is still buffered for the neighbour.
+    pub fn flush(&self) -> Result<(), NodeError> {
+        Ok(self.writer.lock().unwrap().flush()?)
+    }
+
+    /// Writes out whatever is still buffered, then closes the channel in both directions.
+    ///
+    /// The writer thread, if started, is stopped once it wrote every message queued so far.
+    /// Messages enqueued afterwards are written right away. Nothing is written to a broken
+    /// neighbour, its channel being closed already.
+    pub fn shutdown(&self) -> Result<(), NodeError> {
+        // The writer thread stops once the queue is closed and drained
+        self.outbox.lock().unwrap().take();
+        if let Some(handle) = self.writer_thread.lock().unwrap().take() {
+            handle
+                .join()
+                .map_err(|_| io::Error::other("the writer thread panicked"))?;
+        }
+        if self.is_broken() {
+            return Ok(());
+        }
+        self.flush()?;
+        Ok(self.channel().shutdown()?)
+    }
+
+    /// Reads the bytes sent by the neighbour into the buffer, returning how many were read.
+    pub fn receive(&self, buf: &mut [u8]) -> Result<usize, NodeError> {
+        Ok(self.reader.lock().unwrap().read(buf)?)
+    }
+
+    /// Reads the next whole frame sent by the neighbour, returning its payload.
+    ///
+    /// Frames announcing more bytes than accepted are rejected with
+    /// [`NodeError::MessageTooLarge`], the stream being left in an unknown state.
+    pub fn receive_frame(&self) -> Result<Vec<u8>, NodeError> {
+        let mut reader = self.reader.lock().unwrap();
+        let length = read_frame_len(&mut *reader)?;
+        if length > self.max_message_bytes {
+            return Err(NodeError::MessageTooLarge {
+                length,
+                max: self.max_message_bytes,
+            });
+        }
+        let mut payload = vec![0; length];
+        reader.read_exact(&mut payload)?;
+        if let Some((recorder, peer)) = self.recording.get() {
+            recorder.record(peer, &payload)?;
+        }
+        Ok(payload)
+    }
+
+    /// Returns whether a whole frame has already been read from the channel, and can be
+    /// received without waiting for the neighbour.
+    ///
+    /// Waiting for the channel to be readable would not tell about such a frame: whoever
+    /// receives frames as the channel becomes readable must receive these ones first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use renraku_node::{channel::MemoryChannel, Neighbour};
+    /// # use renraku_shared::frame::frame;
+    /// use std::io::Write;
+    ///
+    /// let (local, mut remote) = MemoryChannel::pair();
+    /// let neighbour = Neighbour::new(local).unwrap();
+    /// remote.write_all(&[frame(b"one").unwrap(), frame(b"two").unwrap()].concat()).unwrap();
+    ///
+    /// assert_eq!(neighbour.receive_frame().unwrap(), b"one");
+    /// assert!(neighbour.has_buffered_frame());
+    /// assert_eq!(neighbour.receive_frame().unwrap(), b"two");
+    /// assert!(!neighbour.has_buffered_frame());
+    /// ```
+    pub fn has_buffered_frame(&self) -> bool {
+        let reader = self.reader.lock().unwrap();
+        let buffered = reader.buffer();
+        peek_frame_len(buffered).is_some_and(|length| buffered.len() - HEADER_LEN >= length)
+    }
+
+    /// Frames a message to be sent to the neighbour, encoded with its codec.
+    pub fn encode<T: Serialize + ?Sized>(&self, message: &T) -> Result<Vec<u8>, NodeError> {
+        let payload = self.codec.encode(message).map_err(NodeError::Encode)?;
+        Ok(frame(&payload)?)
+    }
+
+    /// Decodes a frame received from the neighbour with its codec, within the accepted
+    /// message size.
+    pub fn decode<T: DeserializeOwned>(&self, frame: &[u8]) -> Result<T, NodeError> {
+        self.codec
+            .decode(frame, self.max_message_bytes as u64)
+            .map_err(|e| NodeError::Decode("message", e))
+    }
+}
+
+/// The neighbours of a node, indexed by their identifier.
+///
+/// Neighbours may be added while the node runs, when the coordinator tells about a node
+/// joining the system (see [`NodeConfig::admit`]). Each of them is handed out behind an
+/// [`Arc`], so that waiting for some to be readable does not keep others from being added.
+///
+/// # Examples
+///
+/// ```
+/// # use renraku_node::{channel::MemoryChannel, Neighbour, Neighbours};
+/// # use renraku_shared::NodeId;
+/// use std::collections::HashMap;
+///
+/// let (local, _remote) = MemoryChannel::pair();
+/// let neighbours = Neighbours::from(HashMap::from([(NodeId(3), Neighbour::new(local).unwrap())]));
+///
+/// let (local, _remote) = MemoryChannel::pair();
+/// assert!(neighbours.insert(NodeId(1), Neighbour::new(local).unwrap()).is_none());
+/// assert_eq!(neighbours.keys().collect::<Vec<_>>(), [NodeId(1), NodeId(3)]);
+/// assert!(neighbours.get(&NodeId(2)).is_none());
+/// ```
+#[derive(Debug)]
+pub struct Neighbours<C: Channel = Stream>(RwLock<HashMap<NodeId, Arc<Neighbour<C>>>>);
+
+impl<C: Channel> Neighbours<C> {
+    /// Returns the neighbour of the given identifier, if it is one.
+    pub fn get(&self, id: &NodeId) -> Option<Arc<Neighbour<C>>> {
+        self.0.read().unwrap().get(id).cloned()
+    }
+
+    pub fn contains_key(&self, id: &NodeId) -> bool {
+        self.0.read().unwrap().contains_key(id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.read().unwrap().is_empty()
+    }
+
+    /// Returns the identifier of each neighbour, in increasing order.
+    pub fn keys(&self) -> impl Iterator<Item = NodeId> {
+        self.iter().map(|(id, _)| id)
+    }
+
+    /// Returns each neighbour, in increasing order of identifier.
+    pub fn values(&self) -> impl Iterator<Item = Arc<Neighbour<C>>> {
+        self.iter().map(|(_, neighbour)| neighbour)
+    }
+
+    /// Returns each neighbour along with its identifier, in increasing order of identifier.
+    ///
+    /// Neighbours added afterwards are left out.
+    pub fn iter(&self) -> impl Iterator<Item = (NodeId, Arc<Neighbour<C>>)> {
+        let mut neighbours: Vec<_> = self
+            .0
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(id, neighbour)| (id.clone(), neighbour.clone()))
+            .collect();
+        neighbours.sort_by_key(|(id, _)| id.0);
+        neighbours.into_iter()
+    }
+
+    /// Adds a neighbour, returning the one it replaces if there was one.
+    pub fn insert(&self, id: NodeId, neighbour: Neighbour<C>) -> Option<Arc<Neighbour<C>>> {
+        self.0.write().unwrap().insert(id, Arc::new(neighbour))
+    }
+}
+
+impl<C: Channel> From<HashMap<NodeId, Neighbour<C>>> for Neighbours<C> {
+    fn from(neighbours: HashMap<NodeId, Neighbour<C>>) -> Self {
+        Self(RwLock::new(
+            neighbours
+                .into_iter()
+                .map(|(id, neighbour)| (id, Arc::new(neighbour)))
+                .collect(),
+        ))
+    }
+}
+
+/// The configuration of a node, as received from the coordinator.
+#[derive(Debug)]
+pub struct NodeConfig<C: Channel = Stream> {
+    /// Number of nodes within the distributed system.
+    pub node_count: usize,
+    /// Identifier of every node within the distributed system, this one included, in
+    /// increasing order. Nodes joining once this one is configured are only added to its
+    /// neighbours, see [`NodeConfig::admit`].
+    pub members: Vec<NodeId>,
+    /// Identifier assigned to this node by the coordinator.
+    pub id: NodeId,
+    /// Each neighbour of this node, indexed by their identifier.
+    pub neighbours: Neighbours<C>,
+    /// Voting quorum of this node (itself included), used by quorum-based algorithms.
+    pub quorum: Vec<NodeId>,
+    /// Role the graph tags this node with, if any, for topologies mixing several roles.
+    pub role: Option<String>,
+    /// Label of the link to each neighbour the graph labels, for links with properties of
+    /// their own.
+    pub labels: HashMap<NodeId, String>,
+    /// Where this node stands in the directed topology, when configured by a coordinator.
+    pub placement: Option<Placement>,
+    /// Where messages going through this node are recorded, if anywhere.
+    pub event_log: Option<EventLog>,
+    /// Vector clock of this node, if messages are stamped with one.
+    pub vector_clock: Option<Mutex<VectorClock>>,
+    /// Where the Lamport clock of this node is persisted, if anywhere.
+    pub clock_file: Option<ClockFile>,
+    /// Link to the controller this node registered to, if it was configured by one.
+    pub controller: Option<ControllerLink>,
+    /// Counters of this node, if they are served to a scraper.
+    pub metrics: Option<Arc<Metrics>>,
+    /// Where this node is recorded, if anywhere, see [`NodeConfig::recorded_by`].
+    pub recorder: Option<Arc<Recorder>>,
+}
+
+/// Link kept with the controller once a node is configured, over which it is told about the
+/// critical sections of the node.
+#[derive(Debug)]
+pub struct ControllerLink {
+    socket: UdpSocket,
+    /// Whether the controller is told about the critical sections.
+    witness: bool,
+    /// Whether neighbours whose stream closed are waited for, until the controller tells
+    /// they restarted.
+    rejoin: bool,
+    /// How the nodes the controller tells about are connected to.
+    dialer: Dialer,
+}
+
+impl ControllerLink {
+    /// Socket the controller is reached through, readable once it told something.
+    pub fn socket(&self) -> &UdpSocket {
+        &self.socket
+    }
+}
+
+impl<C: Channel> NodeConfig<C> {
+    /// Creates the configuration of a node that is not part of any quorum and logs nothing.
+    ///
+    /// The nodes of the system are numbered from `1` to `node_count`.
+    pub fn new(node_count: usize, id: NodeId, neighbours: HashMap<NodeId, Neighbour<C>>) -> Self {
+        Self {
+            node_count,
+            members: (1..=node_count).map(NodeId).collect(),
+            id,
+            neighbours: neighbours.into(),
+            quorum: Vec::new(),
+            role: None,
+            labels: HashMap::new(),
+            placement: None,
+            event_log: None,
+            vector_clock: None,
+            clock_file: None,
+            controller: None,
+            metrics: None,
+            recorder: None,
+        }
+    }
+
+    /// Returns whether a neighbour whose stream closed is waited for until it rejoins the
+    /// system, see [`NodeArguments::rejoin`].
+    pub fn awaits_rejoin(&self) -> bool {
+        self.controller.as_ref().is_some_and(|link| link.rejoin)
+    }
+
+    /// Replaces the nodes of the system, numbered otherwise from `1` to `node_count`.
+    pub fn with_members(mut self, mut members: Vec<NodeId>) -> Self {
+        members.sort();
+        self.node_count = members.len();
+        self.members = members;
+        self
+    }
+
+    /// Returns the identifier of every other node within the system, in increasing order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use renraku_node::{channel::MemoryChannel, NodeConfig};
+    /// # use renraku_shared::NodeId;
+    /// # use std::collections::HashMap;
+    ///
+    /// let config = NodeConfig::<MemoryChannel>::new(3, NodeId(2), HashMap::new());
+    /// assert_eq!(config.others().collect::<Vec<_>>(), [&NodeId(1), &NodeId(3)]);
+    ///
+    /// let config = config.with_members(vec![NodeId(6), NodeId(2), NodeId(4)]);
+    /// assert_eq!(config.others().collect::<Vec<_>>(), [&NodeId(4), &NodeId(6)]);
+    /// ```
+    pub fn others(&self) -> impl Iterator<Item = &NodeId> {
+        self.members.iter().filter(|member| **member != self.id)
+    }
+
+    /// Records the frames received from every neighbour, along with what this node does on
+    /// its own, for the recording to be replayed, see [`record::Replay`].
+    pub fn recorded_by(mut self, recorder: Arc<Recorder>) -> Self {
+        for (peer, neighbour) in self.neighbours.iter() {
+            neighbour.record_into(recorder.clone(), peer);
+        }
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// Records something this node did on its own, if it is recorded.
+    pub fn record_local(&self, event: LocalEvent) -> Result<(), NodeError> {
+        match &self.recorder {
+            Some(recorder) => recorder.record_local(event),
+            None => Ok(()),
+        }
+    }
+
+    /// Stamps the messages of this node with a vector clock, starting before any event.
+    pub fn with_vector_clock(mut self) -> Self {
+        self.vector_clock = Some(Mutex::new(VectorClock::new(self.node_count)));
+        self
+    }
+
+    /// Counts the sending of a message, returning the clock to stamp it with if messages are
+    /// stamped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use renraku_node::{channel::MemoryChannel, NodeConfig};
+    /// # use renraku_shared::NodeId;
+    /// use std::collections::HashMap;
+    ///
+    /// let node = |id| NodeConfig::<MemoryChannel>::new(2, NodeId(id), HashMap::new());
+    /// assert_eq!(node(1).tick(), None);
+    ///
+    /// let (first, second) = (node(1).with_vector_clock(), node(2).with_vector_clock());
+    /// let sent = first.tick().unwrap();
+    /// second.observe(Some(&sent));
+    /// assert!(sent.happens_before(&second.tick().unwrap()));
+    /// ```
+    pub fn tick(&self) -> Option<VectorClock> {
+        self.vector_clock.as_ref().map(|clock| {
+            let mut clock = clock.lock().unwrap();
+            clock.tick(&self.id);
+            clock.clone()
+        })
+    }
+
+    /// Counts the receipt of a message, taking into account the clock it is stamped with.
+    pub fn observe(&self, stamp: Option<&VectorClock>) {
+        if let Some(clock) = &self.vector_clock {
+            let mut clock = clock.lock().unwrap();
+            if let Some(stamp) = stamp {
+                clock.merge(stamp);
+            }
+            clock.tick(&self.id);
+        }
+    }
+
+    /// Starts the writer thread of every neighbour, see [`Neighbour::start_writer`].
+    pub fn start_writers(&self)
+    where
+        C: 'static,
+    {
+        self.neighbours
+            .values()
+            .for_each(|neighbour| neighbour.start_writer());
+    }
+
+    /// Sends a message, as a frame, to every neighbour except the excluded one if any.
+    ///
+    /// The message is serialized once, whatever the number of neighbours, then queued as
+    /// [`Neighbour::enqueue`] does.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use renraku_node::{channel::MemoryChannel, Neighbour, NodeConfig};
+    /// # use renraku_shared::NodeId;
+    /// use std::collections::HashMap;
+    ///
+    /// let (mut ends, mut neighbours) = (HashMap::new(), HashMap::new());
+    /// for peer in 2..5 {
+    ///     let (local, remote) = MemoryChannel::pair();
+    ///     neighbours.insert(NodeId(peer), Neighbour::new(local).unwrap());
+    ///     ends.insert(peer, remote);
+    /// }
+    /// let config = NodeConfig::new(4, NodeId(1), neighbours);
+    ///
+    /// config.broadcast(&42u64, Some(NodeId(3))).unwrap();
+    /// assert_eq!(ends[&2].pending(), 12);
+    /// assert_eq!(ends[&3].pending(), 0);
+    /// assert_eq!(ends[&4].pending(), 12);
+    /// ```
+    pub fn broadcast(
+        &self,
+        message: &impl Serialize,
+        exclude: Option<NodeId>,
+    ) -> Result<(), NodeError> {
+        for (id, neighbour) in self.neighbours.iter() {
+            if exclude.as_ref() != Some(&id) {
+                neighbour.enqueue(neighbour.encode(message)?)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads the Lamport clock persisted by a previous run of this node, `0` if there is none.
+    pub fn load_clock(&self) -> Result<usize, NodeError> {
+        match &self.clock_file {
+            Some(file) => file.load(),
+            None => Ok(0),
+        }
+    }
+
+    /// Persists the Lamport clock of this node, if it has a clock file.
+    pub fn save_clock(&self, clock: usize) -> Result<(), NodeError> {
+        match &self.clock_file {
+            Some(file) => file.save(clock),
+            None => Ok(()),
+        }
+    }
+
+    /// Closes the channel to each neighbour once the messages queued for it are written, so
+    /// that they see this node leaving rather than a connection left half-open.
+    ///
+    /// Also done when the configuration is dropped.
+    pub fn shutdown(&self) {
+        for (peer, neighbour) in self.neighbours.iter() {
+            match neighbour.shutdown() {
+                Ok(()) => info!("👋 Closed the connection to {peer:?}"),
+                Err(e) => warn!("👋 Closing the connection to {peer:?}: {e}"),
+            }
+        }
+    }
+
+    /// Tells the controller this node entered the critical section of the resource, or left
+    /// it, when it witnesses this node.
+    ///
+    /// Entering and leaving count as events of the vector clock the notification is stamped
+    /// with, which [`configure`] keeps for witnessed nodes even without `--vector-clock`.
+    pub fn witness(&self, resource: &str, inside: bool) -> Result<(), NodeError> {
+        let Some(link) = self.controller.as_ref().filter(|link| link.witness) else {
+            return Ok(());
+        };
+        let (id, resource) = (self.id.clone(), resource.to_string());
+        let clock = self.tick().unwrap_or_default();
+        let message = match inside {
+            true => ControlMessage::Entered {
+                id,
+                resource,
+                clock,
+            },
+            false => ControlMessage::Left {
+                id,
+                resource,
+                clock,
+            },
+        };
+        link.socket.send(&message.encode()?)?;
+        Ok(())
+    }
+
+    /// Records a message exchanged with a neighbour in the event log, if there is one.
+    pub fn record(
+        &self,
+        peer: &NodeId,
+        direction: Direction,
+        message: &str,
+        clock: usize,
+    ) -> Result<(), NodeError> {
+        if let Some(metrics) = &self.metrics {
+            metrics.message(direction, message, clock);
+        }
+        match &self.event_log {
+            Some(log) => log.record(&self.id, peer, direction, message, clock),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<C: Channel> Drop for NodeConfig<C> {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+impl NodeConfig {
+    /// Receives a message the controller sent once the node was configured, returning the
+    /// node it tells joined the system along with the address it listens on.
+    ///
+    /// The controller socket must be readable, lest this waits for the controller. Anything
+    /// else than a [`ControlMessage::Join`] is ignored, as by a node not configured by a
+    /// controller.
+    pub fn receive_join(&self) -> Result<Option<(NodeId, SocketAddr)>, NodeError> {
+        let Some(link) = &self.controller else {
+            return Ok(None);
+        };
+        let dialer = &link.dialer;
+        let mut buf = vec![0; u16::MAX as usize];
+        let received = link.socket.recv(&mut buf)?;
+        if received > dialer.max_message_bytes {
+            return Err(NodeError::MessageTooLarge {
+                length: received,
+                max: dialer.max_message_bytes,
+            });
+        }
+        match ControlMessage::decode_with_limit(&buf[..received], dialer.max_message_bytes as u64) {
+            Ok(ControlMessage::Join { id, address }) => Ok(Some((id, address.into()))),
+            Ok(other) => {
+                warn!("📨 Ignoring {other:?} from the controller");
+                Ok(None)
+            }
+            Err(e) => Err(NodeError::Deserialize("joining node", e)),
+        }
+    }
+
+    /// Connects to the node that joined the system, listening on `address`, as told by
+    /// [`NodeConfig::receive_join`].
+    ///
+    /// A neighbour that restarted joins again, the new stream replacing the one it had.
+    /// [`NodeConfig::members`] are left as the controller first told them, the algorithm being
+    /// told about the new neighbour, see [`runtime::admit`]. A node not configured by a
+    /// controller connects to nobody.
+    pub fn admit(&self, id: &NodeId, address: SocketAddr) -> Result<(), NodeError> {
+        let Some(link) = &self.controller else {
+            return Ok(());
+        };
+        let dialer = &link.dialer;
+        if !self.neighbours.contains_key(id) {
+            check_fan_out(self.neighbours.len() + 1, dialer.max_neighbours)?;
+        }
+        let (neighbour, stream_id) = dialer.dial(address, &self.id)?;
+        if stream_id != *id {
+            return Err(NodeError::UnexpectedPeer {
+                addr: dialer.peer(address),
+                expected: id.clone(),
+                actual: stream_id,
+            });
+        }
+        neighbour.start_writer();
+        if let Some(recorder) = &self.recorder {
+            neighbour.record_into(recorder.clone(), id.clone());
+        }
+        match self.neighbours.insert(id.clone(), neighbour) {
+            Some(_) => info!("🔁 {id:?} rejoined the system"),
+            None => info!("🆕 {id:?} joined the system"),
+        }
+        Ok(())
+    }
+
+    /// Describes the configuration of the node, along with the address of each neighbour.
+    pub fn describe(&self) -> String {
+        let mut neighbours: Vec<(usize, String)> = self
+            .neighbours
+            .iter()
+            .map(|(id, n)| {
+                let addr = n.channel().peer_addr();
+                (id.0, addr.map_or_else(|e| e.to_string(), |a| a.to_string()))
+            })
+            .collect();
+        neighbours.sort();
+        let neighbours: Vec<String> = neighbours
+            .into_iter()
+            .map(|(id, addr)| format!("{id} ({addr})"))
+            .collect();
+        let mut description = format!(
+            "Node #{} of {}, neighbours: [{}], quorum: {:?}",
+            self.id.0,
+            self.node_count,
+            neighbours.join(", "),
+            self.quorum.iter().map(|n| n.0).collect::<Vec<_>>()
+        );
+        if let Some(role) = &self.role {
+            description.push_str(&format!(", role: {role}"));
+        }
+        description
+    }
+}
+
+/// Delay before dialing a neighbour again, multiplied by the number of failed attempts.
+const CONNECT_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// How many times the listener of a neighbour is dialed, during the setup or once it joined,
+/// before giving up.
+const CONNECT_ATTEMPTS: usize = 5;
+
+/// Where a node accepts its neighbours from, and how it reaches the listener of the others.
+#[derive(Debug)]
+enum Transport {
+    Tcp(TcpListener),
+    /// UNIX domain sockets within a directory, see [`unix::socket_path`].
+    #[cfg(all(unix, feature = "unix-socket"))]
+    Unix(std::os::unix::net::UnixListener),
+}
+
+impl Transport {
+    /// Listens within `socket_dir` when there is one, for the neighbours being told to connect
+    /// to `port`, and on the TCP listener otherwise.
+    #[cfg(all(unix, feature = "unix-socket"))]
+    fn new(tcp: TcpListener, socket_dir: Option<PathBuf>, port: u16) -> Result<Self, NodeError> {
+        match socket_dir {
+            Some(dir) => Ok(Transport::Unix(unix::listen(&dir, port)?)),
+            None => Ok(Transport::Tcp(tcp)),
+        }
+    }
+
+    /// Without UNIX domain sockets, asking for them is an error.
+    #[cfg(not(all(unix, feature = "unix-socket")))]
+    fn new(tcp: TcpListener, socket_dir: Option<PathBuf>, _port: u16) -> Result<Self, NodeError> {
+        match socket_dir {
+            Some(dir) => Err(NodeError::UnixSocketDisabled(dir)),
+            None => Ok(Transport::Tcp(tcp)),
+        }
+    }
+
+    fn accept(&self) -> io::Result<(Stream, PeerAddr)> {
+        match self {
+            Transport::Tcp(listener) => {
+                let (stream, peer) = listener.accept()?;
+                Ok((stream.into(), peer.into()))
+            }
+            #[cfg(all(unix, feature = "unix-socket"))]
+            Transport::Unix(listener) => {
+                let stream = Stream::from(listener.accept()?.0);
+                let peer = stream.peer_addr()?;
+                Ok((stream, peer))
+            }
+        }
+    }
+
+    /// Connects to the listener at `peer`, giving up after `timeout` over TCP.
+    fn connect(peer: &PeerAddr, timeout: Duration) -> io::Result<Stream> {
+        match peer {
+            PeerAddr::Inet(addr) => TcpStream::connect_timeout(addr, timeout).map(Into::into),
+            #[cfg(all(unix, feature = "unix-socket"))]
+            PeerAddr::Unix(path) => std::os::unix::net::UnixStream::connect(path).map(Into::into),
+            #[cfg(not(all(unix, feature = "unix-socket")))]
+            PeerAddr::Unix(path) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("{} is a UNIX domain socket", path.display()),
+            )),
+        }
+    }
+}
+
+/// How a node sets up the streams to its neighbours, during the setup and once it runs.
+#[derive(Debug, Clone)]
+struct Dialer {
+    /// Directory of the UNIX domain sockets the neighbours listen on, if they are reached
+    /// over those rather than over TCP.
+    socket_dir: Option<PathBuf>,
+    connect_timeout: Duration,
+    send_timeout: Option<Duration>,
+    nagle: bool,
+    max_message_bytes: usize,
+    max_neighbours: usize,
+    codec: Format,
+}
+
+impl Dialer {
+    fn new(args: &NodeArguments) -> Self {
+        Self {
+            socket_dir: args.socket_dir.clone(),
+            connect_timeout: Duration::from_millis(args.peer_connect_timeout_ms),
+            send_timeout: args.send_timeout_ms.map(Duration::from_millis),
+            nagle: args.nagle,
+            max_message_bytes: args.max_message_bytes,
+            max_neighbours: args.max_neighbours,
+            codec: args.codec,
+        }
+    }
+
+    /// Where the neighbour the controller hands out `addr` for listens.
+    fn peer(&self, addr: SocketAddr) -> PeerAddr {
+        match &self.socket_dir {
+            #[cfg(all(unix, feature = "unix-socket"))]
+            Some(dir) => PeerAddr::Unix(unix::socket_path(dir, addr.port())),
+            _ => addr.into(),
+        }
+    }
+
+    /// Connects to the listener the controller hands out `addr` for, returning the neighbour
+    /// along with its identifier.
+    fn dial(&self, addr: SocketAddr, id: &NodeId) -> Result<(Neighbour, NodeId), NodeError> {
+        let peer = self.peer(addr);
+        let (stream, stream_id) =
+            dial_within(peer.clone(), id, self.connect_timeout, CONNECT_ATTEMPTS)?;
+        info!("🤝 Dialed {peer} for {stream_id:?}");
+        Ok((self.neighbour(stream)?, stream_id))
+    }
+
+    fn neighbour(&self, stream: Stream) -> Result<Neighbour, NodeError> {
+        stream.set_nodelay(!self.nagle)?;
+        Neighbour::new(stream)?
+            .with_max_message_bytes(self.max_message_bytes)
+            .with_codec(self.codec)
+            .with_send_timeout(self.send_timeout)
+    }
+}
+
+/// Connects to the listener of a neighbour, exchanging identifiers with it.
+///
+/// Connecting over TCP is given up after `timeout`.
+fn dial(peer: PeerAddr, id: &NodeId, timeout: Duration) -> Result<(Stream, NodeId), NodeError> {
+    let mut stream =
+        Transport::connect(&peer, timeout).map_err(|e| NodeError::PeerConnect(peer.clone(), e))?;
+    let stream_id = handshake::exchange(&mut stream, peer, id, true)?;
+    Ok((stream, stream_id))
+}
+
+/// Connects to the listener of a neighbour as [`dial`] does, giving each attempt `timeout`
+/// and trying up to `attempts` times, waiting a little longer between each attempt.
+///
+/// The controller may hand out the address of a listener before it is ready to accept.
+fn dial_within(
+    peer: PeerAddr,
+    id: &NodeId,
+    timeout: Duration,
+    attempts: usize,
+) -> Result<(Stream, NodeId), NodeError> {
+    let mut attempt = 1;
+    loop {
+        match dial(peer.clone(), id, timeout) {
+            Ok(dialed) => return Ok(dialed),
+            Err(NodeError::PeerConnect(_, e)) if attempt < attempts => {
+                warn!("🔌 Attempt {attempt}/{attempts} to connect to {peer} failed: {e}");
+                sleep(CONNECT_RETRY_DELAY * attempt as u32);
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
 }
 
+/// Records why writing to a neighbour failed, for the following sends to fail as well, and
+/// closes the channel.
 ///
-pub fn configure(args: NodeArguments) -> Result<(usize, NodeId, HashMap<NodeId, TcpStream>)> {
-    let controller_socket = UdpSocket::bind("localhost:0")?;
-    let tcp_listener = TcpListener::bind("localhost:0")?;
+/// A write failing midway may have left part of a frame on the channel, so that nothing
+/// written after it could be read by the neighbour.
+fn give_up<C: Channel>(broken: &Mutex<Option<String>>, channel: &C, error: NodeError) -> NodeError {
+    warn!("✉️ No longer writing to the neighbour: {error}");
+    *broken.lock().unwrap() = Some(error.to_string());
+    if let Err(e) = channel.shutdown() {
+        warn!("✉️ Closing the channel to the neighbour: {e}");
+    }
+    error
+}
+
+/// Tells a write that timed out, a send timeout being set, from any other failure.
+fn send_error(timeout: Option<Duration>, e: io::Error) -> NodeError {
+    match (timeout, e.kind()) {
+        (Some(timeout), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => {
+            NodeError::SendTimeout(timeout)
+        }
+        _ => e.into(),
+    }
+}
+
+/// Ensures a node will not hold more than `max` neighbour streams.
+///
+/// # Examples
+///
+/// ```
+/// # use renraku_node::{check_fan_out, NodeError};
+///
+/// assert!(check_fan_out(20, 256).is_ok());
+/// assert!(matches!(
+///     check_fan_out(20, 10),
+///     Err(NodeError::FanOut { neighbours: 20, max: 10 })
+/// ));
+/// ```
+pub fn check_fan_out(neighbours: usize, max: usize) -> Result<(), NodeError> {
+    if neighbours > max {
+        return Err(NodeError::FanOut { neighbours, max });
+    }
+    Ok(())
+}
+
+/// Checks the address of a controller is written as `host:port`, without resolving the host,
+/// and normalizes it.
+///
+/// IP addresses are written back in their canonical form, and host names in lowercase.
+///
+/// # Examples
+///
+/// ```
+/// # use renraku_node::parse_controller;
+///
+/// assert_eq!(parse_controller(" LocalHost:3000").unwrap(), "localhost:3000");
+/// assert_eq!(parse_controller("[0:0::1]:3000").unwrap(), "[::1]:3000");
+/// assert_eq!(parse_controller("10.0.0.1:3000").unwrap(), "10.0.0.1:3000");
+///
+/// assert!(parse_controller("localhost;3000").is_err());
+/// assert!(parse_controller("localhost:99999").is_err());
+/// assert!(parse_controller(":3000").is_err());
+/// assert!(parse_controller("::1:3000").is_err());
+/// ```
+pub fn parse_controller(s: &str) -> Result<String, String> {
+    let s = s.trim();
+    if let Ok(address) = s.parse::<SocketAddr>() {
+        return Ok(address.to_string());
+    }
+    let Some((host, port)) = s.rsplit_once(':') else {
+        return Err(format!("{s:?} is not written as host:port"));
+    };
+    port.parse::<u16>()
+        .map_err(|e| format!("{port:?} is not a port of {s:?}: {e}"))?;
+    if host.contains(':') {
+        return Err(format!(
+            "IPv6 addresses are written within brackets, as in [{host}]:{port}"
+        ));
+    }
+    let valid = |c: char| c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_');
+    if host.is_empty() || !host.chars().all(valid) {
+        return Err(format!("{host:?} is not a host name of {s:?}"));
+    }
+    Ok(format!("{}:{port}", host.to_ascii_lowercase()))
+}
+
+/// Says hello to each of the addresses of the controller in turn, until one of them
+/// acknowledges it within `timeout`, returning that address.
+///
+/// The socket is then connected to the controller, so that nothing else is received from
+/// another address.
+///
+/// # Examples
+///
+/// ```
+/// # use renraku_node::register;
+/// # use renraku_shared::control::ControlMessage;
+/// use std::{net::UdpSocket, thread, time::Duration};
+///
+/// // Nothing listens on the first address anymore
+/// let dead = UdpSocket::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+/// let controller = UdpSocket::bind("127.0.0.1:0").unwrap();
+/// let live = controller.local_addr().unwrap();
+/// thread::spawn(move || {
+///     let mut buf = [0; 1024];
+///     let (_, node) = controller.recv_from(&mut buf).unwrap();
+///     controller.send_to(&ControlMessage::Registered.encode().unwrap(), node).unwrap();
+/// });
+///
+/// let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+/// let hello = ControlMessage::Hello {
+///     listen_port: 4242,
+///     capacity: 1,
+///     desired_id: None,
+///     advertise: None,
+/// };
+/// let registered = register(&socket, &[dead, live], &hello, Duration::from_millis(200));
+/// assert_eq!(registered.unwrap(), live);
+///
+/// assert!(register(&socket, &[dead], &hello, Duration::from_millis(200)).is_err());
+/// ```
+pub fn register(
+    socket: &UdpSocket,
+    addresses: &[SocketAddr],
+    hello: &ControlMessage,
+    timeout: Duration,
+) -> Result<SocketAddr, NodeError> {
+    let datagram = hello.encode()?;
     let mut buf = [0; 1024];
+    let mut last_error = None;
+    for addr in addresses {
+        let attempt = (|| {
+            socket.connect(addr)?;
+            socket.send(&datagram)?;
+            socket.set_read_timeout(Some(timeout))?;
+            let received = socket.recv(&mut buf)?;
+            socket.set_read_timeout(None)?;
+            match ControlMessage::decode(&buf[..received]) {
+                Ok(ControlMessage::Registered) => Ok(()),
+                Ok(other) => Err(NodeError::UnexpectedControl {
+                    expected: "an acknowledgement",
+                    received: Box::new(other),
+                }),
+                Err(e) => Err(NodeError::Deserialize("acknowledgement", e)),
+            }
+        })();
+        match attempt {
+            Ok(()) => return Ok(*addr),
+            Err(e) => {
+                warn!("📭 The controller at {addr} did not acknowledge the hello: {e}");
+                last_error = Some(e);
+            }
+        }
+    }
+    Err(last_error.unwrap_or_else(|| {
+        io::Error::new(io::ErrorKind::NotFound, "the controller has no address").into()
+    }))
+}
+
+/// Registers the node to the coordinator and connects it to each of its neighbours.
+///
+/// # Examples
+///
+/// ```
+/// # use renraku_node::{configure, NodeArguments, NodeError};
+///
+/// let args = NodeArguments {
+///     controller: "not an address".to_string(),
+///     ..NodeArguments::default()
+/// };
+/// assert!(matches!(configure(args), Err(NodeError::Controller { .. })));
+/// ```
+pub fn configure(args: NodeArguments) -> Result<NodeConfig, NodeError> {
+    let dialer = Dialer::new(&args);
+    let event_log = args.event_log.map(EventLog::open).transpose()?;
+    let metrics = args.metrics_addr.map(serve_metrics).transpose()?;
+    let controller = args.controller;
+    let controller_socket = UdpSocket::bind("localhost:0")
+        .map_err(|e| NodeError::Bind("socket to the controller", e))?;
+    let tcp_listener = TcpListener::bind("localhost:0")
+        .map_err(|e| NodeError::Bind("listener for neighbours", e))?;
+    let listen_port = tcp_listener.local_addr()?.port();
+    // Neighbours reach a UNIX domain socket from the port they are told to connect to, the
+    // TCP listener keeping that port for the node. Every socket is bound before registering.
+    let port = args.advertise_addr.map_or(listen_port, |addr| addr.port());
+    let transport = Transport::new(tcp_listener, args.socket_dir, port)?;
+
+    // Sends a message to let the controller identify we are a program, trying each of its
+    // addresses until one of them acknowledges it
+    let hello = ControlMessage::Hello {
+        listen_port,
+        capacity: args.capacity,
+        desired_id: args.desired_id.map(NodeId),
+        advertise: args.advertise_addr.map(Into::into),
+    };
+    let addresses: Vec<SocketAddr> = controller
+        .to_socket_addrs()
+        .map_err(|source| NodeError::Controller {
+            step: "Resolving",
+            controller: controller.clone(),
+            source,
+        })?
+        .collect();
+    let controller_addr = register(
+        &controller_socket,
+        &addresses,
+        &hello,
+        Duration::from_millis(args.controller_timeout_ms),
+    )
+    .map_err(|_| NodeError::Unregistered {
+        controller: controller.clone(),
+        addresses: addresses.len(),
+    })?;
+    info!("📨 Registered to the controller at {controller_addr}");
+
+    // Large enough for any datagram, the outgoing addresses being sent at once
+    let mut buf = vec![0; u16::MAX as usize];
+    let mut receive = |step, expected| {
+        let received =
+            controller_socket
+                .recv(&mut buf)
+                .map_err(|source| NodeError::Controller {
+                    step,
+                    controller: controller.clone(),
+                    source,
+                })?;
+        if received > args.max_message_bytes {
+            return Err(NodeError::MessageTooLarge {
+                length: received,
+                max: args.max_message_bytes,
+            });
+        }
+        ControlMessage::decode_with_limit(&buf[..received], args.max_message_bytes as u64)
+            .map_err(|e| NodeError::Deserialize(expected, e))
+    };
+    let unexpected = |expected, received| NodeError::UnexpectedControl {
+        expected,
+        received: Box::new(received),
+    };
 
-    // Sends a message to let the controller identify we are a program
-    controller_socket.send_to(
-        &bincode::serialize(&tcp_listener.local_addr()?.port())?,
-        args.controller,
-    )?;
     // Receive a first message that contains the ID.
-    controller_socket.recv(&mut buf)?;
-    let (node_count, id) = bincode::deserialize::<(usize, NodeId)>(&buf)?;
-    // Receive a second message with the number of addresses we have to connect to
+    let (id, members) = match receive("Receiving id from", "id")? {
+        ControlMessage::Assign { id, members } => (id, members),
+        other => return Err(unexpected("an id", other)),
+    };
+    let node_count = members.len();
+    // Receive a second message with the number of connections we will accept
     // since at least one program will only receive connections, we know this will
     // not block each of our nodes.
-    controller_socket.recv(&mut buf)?;
-    let read_streams_count = bincode::deserialize::<usize>(&buf)?;
-    controller_socket.recv(&mut buf)?;
-    let write_streams_count = bincode::deserialize::<usize>(&buf)?;
+    let read_streams_count = match receive(
+        "Receiving incoming connections count from",
+        "incoming connections count",
+    )? {
+        ControlMessage::IncomingCount(count) => count,
+        other => return Err(unexpected("an incoming connections count", other)),
+    };
+    // Then the addresses we have to connect to
+    let outgoing = match receive("Receiving outgoing addresses from", "outgoing addresses")? {
+        ControlMessage::Outgoing(addresses) => addresses
+            .into_iter()
+            .map(SocketAddr::from)
+            .collect::<Vec<_>>(),
+        other => return Err(unexpected("outgoing addresses", other)),
+    };
+    let write_streams_count = outgoing.len();
+    // Then the voting quorum computed by the coordinator
+    let quorum = match receive("Receiving quorum from", "quorum")? {
+        ControlMessage::Quorum(quorum) => quorum,
+        other => return Err(unexpected("a quorum", other)),
+    };
+    // Then its role, if the graph tags it with one
+    let role = match receive("Receiving role from", "role")? {
+        ControlMessage::Role(role) => role,
+        other => return Err(unexpected("a role", other)),
+    };
+    // Then the label of its links, for those the graph labels
+    let labels = match receive("Receiving link labels from", "link labels")? {
+        ControlMessage::Labels(labels) => labels.into_iter().collect(),
+        other => return Err(unexpected("link labels", other)),
+    };
+    // Then where it stands in the directed topology
+    let placement = match receive("Receiving placement from", "placement")? {
+        ControlMessage::Placement(placement) => placement,
+        other => return Err(unexpected("a placement", other)),
+    };
+    // And finally how many neighbours it must end up with
+    let neighbour_count = match receive("Receiving neighbour count from", "neighbour count")? {
+        ControlMessage::NeighbourCount(count) => count,
+        other => return Err(unexpected("a neighbour count", other)),
+    };
 
-    let mut id_to_stream = HashMap::with_capacity(read_streams_count + write_streams_count);
+    check_fan_out(
+        read_streams_count + write_streams_count,
+        args.max_neighbours,
+    )?;
+    let mut neighbours = HashMap::with_capacity(read_streams_count + write_streams_count);
 
-    for _ in 0..read_streams_count {
-        let mut stream = tcp_listener.accept()?.0;
-        stream.read(&mut buf)?;
-        let stream_id = bincode::deserialize::<NodeId>(&buf)?;
+    for i in 1..=read_streams_count {
+        let (mut stream, peer) = transport
+            .accept()
+            .map_err(|e| NodeError::Accept(i, read_streams_count, e))?;
+        let stream_id = handshake::exchange(&mut stream, peer.clone(), &id, false)?;
+        info!("🤝 Accepted a connection from {peer}, identified as {stream_id:?}");
+        neighbours.insert(stream_id, dialer.neighbour(stream)?);
+    }
+    // Two neighbours answering with the same id would leave one of them out
+    if neighbours.len() != read_streams_count {
+        return Err(NodeError::NeighbourCount {
+            expected: read_streams_count,
+            actual: neighbours.len(),
+        });
+    }
 
-        stream.write(&bincode::serialize(&id)?)?;
+    // Connect to the addresses received from the controller
+    if outgoing.is_empty() {
+        debug!("📥 This node only accepts connections, from {read_streams_count} neighbours");
+    }
+    for addr in outgoing {
+        let (neighbour, stream_id) = dialer.dial(addr, &id)?;
+        neighbours.insert(stream_id, neighbour);
+    }
 
-        id_to_stream.insert(stream_id, stream);
+    // A lost datagram, or two dialed peers answering with the same id, leaves the topology
+    // partial
+    if neighbours.len() != neighbour_count {
+        return Err(NodeError::NeighbourCount {
+            expected: neighbour_count,
+            actual: neighbours.len(),
+        });
     }
 
-    // Receive the addresses we have to connect to
-    for _ in 0..write_streams_count {
-        controller_socket.recv(&mut buf)?;
-        let addr = bincode::deserialize::<SocketAddr>(&buf)?;
+    // Nothing written during the setup may be left behind the first message of the algorithm
+    for neighbour in neighbours.values() {
+        neighbour.flush()?;
+    }
 
-        let mut stream = TcpStream::connect(addr)?;
-        stream.write(&bincode::serialize(&id)?)?;
+    let recorder = args
+        .record_dir
+        .map(|dir| Recorder::create(dir, &id, &members, args.codec).map(Arc::new))
+        .transpose()?;
 
-        stream.read(&mut buf)?;
-        let stream_id = bincode::deserialize::<NodeId>(&buf)?;
+    let config = NodeConfig {
+        node_count,
+        members,
+        id,
+        neighbours: neighbours.into(),
+        quorum,
+        role,
+        labels,
+        placement: Some(placement),
+        event_log,
+        vector_clock: (args.vector_clock || args.witness)
+            .then(|| Mutex::new(VectorClock::new(node_count))),
+        clock_file: args.clock_file.map(ClockFile::new),
+        controller: Some(ControllerLink {
+            socket: controller_socket,
+            witness: args.witness,
+            rejoin: args.rejoin,
+            dialer,
+        }),
+        metrics,
+        recorder: None,
+    };
+    Ok(match recorder {
+        Some(recorder) => config.recorded_by(recorder),
+        None => config,
+    })
+}
 
-        id_to_stream.insert(stream_id, stream);
-    }
+/// Serves fresh metrics on the address, for the node to count into.
+#[cfg(feature = "metrics")]
+fn serve_metrics(address: SocketAddr) -> Result<Arc<Metrics>, NodeError> {
+    let metrics = Arc::new(Metrics::default());
+    metrics.serve(address)?;
+    Ok(metrics)
+}
 
-    Ok((node_count, id, id_to_stream))
+/// Without an endpoint to serve them on, asking for metrics is an error.
+#[cfg(not(feature = "metrics"))]
+fn serve_metrics(address: SocketAddr) -> Result<Arc<Metrics>, NodeError> {
+    Err(NodeError::MetricsDisabled(address))
 }
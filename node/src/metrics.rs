@@ -0,0 +1,179 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+use crate::event::Direction;
+
+/// Counters describing a running node, rendered in the Prometheus text format.
+///
+/// The messages and the Lamport clock are counted as they are recorded by
+/// [`NodeConfig::record`](crate::NodeConfig::record), the algorithm reporting its state and
+/// the critical sections itself.
+///
+/// # Examples
+///
+/// ```
+/// # use renraku_node::{event::Direction, metrics::Metrics};
+///
+/// let metrics = Metrics::default();
+/// metrics.message(Direction::Sent, "request", 3);
+/// metrics.message(Direction::Received, "permission", 5);
+/// metrics.set_state("critical_section");
+/// metrics.critical_section();
+///
+/// let text = metrics.render();
+/// assert!(text.contains("renraku_messages_total{direction=\"sent\",type=\"request\"} 1\n"));
+/// assert!(text.contains("renraku_lamport_clock 5\n"));
+/// assert!(text.contains("renraku_critical_sections_total 1\n"));
+/// assert!(text.contains("renraku_state{state=\"critical_section\"} 1\n"));
+/// ```
+#[derive(Debug)]
+pub struct Metrics {
+    messages: Mutex<BTreeMap<(&'static str, String), u64>>,
+    clock: AtomicUsize,
+    critical_sections: AtomicUsize,
+    state: Mutex<String>,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self {
+            messages: Mutex::new(BTreeMap::new()),
+            clock: AtomicUsize::new(0),
+            critical_sections: AtomicUsize::new(0),
+            state: Mutex::new("idle".to_string()),
+        }
+    }
+}
+
+impl Metrics {
+    /// Counts a message that went through the node, the clock of the node moving to `clock`.
+    pub fn message(&self, direction: Direction, kind: &str, clock: usize) {
+        let direction = match direction {
+            Direction::Sent => "sent",
+            Direction::Received => "received",
+        };
+        *self
+            .messages
+            .lock()
+            .unwrap()
+            .entry((direction, kind.to_string()))
+            .or_default() += 1;
+        self.clock.fetch_max(clock, Ordering::Relaxed);
+    }
+
+    /// Counts an access to the critical section.
+    pub fn critical_section(&self) {
+        self.critical_sections.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Sets the state the node is in, as named by its algorithm.
+    pub fn set_state(&self, state: &str) {
+        *self.state.lock().unwrap() = state.to_string();
+    }
+
+    /// Renders every metric in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut text = String::new();
+        text.push_str("# HELP renraku_messages_total Messages that went through the node.\n");
+        text.push_str("# TYPE renraku_messages_total counter\n");
+        for ((direction, kind), count) in self.messages.lock().unwrap().iter() {
+            let _ = writeln!(
+                text,
+                "renraku_messages_total{{direction=\"{direction}\",type=\"{}\"}} {count}",
+                escape(kind)
+            );
+        }
+        text.push_str("# HELP renraku_lamport_clock Lamport clock of the node.\n");
+        text.push_str("# TYPE renraku_lamport_clock gauge\n");
+        let _ = writeln!(
+            text,
+            "renraku_lamport_clock {}",
+            self.clock.load(Ordering::Relaxed)
+        );
+        text.push_str("# HELP renraku_critical_sections_total Accesses to the critical section.\n");
+        text.push_str("# TYPE renraku_critical_sections_total counter\n");
+        let _ = writeln!(
+            text,
+            "renraku_critical_sections_total {}",
+            self.critical_sections.load(Ordering::Relaxed)
+        );
+        text.push_str("# HELP renraku_state State the node is in.\n");
+        text.push_str("# TYPE renraku_state gauge\n");
+        let _ = writeln!(
+            text,
+            "renraku_state{{state=\"{}\"}} 1",
+            escape(&self.state.lock().unwrap())
+        );
+        text
+    }
+}
+
+/// Escapes a label value of the Prometheus text format.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(feature = "metrics")]
+mod endpoint {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::{SocketAddr, TcpListener, TcpStream},
+        sync::Arc,
+        thread,
+        time::Duration,
+    };
+
+    use tracing::{info, warn};
+
+    use super::Metrics;
+    use crate::error::NodeError;
+
+    /// Longest a scraper is given to send its request.
+    const REQUEST_TIMEOUT: Duration = Duration::from_secs(1);
+
+    impl Metrics {
+        /// Serves the metrics over HTTP on `address`, whatever the requested path, from a
+        /// thread of its own. Returns the address actually listened on.
+        pub fn serve(self: &Arc<Self>, address: SocketAddr) -> Result<SocketAddr, NodeError> {
+            let listener =
+                TcpListener::bind(address).map_err(|e| NodeError::Bind("metrics endpoint", e))?;
+            let local = listener.local_addr()?;
+            let metrics = self.clone();
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if let Err(e) = stream.and_then(|stream| respond(stream, &metrics)) {
+                        warn!("📈 Serving the metrics: {e}");
+                    }
+                }
+            });
+            info!("📈 Serving metrics on http://{local}/metrics");
+            Ok(local)
+        }
+    }
+
+    /// Reads the request up to its blank line, then answers with the metrics.
+    fn respond(stream: TcpStream, metrics: &Metrics) -> std::io::Result<()> {
+        stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+        let mut reader = BufReader::new(&stream);
+        let mut line = String::new();
+        while reader.read_line(&mut line)? > 0 && !line.trim_end().is_empty() {
+            line.clear();
+        }
+        let body = metrics.render();
+        write!(
+            &stream,
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        )?;
+        (&stream).flush()
+    }
+}
@@ -0,0 +1,289 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Mutex,
+};
+
+use renraku_shared::{codec::Format, frame::write_frame, NodeId};
+use serde::{Deserialize, Serialize};
+
+use crate::{channel::MemoryChannel, error::NodeError, Neighbour, NodeConfig};
+
+/// File holding the identifier of the recorded node and of every node of the system, along
+/// with the codec of their messages.
+const NODE_FILE: &str = "node.json";
+/// File listing, one per line, the neighbour each frame has been received from and what the
+/// node did on its own in between.
+const ORDER_FILE: &str = "order";
+
+/// Name of the file holding the frames received from a neighbour.
+fn peer_file(peer: &NodeId) -> String {
+    format!("peer-{}.frames", peer.0)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RecordedNode {
+    id: NodeId,
+    members: Vec<NodeId>,
+    /// Recordings predating the choice of a codec were all written in bincode.
+    #[serde(default)]
+    codec: Format,
+}
+
+/// Something the recorded node did on its own, rather than in answer to a neighbour, naming
+/// the resource it did it on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LocalEvent {
+    /// The node asked for the critical section.
+    Ask(String),
+    /// The node entered the critical section.
+    Enter(String),
+    /// The node left the critical section, or gave up asking for it.
+    Free(String),
+}
+
+/// An entry of a recording, in the order the node went through them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Recorded {
+    /// A frame received from the neighbour.
+    Frame(NodeId),
+    /// Something the node did on its own.
+    Local(LocalEvent),
+}
+
+impl fmt::Display for Recorded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Recorded::Frame(peer) => write!(f, "{}", peer.0),
+            Recorded::Local(LocalEvent::Ask(resource)) => write!(f, "ask {resource}"),
+            Recorded::Local(LocalEvent::Enter(resource)) => write!(f, "enter {resource}"),
+            Recorded::Local(LocalEvent::Free(resource)) => write!(f, "free {resource}"),
+        }
+    }
+}
+
+impl FromStr for Recorded {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let local = match line.split_once(' ') {
+            Some(("ask", resource)) => LocalEvent::Ask(resource.to_string()),
+            Some(("enter", resource)) => LocalEvent::Enter(resource.to_string()),
+            Some(("free", resource)) => LocalEvent::Free(resource.to_string()),
+            _ => {
+                return line
+                    .trim()
+                    .parse()
+                    .map(|id| Recorded::Frame(NodeId(id)))
+                    .map_err(|_| format!("{line:?} is neither a neighbour nor a local event"))
+            }
+        };
+        Ok(Recorded::Local(local))
+    }
+}
+
+/// What a [`Replay`] feeds back, in the order the node went through it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Replayed {
+    /// A frame received from the neighbour.
+    Frame(NodeId, Vec<u8>),
+    /// Something the node did on its own.
+    Local(LocalEvent),
+}
+
+/// Writes every frame a node receives from its neighbours into a directory, along with what
+/// the node did on its own, for a [`Replay`] to feed them back later.
+///
+/// The frames received from each neighbour are appended to their own file, exactly as they
+/// were framed on the wire, while the `order` file keeps track of the neighbour each frame
+/// came from, and of the [`LocalEvent`]s in between. A local event is recorded once done,
+/// which a frame already read at the time is only handled after: replaying such a frame
+/// before the event reorders them.
+#[derive(Debug)]
+pub struct Recorder {
+    dir: PathBuf,
+    files: Mutex<RecorderFiles>,
+}
+
+#[derive(Debug)]
+struct RecorderFiles {
+    order: File,
+    peers: HashMap<NodeId, File>,
+}
+
+impl Recorder {
+    /// Starts recording the node into the directory, created if needed.
+    ///
+    /// Frames are appended to a previous recording of the same node, if any. They are replayed
+    /// with the codec they were encoded with.
+    pub fn create(
+        dir: impl AsRef<Path>,
+        id: &NodeId,
+        members: &[NodeId],
+        codec: Format,
+    ) -> Result<Self, NodeError> {
+        let dir = dir.as_ref().to_path_buf();
+        let error = |e| NodeError::Recording(dir.clone(), e);
+        fs::create_dir_all(&dir).map_err(error)?;
+        let node = RecordedNode {
+            id: id.clone(),
+            members: members.to_vec(),
+            codec,
+        };
+        fs::write(
+            dir.join(NODE_FILE),
+            serde_json::to_vec(&node).map_err(|e| error(e.into()))?,
+        )
+        .map_err(error)?;
+        let order = open_append(&dir.join(ORDER_FILE)).map_err(error)?;
+        Ok(Self {
+            dir,
+            files: Mutex::new(RecorderFiles {
+                order,
+                peers: HashMap::new(),
+            }),
+        })
+    }
+
+    /// Appends a frame received from the neighbour.
+    pub fn record(&self, peer: &NodeId, payload: &[u8]) -> Result<(), NodeError> {
+        let error = |e| NodeError::Recording(self.dir.clone(), e);
+        let mut files = self.files.lock().unwrap();
+        let file = match files.peers.get_mut(peer) {
+            Some(file) => file,
+            None => {
+                let file = open_append(&self.dir.join(peer_file(peer))).map_err(error)?;
+                files.peers.entry(peer.clone()).or_insert(file)
+            }
+        };
+        write_frame(file, payload).map_err(error)?;
+        writeln!(files.order, "{}", Recorded::Frame(peer.clone())).map_err(error)
+    }
+
+    /// Appends something the node did on its own.
+    pub fn record_local(&self, event: LocalEvent) -> Result<(), NodeError> {
+        let mut files = self.files.lock().unwrap();
+        writeln!(files.order, "{}", Recorded::Local(event))
+            .map_err(|e| NodeError::Recording(self.dir.clone(), e))
+    }
+}
+
+fn open_append(path: &Path) -> std::io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+/// A node whose neighbours replay the frames written by a [`Recorder`], through
+/// [`MemoryChannel`]s rather than the network.
+///
+/// # Examples
+///
+/// ```
+/// # use renraku_node::record::{LocalEvent, Recorder, Replay, Replayed};
+/// # use renraku_shared::{codec::Format, NodeId};
+///
+/// let dir = std::env::temp_dir().join(format!("renraku-replay-doc-{}", std::process::id()));
+/// let members = [NodeId(1), NodeId(2), NodeId(3)];
+/// let recorder = Recorder::create(&dir, &NodeId(1), &members, Format::Bincode).unwrap();
+/// recorder.record(&NodeId(3), b"first").unwrap();
+/// recorder.record_local(LocalEvent::Ask("default".into())).unwrap();
+/// recorder.record(&NodeId(2), b"second").unwrap();
+/// recorder.record(&NodeId(3), b"third").unwrap();
+///
+/// let replay = Replay::open(&dir).unwrap();
+/// assert_eq!(replay.config.id, NodeId(1));
+/// let events: Vec<Replayed> = replay.events().map(Result::unwrap).collect();
+/// assert_eq!(
+///     events,
+///     vec![
+///         Replayed::Frame(NodeId(3), b"first".to_vec()),
+///         Replayed::Local(LocalEvent::Ask("default".into())),
+///         Replayed::Frame(NodeId(2), b"second".to_vec()),
+///         Replayed::Frame(NodeId(3), b"third".to_vec()),
+///     ]
+/// );
+/// assert_eq!(Replay::open(&dir).unwrap().frames().count(), 3);
+/// std::fs::remove_dir_all(&dir).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct Replay {
+    /// Configuration of the recorded node, each neighbour holding the frames it sent.
+    ///
+    /// Whatever the node sends is written to the neighbours and never read.
+    pub config: NodeConfig<MemoryChannel>,
+    /// The neighbour each frame has been received from, and what the node did on its own, in
+    /// order.
+    pub order: Vec<Recorded>,
+}
+
+impl Replay {
+    /// Loads the recording written into the directory.
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, NodeError> {
+        let dir = dir.as_ref();
+        let error = |e| NodeError::Recording(dir.to_path_buf(), e);
+        let node: RecordedNode =
+            serde_json::from_slice(&fs::read(dir.join(NODE_FILE)).map_err(error)?)
+                .map_err(|e| error(e.into()))?;
+
+        let order = BufReader::new(File::open(dir.join(ORDER_FILE)).map_err(error)?)
+            .lines()
+            .map(|line| {
+                line.map_err(error)?
+                    .parse()
+                    .map_err(|e| error(std::io::Error::new(std::io::ErrorKind::InvalidData, e)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut neighbours = HashMap::new();
+        let peers = order.iter().filter_map(|recorded| match recorded {
+            Recorded::Frame(peer) => Some(peer),
+            Recorded::Local(_) => None,
+        });
+        for peer in peers {
+            if neighbours.contains_key(peer) {
+                continue;
+            }
+            let (local, mut remote) = MemoryChannel::pair();
+            remote
+                .write_all(&fs::read(dir.join(peer_file(peer))).map_err(error)?)
+                .map_err(error)?;
+            neighbours.insert(peer.clone(), Neighbour::new(local)?.with_codec(node.codec));
+        }
+        Ok(Self {
+            config: NodeConfig::new(node.members.len(), node.id, neighbours)
+                .with_members(node.members),
+            order,
+        })
+    }
+
+    /// Reads each recorded frame along with the neighbour it came from, and each local event,
+    /// in the order the node went through them.
+    ///
+    /// Frames are read out of the neighbours, so that a recording is only replayed once.
+    pub fn events(&self) -> impl Iterator<Item = Result<Replayed, NodeError>> + '_ {
+        self.order.iter().map(|recorded| match recorded {
+            Recorded::Frame(peer) => {
+                let neighbour = self
+                    .config
+                    .neighbours
+                    .get(peer)
+                    .ok_or_else(|| NodeError::UnknownNeighbour(peer.clone()))?;
+                Ok(Replayed::Frame(peer.clone(), neighbour.receive_frame()?))
+            }
+            Recorded::Local(event) => Ok(Replayed::Local(event.clone())),
+        })
+    }
+
+    /// Reads each recorded frame in the order it has been received, along with the neighbour
+    /// it came from, leaving the local events out.
+    pub fn frames(&self) -> impl Iterator<Item = Result<(NodeId, Vec<u8>), NodeError>> + '_ {
+        self.events().filter_map(|event| match event {
+            Ok(Replayed::Frame(peer, frame)) => Some(Ok((peer, frame))),
+            Ok(Replayed::Local(_)) => None,
+            Err(e) => Some(Err(e)),
+        })
+    }
+}
@@ -0,0 +1,426 @@
+//! Runs an algorithm over the neighbours of a node.
+//!
+//! Algorithms keep their state free of any I/O: each of their operations returns the
+//! [`Outgoing`] messages, which [`dispatch`] hands over to the writer thread of each recipient
+//! once the state is unlocked. Handling a message thus never waits on a socket, and a node
+//! never blocks writing to a neighbour that is itself blocked writing to it. Messages are read
+//! by a single thread, see [`receive`].
+
+use std::{
+    fmt::Debug,
+    io,
+    net::UdpSocket,
+    os::fd::AsRawFd,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Condvar, Mutex, PoisonError,
+    },
+    thread::{self, sleep},
+    time::{Duration, Instant},
+};
+
+use renraku_shared::{clock::VectorClock, NodeId};
+use selecting::Selector;
+use serde::{de::DeserializeOwned, Serialize};
+use tracing::{info, warn};
+
+use crate::{
+    channel::Channel, error::NodeError, event::Direction, Neighbour, NodeArguments, NodeConfig,
+};
+
+/// How many selections may fail in a row before [`receive`] gives up.
+pub const SELECT_ATTEMPTS: usize = 3;
+
+/// How long [`receive`] waits before selecting again after a first failure, waiting that much
+/// longer after each of the following ones.
+pub const SELECT_BACKOFF: Duration = Duration::from_millis(100);
+
+/// How long [`run`] waits before asking for the critical section, and holds it.
+#[derive(clap::Args, Debug, Clone)]
+pub struct Cycle {
+    /// Upper bound of the random delay between two requests for the critical section.
+    #[arg(long, default_value_t = 5000)]
+    pub max_wait_ms: u64,
+    /// Upper bound of the random time spent within the critical section.
+    #[arg(long, default_value_t = 5000)]
+    pub max_hold_ms: u64,
+}
+
+/// The arguments of a node doing nothing but asking for the critical section over and over.
+#[derive(clap::Parser, Debug, Clone)]
+#[command(author, version, about, long_about = None)]
+pub struct Arguments {
+    #[command(flatten)]
+    pub node: NodeArguments,
+    #[command(flatten)]
+    pub cycle: Cycle,
+}
+
+/// A message exchanged by the nodes running an algorithm.
+pub trait Message: Serialize + DeserializeOwned {
+    /// Name of the message, as written in the event log.
+    fn kind(&self) -> &'static str;
+
+    fn sender(&self) -> &NodeId;
+
+    /// Stamps the message with the vector clock of its sender, for the messages carrying one.
+    fn stamp(&mut self, _clock: Option<VectorClock>) {}
+
+    fn send_to(&self, neighbour: &Neighbour<impl Channel>) -> Result<(), NodeError> {
+        neighbour.enqueue(neighbour.encode(self)?)
+    }
+
+    fn receive_from(neighbour: &Neighbour<impl Channel>) -> Result<Self, NodeError> {
+        neighbour.decode(&neighbour.receive_frame()?)
+    }
+}
+
+/// Messages to send to the neighbours, along with the clock of the node when issuing them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outgoing<M> {
+    pub clock: usize,
+    pub messages: Vec<(NodeId, M)>,
+}
+
+/// Sends each of the outgoing messages to its recipient, recording them in the event log.
+///
+/// Each message is stamped with the vector clock of the node when sent, if there is one.
+///
+/// Messages to a neighbour no longer written to are dropped when the node waits for it to
+/// rejoin, see [`NodeConfig::awaits_rejoin`].
+///
+/// This must be called without holding the lock of the state of the algorithm, see the
+/// [module](self) documentation.
+pub fn dispatch<M: Message, C: Channel>(
+    outgoing: Outgoing<M>,
+    config: &NodeConfig<C>,
+) -> Result<(), NodeError> {
+    for (recipient, mut message) in outgoing.messages {
+        let neighbour = config
+            .neighbours
+            .get(&recipient)
+            .ok_or_else(|| NodeError::UnknownNeighbour(recipient.clone()))?;
+        config.record(&recipient, Direction::Sent, message.kind(), outgoing.clock)?;
+        message.stamp(config.tick());
+        match message.send_to(&neighbour) {
+            Err(NodeError::Broken(reason)) if config.awaits_rejoin() => {
+                warn!("✉️ Dropping a message for {recipient:?} until it rejoins: {reason}");
+            }
+            sent => sent?,
+        }
+    }
+    Ok(())
+}
+
+/// Waits until some neighbours have something to read, returning their identifiers.
+pub fn select_readable<C: Channel + AsRawFd>(
+    neighbours: &[(&NodeId, &Neighbour<C>)],
+) -> io::Result<Vec<NodeId>> {
+    Ok(select_with_controller(neighbours, None)?.0)
+}
+
+/// Waits until some neighbours have something to read as [`select_readable`] does, or the
+/// controller, returning as well whether the controller has.
+pub fn select_with_controller<C: Channel + AsRawFd>(
+    neighbours: &[(&NodeId, &Neighbour<C>)],
+    controller: Option<&UdpSocket>,
+) -> io::Result<(Vec<NodeId>, bool)> {
+    let mut selector = Selector::new();
+    neighbours
+        .iter()
+        .for_each(|(_, neighbour)| selector.add_read(neighbour.channel()));
+    if let Some(controller) = controller {
+        selector.add_read(controller);
+    }
+
+    let result = selector.select()?;
+    let readable = neighbours
+        .iter()
+        .filter(|(_, n)| result.is_read(n.channel()))
+        .map(|(peer, _)| (*peer).clone())
+        .collect();
+    Ok((readable, controller.is_some_and(|c| result.is_read(c))))
+}
+
+/// Receives the messages of the neighbours, handing each of them to `handle`, until reading
+/// one fails.
+///
+/// The messages of a neighbour are handled in the order it sent them. `select` waits for some
+/// neighbours to be readable, as [`select_readable`] does, among the neighbours of the node
+/// when it is called: a neighbour added while it waits is only waited for from the next call.
+/// A failing selection is logged along with the neighbours it was waiting for, then tried
+/// again after [`SELECT_BACKOFF`] times the number of failures. The error is only returned
+/// once [`SELECT_ATTEMPTS`] selections failed in a row.
+pub fn receive<M, C, E>(
+    config: &NodeConfig<C>,
+    select: impl FnMut(&[(&NodeId, &Neighbour<C>)]) -> io::Result<Vec<NodeId>>,
+    handle: impl FnMut(M) -> Result<(), E>,
+) -> Result<(), E>
+where
+    M: Message,
+    C: Channel,
+    E: From<NodeError>,
+{
+    receive_until(config, false, select, handle)
+}
+
+/// Receives the messages of the neighbours as [`receive`] does, a neighbour whose stream
+/// closed being no longer waited for when `rejoin` is set, until [`NodeConfig::admit`]
+/// replaces it.
+///
+/// Anything else failing to read from a neighbour, such as a frame too large or a message
+/// that cannot be decoded, still stops receiving: the stream is alive but out of sync.
+pub fn receive_until<M, C, E>(
+    config: &NodeConfig<C>,
+    rejoin: bool,
+    mut select: impl FnMut(&[(&NodeId, &Neighbour<C>)]) -> io::Result<Vec<NodeId>>,
+    mut handle: impl FnMut(M) -> Result<(), E>,
+) -> Result<(), E>
+where
+    M: Message,
+    C: Channel,
+    E: From<NodeError>,
+{
+    let mut failures = 0;
+    let mut lost: Vec<Arc<Neighbour<C>>> = Vec::new();
+
+    loop {
+        let neighbours: Vec<(NodeId, Arc<Neighbour<C>>)> = config.neighbours.iter().collect();
+        // A neighbour that rejoined is connected to through another stream
+        lost.retain(|stream| neighbours.iter().any(|(_, n)| Arc::ptr_eq(n, stream)));
+        let selected: Vec<(&NodeId, &Neighbour<C>)> = neighbours
+            .iter()
+            .filter(|(_, n)| !lost.iter().any(|stream| Arc::ptr_eq(n, stream)))
+            .map(|(peer, neighbour)| (peer, &**neighbour))
+            .collect();
+        let readable = match select(&selected) {
+            Ok(readable) => {
+                failures = 0;
+                readable
+            }
+            Err(e) => {
+                failures += 1;
+                let peers: Vec<NodeId> = neighbours.into_iter().map(|(peer, _)| peer).collect();
+                warn!("🔌 Selecting among the streams of {peers:?} failed ({failures}/{SELECT_ATTEMPTS}): {e}");
+                if failures < SELECT_ATTEMPTS {
+                    sleep(SELECT_BACKOFF * failures as u32);
+                    continue;
+                }
+                return Err(NodeError::Select(peers, failures, e).into());
+            }
+        };
+        for peer in readable {
+            let neighbour = neighbours
+                .iter()
+                .find_map(|(id, neighbour)| (*id == peer).then_some(neighbour))
+                .ok_or_else(|| NodeError::UnknownNeighbour(peer.clone()))?;
+            // Frames read along with the first one would never make the stream readable again
+            loop {
+                let message = match M::receive_from(neighbour) {
+                    Ok(message) => message,
+                    Err(e) if rejoin && e.is_disconnection() => {
+                        warn!("🔌 Lost {peer:?}, waiting for it to rejoin: {e}");
+                        lost.push(neighbour.clone());
+                        break;
+                    }
+                    Err(e) => return Err(NodeError::Receive(peer.clone(), Box::new(e)).into()),
+                };
+                handle(message)?;
+                if !neighbour.has_buffered_frame() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// An algorithm granting a single critical section, which [`run`] asks for over and over.
+pub trait Exclusion: Debug + Send + 'static {
+    type Message: Message;
+
+    /// Asks for the critical section.
+    fn ask(&mut self) -> Outgoing<Self::Message>;
+
+    /// Leaves the critical section.
+    fn free(&mut self) -> Outgoing<Self::Message>;
+
+    /// Handles a message received from a neighbour.
+    fn handle(&mut self, message: Self::Message) -> Outgoing<Self::Message>;
+
+    /// Lamport clock of the node, as written in the event log.
+    fn clock(&self) -> usize;
+
+    /// Whether the algorithm takes nodes joining the system once it was set up into account,
+    /// see [`Exclusion::join`]. Nodes running any other refuse to connect to them.
+    const SUPPORTS_JOIN: bool = false;
+
+    /// Takes into account a node that joined the system once it was set up, which is now a
+    /// neighbour of this one, or a neighbour that restarted, see [`admit`].
+    ///
+    /// Only called when the algorithm [supports it](Exclusion::SUPPORTS_JOIN).
+    fn join(&mut self, _peer: NodeId) -> Outgoing<Self::Message> {
+        Outgoing {
+            clock: self.clock(),
+            messages: Vec::new(),
+        }
+    }
+
+    fn in_critical_section(&self) -> bool;
+}
+
+/// Handles the messages of the neighbours, waking up whoever waits on `permission_signal` as
+/// soon as the critical section is entered.
+///
+/// Nodes the controller tells joined the system are connected to as soon as it does, then
+/// handed to the algorithm, see [`Exclusion::join`]. A neighbour whose stream closed stops
+/// the thread, unless the node [awaits it to rejoin](NodeConfig::awaits_rejoin).
+pub fn receive_thread<A: Exclusion>(
+    mutex: Arc<Mutex<A>>,
+    permission_signal: Arc<Condvar>,
+    config: Arc<NodeConfig>,
+) -> Result<(), NodeError> {
+    let controller = config.controller.as_ref().map(|link| &link.socket);
+    let select = |neighbours: &[(&NodeId, &Neighbour)]| {
+        let (readable, joining) = select_with_controller(neighbours, controller)?;
+        if joining {
+            admit(&config, A::SUPPORTS_JOIN, |peer| {
+                mutex.lock().unwrap().join(peer)
+            });
+        }
+        Ok(readable)
+    };
+    receive_until(
+        &config,
+        config.awaits_rejoin(),
+        select,
+        |message: A::Message| {
+            let mut v = mutex.lock().unwrap();
+            config.record(
+                message.sender(),
+                Direction::Received,
+                message.kind(),
+                v.clock(),
+            )?;
+            let outgoing = v.handle(message);
+            if v.in_critical_section() {
+                permission_signal.notify_all();
+            }
+            drop(v);
+            dispatch(outgoing, &config)
+        },
+    )
+}
+
+/// Connects to the node the controller tells joined the system, then hands it to `join`,
+/// sending the messages it returns. The controller socket must be readable.
+///
+/// A node whose algorithm is not `supported` by its neighbours refuses to connect to it.
+/// Neither that nor failing to connect is a reason for this node to stop: the joining node
+/// fails its own setup instead, lacking a neighbour.
+pub fn admit<M: Message>(
+    config: &NodeConfig,
+    supported: bool,
+    join: impl FnOnce(NodeId) -> Outgoing<M>,
+) {
+    let (peer, address) = match config.receive_join() {
+        Ok(Some(joining)) => joining,
+        Ok(None) => return,
+        Err(e) => {
+            warn!("🆕 Receiving a node joining the system: {e}");
+            return;
+        }
+    };
+    if !supported {
+        warn!("🆕 Refusing {peer:?}, the algorithm does not support nodes joining the system");
+        return;
+    }
+    if let Err(e) = config.admit(&peer, address) {
+        warn!("🆕 Admitting {peer:?} joining the system: {e}");
+        return;
+    }
+    let outgoing = join(peer);
+    if let Err(e) = dispatch(outgoing, config) {
+        warn!("🆕 Telling the joining node about this one: {e}");
+    }
+}
+
+/// Runs the algorithm on this node for ever, asking for the critical section after a random
+/// delay and holding it for another, both bounded by `cycle`.
+pub fn run<A: Exclusion>(state: A, config: NodeConfig, cycle: &Cycle) -> Result<(), NodeError> {
+    config.start_writers();
+    let config = Arc::new(config);
+    let mutex = Arc::new(Mutex::new(state));
+    let permission = Arc::new(Condvar::new());
+
+    let stopped = Arc::new(AtomicBool::new(false));
+    let t = (mutex.clone(), permission.clone(), config.clone());
+    let flag = stopped.clone();
+    let receiver = thread::spawn(move || {
+        let _stopped = Stopped {
+            mutex: &*t.0,
+            signal: &t.1,
+            flag: &flag,
+        };
+        receive_thread(t.0.clone(), t.1.clone(), t.2)
+    });
+
+    loop {
+        sleep(jitter(cycle.max_wait_ms));
+        let asked_at = Instant::now();
+        let outgoing = mutex.lock().unwrap().ask();
+        dispatch(outgoing, &config)?;
+        let lock = permission
+            .wait_while(mutex.lock().unwrap(), |v| {
+                !v.in_critical_section() && !stopped.load(Ordering::SeqCst)
+            })
+            .unwrap();
+        drop(lock);
+        if stopped.load(Ordering::SeqCst) {
+            return receiver
+                .join()
+                .unwrap_or_else(|_| Err(io::Error::other("the receiving thread panicked").into()));
+        }
+        info!(
+            latency_us = asked_at.elapsed().as_micros() as u64,
+            "👍 Entering critical section"
+        );
+        sleep(jitter(cycle.max_hold_ms));
+        info!("👍 Leaving critical section");
+        let outgoing = mutex.lock().unwrap().free();
+        dispatch(outgoing, &config)?;
+    }
+}
+
+/// Tells whoever waits for the critical section that the receiving thread stopped, once
+/// dropped, the critical section then never being granted.
+struct Stopped<'a, A> {
+    mutex: &'a Mutex<A>,
+    signal: &'a Condvar,
+    flag: &'a AtomicBool,
+}
+
+impl<A> Drop for Stopped<'_, A> {
+    fn drop(&mut self) {
+        let _lock = self.mutex.lock().unwrap_or_else(PoisonError::into_inner);
+        self.flag.store(true, Ordering::SeqCst);
+        self.signal.notify_all();
+    }
+}
+
+/// Picks a random duration lower than the given bound in milliseconds.
+///
+/// # Examples
+///
+/// ```
+/// # use renraku_node::runtime::jitter;
+/// use std::time::Duration;
+///
+/// assert!((0..1000).all(|_| jitter(20) < Duration::from_millis(20)));
+/// assert_eq!(jitter(0), Duration::ZERO);
+/// ```
+pub fn jitter(max_ms: u64) -> Duration {
+    match max_ms {
+        0 => Duration::ZERO,
+        max => Duration::from_millis(rand::random::<u64>() % max),
+    }
+}
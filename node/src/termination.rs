@@ -0,0 +1,173 @@
+use renraku_shared::NodeId;
+use serde::{Deserialize, Serialize};
+
+use crate::runtime::{self, Outgoing};
+
+/// Messages of the Dijkstra-Scholten termination detection algorithm.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum Message {
+    /// Hands some work to the recipient, which may engage in the computation.
+    Activate { sender: NodeId },
+    /// Signals that the work handed by the recipient is done.
+    Ack { sender: NodeId },
+}
+
+impl runtime::Message for Message {
+    fn kind(&self) -> &'static str {
+        match self {
+            Message::Activate { .. } => "Activate",
+            Message::Ack { .. } => "Ack",
+        }
+    }
+
+    fn sender(&self) -> &NodeId {
+        match self {
+            Message::Activate { sender } | Message::Ack { sender } => sender,
+        }
+    }
+}
+
+/// State of a node taking part in a diffusing computation, following Dijkstra-Scholten.
+///
+/// The root starts the computation. A node engages when it is first activated, the sender
+/// becoming its parent in a spanning tree of the computation. Every activation is eventually
+/// acknowledged: right away when the recipient is already engaged, and otherwise once the
+/// recipient is passive and all of its own activations have been acknowledged, at which point
+/// it leaves the tree. The computation has globally terminated once the root is passive and
+/// has no outstanding acknowledgement.
+///
+/// # Examples
+///
+/// ```
+/// # use renraku_node::termination::TerminationDetector;
+/// # use renraku_shared::NodeId;
+///
+/// let mut root = TerminationDetector::root(NodeId(1));
+/// let mut leaf = TerminationDetector::new(NodeId(2));
+///
+/// let (_, activate) = root.activate(NodeId(2)).messages.remove(0);
+/// assert!(leaf.handle(activate).messages.is_empty());
+/// assert!(root.passivate().messages.is_empty());
+/// assert!(!root.is_terminated());
+///
+/// for (_, ack) in leaf.passivate().messages {
+///     root.handle(ack);
+/// }
+/// assert!(root.is_terminated());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct TerminationDetector {
+    pub id: NodeId,
+    /// Node that engaged us in the computation, `None` for the root or a disengaged node.
+    pub parent: Option<NodeId>,
+    /// Number of activations sent that have not been acknowledged yet.
+    pub deficit: usize,
+    /// Whether the node is still working on its share of the computation.
+    pub active: bool,
+    /// Number of activations sent, written in the event log as the clock of the node.
+    pub activations: usize,
+    is_root: bool,
+    terminated: bool,
+}
+
+impl TerminationDetector {
+    /// A node waiting to be engaged in the computation.
+    pub fn new(id: NodeId) -> Self {
+        Self {
+            id,
+            parent: None,
+            deficit: 0,
+            active: false,
+            activations: 0,
+            is_root: false,
+            terminated: false,
+        }
+    }
+
+    /// The node starting the computation, active from the outset.
+    pub fn root(id: NodeId) -> Self {
+        Self {
+            active: true,
+            is_root: true,
+            ..Self::new(id)
+        }
+    }
+
+    /// Returns whether the node currently belongs to the tree of the computation.
+    pub fn is_engaged(&self) -> bool {
+        self.parent.is_some() || (self.is_root && !self.terminated)
+    }
+
+    /// Returns whether the whole computation is over, which only the root can tell.
+    pub fn is_terminated(&self) -> bool {
+        self.terminated
+    }
+
+    /// Hands some work to a neighbour, returning the message to send to it.
+    pub fn activate(&mut self, neighbour: NodeId) -> Outgoing<Message> {
+        self.deficit += 1;
+        self.activations += 1;
+        let activate = Message::Activate {
+            sender: self.id.clone(),
+        };
+        self.outgoing(vec![(neighbour, activate)])
+    }
+
+    /// Tells the node its own work is done, returning the acknowledgement to its parent if it
+    /// leaves the tree.
+    pub fn passivate(&mut self) -> Outgoing<Message> {
+        self.active = false;
+        let messages = self.try_detach();
+        self.outgoing(messages)
+    }
+
+    pub fn handle(&mut self, message: Message) -> Outgoing<Message> {
+        let messages = match message {
+            Message::Activate { sender } => {
+                self.active = true;
+                if self.is_engaged() {
+                    vec![(
+                        sender,
+                        Message::Ack {
+                            sender: self.id.clone(),
+                        },
+                    )]
+                } else {
+                    self.parent = Some(sender);
+                    Vec::new()
+                }
+            }
+            Message::Ack { .. } => {
+                self.deficit = self.deficit.saturating_sub(1);
+                self.try_detach()
+            }
+        };
+        self.outgoing(messages)
+    }
+
+    fn outgoing(&self, messages: Vec<(NodeId, Message)>) -> Outgoing<Message> {
+        Outgoing {
+            clock: self.activations,
+            messages,
+        }
+    }
+
+    /// Leaves the tree once passive with every activation acknowledged.
+    fn try_detach(&mut self) -> Vec<(NodeId, Message)> {
+        if self.active || self.deficit > 0 {
+            return Vec::new();
+        }
+        if self.is_root {
+            self.terminated = true;
+        }
+        match self.parent.take() {
+            Some(parent) => vec![(
+                parent,
+                Message::Ack {
+                    sender: self.id.clone(),
+                },
+            )],
+            None => Vec::new(),
+        }
+    }
+}
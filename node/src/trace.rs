@@ -0,0 +1,88 @@
+use serde::{Deserialize, Serialize};
+use tracing::{info_span, Span};
+
+/// Identifies the operation a span belongs to, so that the spans several nodes open for it
+/// can be correlated in their logs.
+///
+/// The identifiers are only recorded as fields of the spans, which no exporter links into a
+/// single trace: grepping the logs for a `trace_id` gathers the spans of an operation, and
+/// `parent_span_id` tells which span led to which. They are formatted after the W3C Trace
+/// Context, only to be readable as a `traceparent` header.
+///
+/// # Examples
+///
+/// ```
+/// # use renraku_node::trace::TraceContext;
+///
+/// let root = TraceContext::root();
+/// let child = root.child();
+/// assert_eq!(child.trace_id, root.trace_id);
+/// assert_ne!(child.span_id, root.span_id);
+///
+/// let header = child.traceparent();
+/// assert_eq!(header.len(), 55);
+/// assert_eq!(TraceContext::from_traceparent(&header), Some(child));
+/// assert_eq!(TraceContext::from_traceparent("00-zz-00-01"), None);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TraceContext {
+    pub trace_id: u128,
+    pub span_id: u64,
+}
+
+impl TraceContext {
+    /// Starts a new trace.
+    pub fn root() -> Self {
+        Self {
+            // Zero is an invalid identifier for both
+            trace_id: rand::random::<u128>().max(1),
+            span_id: rand::random::<u64>().max(1),
+        }
+    }
+
+    /// A new span within the same trace.
+    pub fn child(&self) -> Self {
+        Self {
+            trace_id: self.trace_id,
+            span_id: rand::random::<u64>().max(1),
+        }
+    }
+
+    /// Formats the context as a W3C `traceparent` header.
+    pub fn traceparent(&self) -> String {
+        format!("00-{:032x}-{:016x}-01", self.trace_id, self.span_id)
+    }
+
+    /// Parses a W3C `traceparent` header.
+    pub fn from_traceparent(header: &str) -> Option<Self> {
+        let mut fields = header.split('-');
+        let (Some("00"), Some(trace_id), Some(span_id), Some(_)) =
+            (fields.next(), fields.next(), fields.next(), fields.next())
+        else {
+            return None;
+        };
+        if trace_id.len() != 32 || span_id.len() != 16 {
+            return None;
+        }
+        Some(Self {
+            trace_id: u128::from_str_radix(trace_id, 16).ok()?,
+            span_id: u64::from_str_radix(span_id, 16).ok()?,
+        })
+    }
+
+    /// Opens a span continuing the trace, returning it along with its own context.
+    ///
+    /// The span records the operation it handles, along with the identifiers of the trace, of
+    /// itself and of its parent.
+    pub fn child_span(&self, name: &'static str) -> (Self, Span) {
+        let child = self.child();
+        let span = info_span!(
+            "traced",
+            operation = name,
+            trace_id = %format_args!("{:032x}", child.trace_id),
+            span_id = %format_args!("{:016x}", child.span_id),
+            parent_span_id = %format_args!("{:016x}", self.span_id),
+        );
+        (child, span)
+    }
+}
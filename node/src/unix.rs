@@ -0,0 +1,47 @@
+use std::{
+    fs, io,
+    net::Shutdown,
+    os::unix::net::{UnixListener, UnixStream},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::{channel::Channel, error::NodeError};
+
+impl Channel for UnixStream {
+    fn try_clone(&self) -> io::Result<Self> {
+        UnixStream::try_clone(self)
+    }
+
+    fn shutdown(&self) -> io::Result<()> {
+        UnixStream::shutdown(self, Shutdown::Both)
+    }
+
+    fn set_write_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        UnixStream::set_write_timeout(self, timeout)
+    }
+
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        UnixStream::set_read_timeout(self, timeout)
+    }
+}
+
+/// Path of the socket a node listens on within the directory, named after the port the
+/// controller hands out to its neighbours, so that they find it from the address they are
+/// told to connect to.
+pub fn socket_path(dir: impl AsRef<Path>, port: u16) -> PathBuf {
+    dir.as_ref().join(format!("node-{port}.sock"))
+}
+
+/// Binds the socket the node listens on for its neighbours, replacing the one left by a
+/// previous run if any.
+pub fn listen(dir: impl AsRef<Path>, port: u16) -> Result<UnixListener, NodeError> {
+    let path = socket_path(dir, port);
+    match fs::remove_file(&path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => {
+            return Err(NodeError::Bind("UNIX socket for neighbours", e))
+        }
+        _ => {}
+    }
+    UnixListener::bind(&path).map_err(|e| NodeError::Bind("UNIX socket for neighbours", e))
+}
@@ -0,0 +1,43 @@
+use clap::Parser;
+use renraku_node::NodeArguments;
+
+fn controller(args: &[&str]) -> Result<String, clap::Error> {
+    let args = ["node"].iter().chain(args);
+    NodeArguments::try_parse_from(args).map(|args| args.controller)
+}
+
+#[test]
+fn invalid_controller_is_rejected_when_parsing_arguments() {
+    let error = controller(&["--controller", "localhost;3000"]).unwrap_err();
+    assert_eq!(error.kind(), clap::error::ErrorKind::ValueValidation);
+    assert!(error.to_string().contains("host:port"), "{error}");
+
+    assert!(controller(&["--controller", "localhost:port"]).is_err());
+}
+
+#[test]
+fn valid_controller_is_normalized() {
+    assert_eq!(controller(&[]).unwrap(), "localhost:3000");
+    assert_eq!(
+        controller(&["-c", "Coordinator.local:4000"]).unwrap(),
+        "coordinator.local:4000"
+    );
+    assert_eq!(controller(&["-c", "[::1]:4000"]).unwrap(), "[::1]:4000");
+}
+
+#[test]
+fn advertised_address_is_read_from_the_environment() {
+    // Only this test reads the variable, so that setting it does not race with the others
+    std::env::set_var("RENRAKU_ADVERTISE_ADDR", "203.0.113.7:30443");
+    let parse = |args: &[&str]| {
+        let args = ["node"].iter().chain(args);
+        NodeArguments::try_parse_from(args).unwrap().advertise_addr
+    };
+
+    assert_eq!(parse(&[]), Some("203.0.113.7:30443".parse().unwrap()));
+    // The command line takes precedence over the environment
+    assert_eq!(
+        parse(&["--advertise-addr", "198.51.100.1:4000"]),
+        Some("198.51.100.1:4000".parse().unwrap())
+    );
+}
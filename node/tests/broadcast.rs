@@ -0,0 +1,138 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fs,
+    sync::{Arc, Mutex},
+};
+
+use renraku_node::{
+    broadcast::{Message, TotalOrderBroadcast},
+    channel::MemoryChannel,
+    clock_file::ClockFile,
+    runtime::{Message as _, Outgoing},
+    Neighbour, NodeConfig,
+};
+use renraku_shared::NodeId;
+
+const NODES: usize = 3;
+const BROADCASTS: usize = 10;
+
+/// FIFO channels between each pair of nodes.
+type Network = HashMap<(usize, usize), VecDeque<Message<String>>>;
+
+/// Payloads delivered to a node, along with their sender.
+type Delivered = Arc<Mutex<Vec<(NodeId, String)>>>;
+
+fn send(network: &mut Network, from: usize, outgoing: Outgoing<Message<String>>) {
+    for (to, message) in outgoing.messages {
+        network.entry((from, to.0)).or_default().push_back(message);
+    }
+}
+
+#[test]
+fn every_node_delivers_in_the_same_total_order() {
+    let delivered: Vec<Delivered> = (0..NODES).map(|_| Arc::default()).collect();
+    let mut nodes: Vec<TotalOrderBroadcast<String>> = (1..=NODES)
+        .map(|i| {
+            let log = delivered[i - 1].clone();
+            TotalOrderBroadcast::new(
+                NodeId(i),
+                (1..=NODES).map(NodeId).collect(),
+                move |sender, payload| log.lock().unwrap().push((sender.clone(), payload)),
+            )
+        })
+        .collect();
+    let mut network = Network::new();
+    let mut sent = [0; NODES];
+    let mut seed: u64 = 7;
+
+    loop {
+        seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        let pick = (seed >> 33) as usize;
+        // Nodes broadcast concurrently, while messages are in flight
+        let i = (pick >> 4) % NODES;
+        if sent[i] < BROADCASTS && pick.is_multiple_of(3) {
+            sent[i] += 1;
+            let messages = nodes[i].broadcast(format!("{} from {}", sent[i], i + 1));
+            send(&mut network, i + 1, messages);
+            continue;
+        }
+
+        // Delivers the head of a pseudo-randomly chosen non-empty channel
+        let mut pending: Vec<(usize, usize)> = network
+            .iter()
+            .filter(|(_, channel)| !channel.is_empty())
+            .map(|(k, _)| *k)
+            .collect();
+        if pending.is_empty() {
+            if sent.iter().all(|s| *s == BROADCASTS) {
+                break;
+            }
+            continue;
+        }
+        pending.sort();
+        let (from, to) = pending[pick % pending.len()];
+        let message = network.get_mut(&(from, to)).unwrap().pop_front().unwrap();
+        let messages = nodes[to - 1].handle(message);
+        send(&mut network, to, messages);
+    }
+
+    let reference = delivered[0].lock().unwrap().clone();
+    assert_eq!(reference.len(), NODES * BROADCASTS);
+    for log in &delivered[1..] {
+        assert_eq!(*log.lock().unwrap(), reference);
+    }
+    assert!(nodes.iter().all(|node| node.pending.is_empty()));
+    // Each node delivers its own payloads in the order it broadcast them
+    for i in 1..=NODES {
+        let own: Vec<&String> = reference
+            .iter()
+            .filter(|(sender, _)| sender.0 == i)
+            .map(|(_, payload)| payload)
+            .collect();
+        let expected: Vec<String> = (1..=BROADCASTS).map(|n| format!("{n} from {i}")).collect();
+        assert_eq!(own, expected.iter().collect::<Vec<_>>());
+    }
+}
+
+#[test]
+fn nodes_resume_the_clock_they_persisted() {
+    let (first, second) = MemoryChannel::pair();
+    let path = std::env::temp_dir().join(format!("renraku-broadcast-{}", std::process::id()));
+    ClockFile::new(&path).save(41).unwrap();
+    let config = |id: usize, peer: usize, channel| {
+        let neighbours = HashMap::from([(NodeId(peer), Neighbour::new(channel).unwrap())]);
+        NodeConfig::new(2, NodeId(id), neighbours)
+    };
+    let mut first_config = config(1, 2, first);
+    first_config.clock_file = Some(ClockFile::new(&path));
+    let second_config = config(2, 1, second);
+
+    let delivered: Delivered = Arc::default();
+    let log = delivered.clone();
+    let mut first = TotalOrderBroadcast::on(&first_config, move |sender, payload| {
+        log.lock().unwrap().push((sender.clone(), payload))
+    })
+    .unwrap();
+    let mut second = TotalOrderBroadcast::on(&second_config, |_, _: String| {}).unwrap();
+
+    first.send("hello".to_string(), &first_config).unwrap();
+    let received = |config: &NodeConfig<MemoryChannel>, peer| {
+        Message::receive_from(&config.neighbours.get(&NodeId(peer)).unwrap()).unwrap()
+    };
+    let broadcast = received(&second_config, 1);
+    assert!(matches!(broadcast, Message::Broadcast { date: 42, .. }));
+    second.receive(broadcast, &second_config).unwrap();
+    first
+        .receive(received(&first_config, 2), &first_config)
+        .unwrap();
+
+    assert_eq!(
+        *delivered.lock().unwrap(),
+        [(NodeId(1), "hello".to_string())]
+    );
+    // Handling the acknowledgement, dated 43 by the second node, moved the clock past it
+    assert_eq!(ClockFile::new(&path).load().unwrap(), 44);
+    fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,94 @@
+use std::{
+    io::{self, Write},
+    net::{SocketAddr, TcpListener, TcpStream, UdpSocket},
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use renraku_node::{configure, handshake, NodeArguments};
+use renraku_shared::{control::ControlMessage, NodeId, Placement};
+
+/// Lines logged by every thread of the test.
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn peers_are_logged_along_with_their_id() {
+    let captured = Captured::default();
+    let writer = captured.clone();
+    tracing_subscriber::fmt()
+        .with_writer(move || writer.clone())
+        .with_ansi(false)
+        .init();
+
+    // Node 2 is dialed by the node, node 3 connects to it
+    let dialed = TcpListener::bind("127.0.0.1:0").unwrap();
+    let dialed_addr = dialed.local_addr().unwrap();
+    let neighbour = thread::spawn(move || {
+        let (mut stream, peer) = dialed.accept().unwrap();
+        handshake::exchange(&mut stream, peer, &NodeId(2), false).unwrap()
+    });
+    let controller = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let args = NodeArguments {
+        controller: controller.local_addr().unwrap().to_string(),
+        ..NodeArguments::default()
+    };
+    let node = thread::spawn(move || configure(args));
+
+    let mut buf = [0; 1024];
+    let (received, addr) = controller.recv_from(&mut buf).unwrap();
+    let ControlMessage::Hello { listen_port, .. } =
+        ControlMessage::decode(&buf[..received]).unwrap()
+    else {
+        panic!("expected a hello");
+    };
+    let messages = [
+        ControlMessage::Registered,
+        ControlMessage::Assign {
+            id: NodeId(1),
+            members: vec![NodeId(1), NodeId(2), NodeId(3)],
+        },
+        ControlMessage::IncomingCount(1),
+        ControlMessage::Outgoing(vec![dialed_addr.into()]),
+        ControlMessage::Quorum(vec![NodeId(1)]),
+        ControlMessage::Role(None),
+        ControlMessage::Labels(Vec::new()),
+        ControlMessage::Placement(Placement::Internal),
+        ControlMessage::NeighbourCount(2),
+    ];
+    for message in messages {
+        controller
+            .send_to(&message.encode().unwrap(), addr)
+            .unwrap();
+    }
+    let mut accepted = TcpStream::connect(SocketAddr::new(addr.ip(), listen_port)).unwrap();
+    let accepted_addr = accepted.local_addr().unwrap();
+    let listener = accepted.peer_addr().unwrap();
+    handshake::exchange(&mut accepted, listener, &NodeId(3), true).unwrap();
+
+    let config = node.join().unwrap().unwrap();
+    assert_eq!(neighbour.join().unwrap(), NodeId(1));
+    assert_eq!(config.neighbours.len(), 2);
+    let logs = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+    assert!(
+        logs.contains(&format!(
+            "Accepted a connection from {accepted_addr}, identified as NodeId(3)"
+        )),
+        "{logs}"
+    );
+    assert!(
+        logs.contains(&format!("Dialed {dialed_addr} for NodeId(2)")),
+        "{logs}"
+    );
+}
@@ -0,0 +1,84 @@
+use std::{
+    net::{TcpListener, TcpStream},
+    sync::mpsc,
+    thread,
+    time::Instant,
+};
+
+use renraku_node::{
+    handshake::{exchange, Message, HANDSHAKE_TIMEOUT},
+    NodeError,
+};
+use renraku_shared::{
+    frame::{read_frame, write_frame},
+    NodeId,
+};
+
+#[test]
+fn both_peers_acknowledge_the_exchanged_ids() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let acceptor = thread::spawn(move || {
+        let (mut stream, peer) = listener.accept().unwrap();
+        let id = exchange(&mut stream, peer, &NodeId(2), false).unwrap();
+        // The algorithm starts right after the handshake
+        (id, read_frame(&mut stream).unwrap())
+    });
+
+    let mut stream = TcpStream::connect(addr).unwrap();
+    assert_eq!(
+        exchange(&mut stream, addr, &NodeId(1), true).unwrap(),
+        NodeId(2)
+    );
+    write_frame(&mut stream, b"first message").unwrap();
+    assert_eq!(
+        acceptor.join().unwrap(),
+        (NodeId(1), b"first message".to_vec())
+    );
+}
+
+#[test]
+fn acknowledging_another_id_is_rejected() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    // A peer that stored the dialing node under the wrong id
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        read_frame(&mut stream).unwrap();
+        for message in [Message::Id(NodeId(2)), Message::Ack(NodeId(3))] {
+            write_frame(&mut stream, &bincode::serialize(&message).unwrap()).unwrap();
+        }
+        read_frame(&mut stream).unwrap();
+    });
+
+    let mut stream = TcpStream::connect(addr).unwrap();
+    assert!(matches!(
+        exchange(&mut stream, addr, &NodeId(1), true),
+        Err(NodeError::Unacknowledged { expected: NodeId(1), received, .. })
+            if *received == Message::Ack(NodeId(3))
+    ));
+}
+
+#[test]
+fn peer_that_never_acknowledges_is_given_up() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    // A peer that answers with its id, then goes silent while keeping the connection open
+    let (release, released) = mpsc::channel::<()>();
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        read_frame(&mut stream).unwrap();
+        let id = bincode::serialize(&Message::Id(NodeId(2))).unwrap();
+        write_frame(&mut stream, &id).unwrap();
+        released.recv().ok();
+    });
+
+    let mut stream = TcpStream::connect(addr).unwrap();
+    let start = Instant::now();
+    assert!(matches!(
+        exchange(&mut stream, addr, &NodeId(1), true),
+        Err(NodeError::Handshake(..))
+    ));
+    assert!(start.elapsed() >= HANDSHAKE_TIMEOUT);
+    drop(release);
+}
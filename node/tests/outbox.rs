@@ -0,0 +1,38 @@
+use std::{sync::Arc, thread};
+
+use renraku_node::{channel::MemoryChannel, Neighbour};
+use renraku_shared::frame::frame;
+
+const THREADS: usize = 8;
+const MESSAGES: usize = 500;
+
+#[test]
+fn queued_messages_are_delivered_in_order() {
+    let (local, remote) = MemoryChannel::pair();
+    let sender = Arc::new(Neighbour::new(local).unwrap());
+    let receiver = Neighbour::new(remote).unwrap();
+    sender.start_writer();
+
+    // Several handlers queue their messages concurrently
+    let handlers: Vec<_> = (0..THREADS)
+        .map(|t| {
+            let sender = sender.clone();
+            thread::spawn(move || {
+                for i in 0..MESSAGES {
+                    let payload = bincode::serialize(&(t, i)).unwrap();
+                    sender.enqueue(frame(&payload).unwrap()).unwrap();
+                }
+            })
+        })
+        .collect();
+    handlers.into_iter().for_each(|h| h.join().unwrap());
+
+    // Messages of each handler arrive whole and in the order they were queued
+    let mut next = [0; THREADS];
+    for _ in 0..THREADS * MESSAGES {
+        let (t, i): (usize, usize) = receiver.decode(&receiver.receive_frame().unwrap()).unwrap();
+        assert_eq!(i, next[t], "message of handler {t}");
+        next[t] += 1;
+    }
+    assert_eq!(next, [MESSAGES; THREADS]);
+}
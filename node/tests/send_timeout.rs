@@ -0,0 +1,64 @@
+use std::{
+    io::Read,
+    net::{TcpListener, TcpStream},
+    thread,
+    time::{Duration, Instant},
+};
+
+use renraku_node::{Neighbour, NodeError};
+
+#[test]
+fn sending_to_a_neighbour_that_does_not_read_times_out() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    // Never read, so that the socket buffers end up full
+    let (mut peer, _) = listener.accept().unwrap();
+    let timeout = Duration::from_millis(100);
+    let neighbour = Neighbour::new(stream)
+        .unwrap()
+        .with_send_timeout(Some(timeout))
+        .unwrap();
+
+    let started = Instant::now();
+    let message = vec![0; 1 << 20];
+    let error = (0..256)
+        .find_map(|_| neighbour.send(&message).err())
+        .expect("the socket buffers hold less than 256 MiB");
+
+    assert!(matches!(error, NodeError::SendTimeout(t) if t == timeout));
+    assert!(started.elapsed() < Duration::from_secs(10));
+    assert!(matches!(neighbour.send(b"late"), Err(NodeError::Broken(_))));
+    // The stream may end within a frame, but nothing is written to it after the timeout
+    peer.set_read_timeout(Some(Duration::from_secs(10)))
+        .unwrap();
+    assert!(peer.read_to_end(&mut Vec::new()).is_ok());
+}
+
+#[test]
+fn queued_messages_fail_once_the_writer_timed_out() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let (_peer, _) = listener.accept().unwrap();
+    let neighbour = Neighbour::new(stream)
+        .unwrap()
+        .with_send_timeout(Some(Duration::from_millis(100)))
+        .unwrap();
+    neighbour.start_writer();
+
+    // The writer thread is the one timing out, once the socket buffers are full
+    for _ in 0..64 {
+        neighbour.enqueue(vec![0; 1 << 20]).unwrap();
+    }
+    let started = Instant::now();
+    while !neighbour.is_broken() {
+        assert!(
+            started.elapsed() < Duration::from_secs(10),
+            "the writer never timed out"
+        );
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    let error = neighbour.enqueue(b"late".to_vec()).unwrap_err();
+    assert!(matches!(error, NodeError::Broken(reason) if reason.contains("not read anything")));
+    assert!(neighbour.is_broken());
+}
@@ -0,0 +1,161 @@
+use std::{
+    net::{SocketAddr, TcpListener, TcpStream, UdpSocket},
+    thread,
+    time::Duration,
+};
+
+use renraku_node::{configure, handshake, NodeArguments, NodeConfig, NodeError};
+use renraku_shared::{
+    control::ControlMessage,
+    frame::{read_frame, write_frame},
+    NodeId, Placement,
+};
+
+/// Configures a node against a fake controller, which answers its hello with the setup of
+/// node 1 of 2, connecting to the `outgoing` addresses.
+fn configure_with(outgoing: Vec<SocketAddr>) -> Result<NodeConfig, NodeError> {
+    configure_dialed_by(Vec::new(), outgoing)
+}
+
+/// Same as [`configure_with`], the node also being dialed by a neighbour presenting itself
+/// with each of the `incoming` identifiers.
+fn configure_dialed_by(
+    incoming: Vec<NodeId>,
+    outgoing: Vec<SocketAddr>,
+) -> Result<NodeConfig, NodeError> {
+    configure_announcing(incoming.len() + outgoing.len(), incoming, outgoing)
+}
+
+/// Same as [`configure_dialed_by`], the controller announcing `neighbour_count` neighbours.
+fn configure_announcing(
+    neighbour_count: usize,
+    incoming: Vec<NodeId>,
+    outgoing: Vec<SocketAddr>,
+) -> Result<NodeConfig, NodeError> {
+    let controller = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let args = NodeArguments {
+        controller: controller.local_addr().unwrap().to_string(),
+        ..NodeArguments::default()
+    };
+    let node = thread::spawn(move || configure(args));
+
+    let mut buf = [0; 1024];
+    let (received, addr) = controller.recv_from(&mut buf).unwrap();
+    let Ok(ControlMessage::Hello { listen_port, .. }) = ControlMessage::decode(&buf[..received])
+    else {
+        panic!("the node did not say hello");
+    };
+    let messages = [
+        ControlMessage::Registered,
+        ControlMessage::Assign {
+            id: NodeId(1),
+            members: vec![NodeId(1), NodeId(2)],
+        },
+        ControlMessage::IncomingCount(incoming.len()),
+        ControlMessage::Outgoing(outgoing.into_iter().map(Into::into).collect()),
+        ControlMessage::Quorum(vec![NodeId(1)]),
+        ControlMessage::Role(None),
+        ControlMessage::Labels(Vec::new()),
+        ControlMessage::Placement(Placement::Source),
+        ControlMessage::NeighbourCount(neighbour_count),
+    ];
+    for message in messages {
+        controller
+            .send_to(&message.encode().unwrap(), addr)
+            .unwrap();
+    }
+    for id in incoming {
+        thread::spawn(move || {
+            let listener = SocketAddr::from(([127, 0, 0, 1], listen_port));
+            let mut stream = TcpStream::connect(listener).unwrap();
+            // The node may give up on the neighbour before the handshake is over
+            let _ = handshake::exchange(&mut stream, listener, &id, true);
+        });
+    }
+    node.join().unwrap()
+}
+
+#[test]
+fn neighbour_claiming_our_id_is_rejected() {
+    // A misconfigured neighbour that answers with the id of the node itself
+    let impostor = TcpListener::bind("127.0.0.1:0").unwrap();
+    let impostor_addr = impostor.local_addr().unwrap();
+    thread::spawn(move || {
+        let (mut stream, _) = impostor.accept().unwrap();
+        read_frame(&mut stream).unwrap();
+        let id = handshake::Message::Id(NodeId(1));
+        write_frame(&mut stream, &bincode::serialize(&id).unwrap()).unwrap();
+    });
+
+    assert!(matches!(
+        configure_with(vec![impostor_addr]),
+        Err(NodeError::SelfConnection(addr, NodeId(1))) if addr == impostor_addr
+    ));
+}
+
+#[test]
+fn dialed_neighbours_sharing_an_id_are_a_count_mismatch() {
+    // Two misconfigured neighbours both answer as node 2
+    let addresses = (0..2)
+        .map(|_| {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let addr = listener.local_addr().unwrap();
+            thread::spawn(move || {
+                let (mut stream, peer) = listener.accept().unwrap();
+                handshake::exchange(&mut stream, peer, &NodeId(2), false).unwrap();
+            });
+            addr
+        })
+        .collect();
+
+    assert!(matches!(
+        configure_with(addresses),
+        Err(NodeError::NeighbourCount {
+            expected: 2,
+            actual: 1
+        })
+    ));
+}
+
+#[test]
+fn dropped_outgoing_address_is_a_count_mismatch() {
+    // The only neighbour of the node went missing from its outgoing addresses
+    assert!(matches!(
+        configure_announcing(1, Vec::new(), Vec::new()),
+        Err(NodeError::NeighbourCount {
+            expected: 1,
+            actual: 0
+        })
+    ));
+}
+
+#[test]
+fn accepted_neighbours_sharing_an_id_are_a_count_mismatch() {
+    // Two misconfigured neighbours both dial the node as node 2
+    assert!(matches!(
+        configure_dialed_by(vec![NodeId(2), NodeId(2)], Vec::new()),
+        Err(NodeError::NeighbourCount {
+            expected: 2,
+            actual: 1
+        })
+    ));
+}
+
+#[test]
+fn neighbour_listening_late_is_connected_to_on_retry() {
+    // The address is handed out before the neighbour is listening on it
+    let addr = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let neighbour = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(250));
+        let listener = TcpListener::bind(addr).unwrap();
+        let (mut stream, peer) = listener.accept().unwrap();
+        handshake::exchange(&mut stream, peer, &NodeId(2), false).unwrap()
+    });
+
+    let config = configure_with(vec![addr]).unwrap();
+    assert!(config.neighbours.contains_key(&NodeId(2)));
+    assert_eq!(neighbour.join().unwrap(), NodeId(1));
+}
@@ -0,0 +1,62 @@
+use std::{
+    collections::HashMap,
+    io::Read,
+    net::{TcpListener, TcpStream},
+};
+
+use renraku_node::{Neighbour, NodeConfig};
+use renraku_shared::NodeId;
+
+#[test]
+fn dropping_a_config_shuts_its_streams_down() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let (mut peer, _) = listener.accept().unwrap();
+    // A handle outliving the config, which alone would keep the connection open
+    let _kept = stream.try_clone().unwrap();
+
+    let config = NodeConfig::new(
+        2,
+        NodeId(1),
+        HashMap::from([(NodeId(2), Neighbour::new(stream).unwrap())]),
+    );
+    config
+        .neighbours
+        .get(&NodeId(2))
+        .unwrap()
+        .enqueue(b"bye".to_vec())
+        .unwrap();
+    drop(config);
+
+    // What was sent is still delivered, followed by the end of the stream
+    let mut received = Vec::new();
+    peer.read_to_end(&mut received).unwrap();
+    assert_eq!(received, b"bye");
+}
+
+#[test]
+fn shutting_down_waits_for_the_queued_messages() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let (mut peer, _) = listener.accept().unwrap();
+
+    let config = NodeConfig::new(
+        2,
+        NodeId(1),
+        HashMap::from([(NodeId(2), Neighbour::new(stream).unwrap())]),
+    );
+    config.start_writers();
+    for _ in 0..100 {
+        config
+            .neighbours
+            .get(&NodeId(2))
+            .unwrap()
+            .enqueue(b"queued".to_vec())
+            .unwrap();
+    }
+    config.shutdown();
+
+    let mut received = Vec::new();
+    peer.read_to_end(&mut received).unwrap();
+    assert_eq!(received, b"queued".repeat(100));
+}
@@ -0,0 +1,54 @@
+use std::collections::VecDeque;
+
+use renraku_node::termination::{Message, TerminationDetector};
+use renraku_shared::NodeId;
+
+/// Messages in flight, delivered in the order they were sent.
+type Network = VecDeque<(NodeId, Message)>;
+
+fn deliver_all(nodes: &mut [TerminationDetector], network: &mut Network) {
+    while let Some((recipient, message)) = network.pop_front() {
+        network.extend(nodes[recipient.0 - 1].handle(message).messages);
+    }
+}
+
+#[test]
+fn root_detects_termination_of_a_tree() {
+    // 1 is the root, 2 and 3 its children, 4 a child of 2
+    let mut nodes = vec![
+        TerminationDetector::root(NodeId(1)),
+        TerminationDetector::new(NodeId(2)),
+        TerminationDetector::new(NodeId(3)),
+        TerminationDetector::new(NodeId(4)),
+    ];
+    let mut network = Network::new();
+
+    network.extend(nodes[0].activate(NodeId(2)).messages);
+    network.extend(nodes[0].activate(NodeId(3)).messages);
+    deliver_all(&mut nodes, &mut network);
+    network.extend(nodes[1].activate(NodeId(4)).messages);
+    deliver_all(&mut nodes, &mut network);
+    assert_eq!(nodes[1].parent, Some(NodeId(1)));
+    assert_eq!(nodes[3].parent, Some(NodeId(2)));
+
+    // 4 hands some work back to 2, which is already engaged and acknowledges it at once
+    network.extend(nodes[3].activate(NodeId(2)).messages);
+    deliver_all(&mut nodes, &mut network);
+    assert_eq!(nodes[3].deficit, 0);
+
+    // The root, 3 and 2 are done, but 4 is still working
+    for i in [0, 2, 1] {
+        network.extend(nodes[i].passivate().messages);
+        deliver_all(&mut nodes, &mut network);
+    }
+    assert!(!nodes[0].is_terminated());
+    assert_eq!(nodes[0].deficit, 1);
+    assert!(nodes[1].is_engaged());
+    assert!(!nodes[2].is_engaged());
+
+    // The acknowledgement of 4 makes 2 leave the tree, and the root detect termination
+    network.extend(nodes[3].passivate().messages);
+    deliver_all(&mut nodes, &mut network);
+    assert!(nodes[0].is_terminated());
+    assert!(nodes.iter().all(|n| !n.is_engaged() && n.deficit == 0));
+}
@@ -1,31 +1,125 @@
 use std::{
     collections::{HashMap, HashSet},
-    io::{Read, Write},
-    net::TcpStream,
     sync::{Arc, Condvar, Mutex, MutexGuard},
+    time::{Duration, Instant},
 };
 
 use color_eyre::eyre::Result;
-use renraku_shared::NodeId;
+pub use renraku_node::runtime::dispatch;
+use renraku_node::{
+    channel::Channel,
+    event::Direction,
+    record::LocalEvent,
+    runtime::{self, Message as _},
+    trace::TraceContext,
+    NodeConfig,
+};
+use renraku_shared::{clock::VectorClock, NodeId};
 use serde::{Deserialize, Serialize};
-use tracing::debug;
+use tracing::{debug, info, warn, Span};
+
+/// Identifies one of the independent critical sections nodes coordinate on.
+pub type ResourceId = String;
+
+/// Resource used when a single critical section is needed.
+pub const DEFAULT_RESOURCE: &str = "default";
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum Message {
-    Request { date: usize, requester: NodeId },
-    Permission { authorizer: NodeId },
+    Request {
+        date: usize,
+        requester: NodeId,
+        resource: ResourceId,
+        /// Priority class of the request, the differed requests of the highest classes being
+        /// granted first.
+        priority: u8,
+        /// Trace of the acquisition of the critical section, if traced.
+        trace: Option<TraceContext>,
+        /// Vector clock of the requester when sending, if messages are stamped.
+        clock: Option<VectorClock>,
+    },
+    Permission {
+        authorizer: NodeId,
+        resource: ResourceId,
+        /// Date of the request granted, the permissions granting an earlier request of the
+        /// recipient being ignored.
+        date: usize,
+        /// Span of the authorizer that handled the request, within the trace of the request.
+        trace: Option<TraceContext>,
+        /// Vector clock of the authorizer when sending, if messages are stamped.
+        clock: Option<VectorClock>,
+    },
+    /// Chases the wait-for edges to detect a deadlock, see [`RicAgrawala::probe`].
+    Probe {
+        /// Node that started the detection, waiting for the resource.
+        initiator: NodeId,
+        sender: NodeId,
+        target: NodeId,
+        resource: ResourceId,
+        /// Vector clock of the sender when sending, if messages are stamped.
+        clock: Option<VectorClock>,
+    },
+    /// The sender asks for no critical section anymore, see [`Node::leave`](crate::guard::Node::leave).
+    Leave {
+        sender: NodeId,
+        /// Vector clock of the sender when sending, if messages are stamped.
+        clock: Option<VectorClock>,
+    },
+}
+
+impl runtime::Message for Message {
+    fn kind(&self) -> &'static str {
+        match self {
+            Message::Request { .. } => "Request",
+            Message::Permission { .. } => "Permission",
+            Message::Probe { .. } => "Probe",
+            Message::Leave { .. } => "Leave",
+        }
+    }
+
+    fn sender(&self) -> &NodeId {
+        match self {
+            Message::Request { requester, .. } => requester,
+            Message::Permission { authorizer, .. } => authorizer,
+            Message::Probe { sender, .. } | Message::Leave { sender, .. } => sender,
+        }
+    }
+
+    fn stamp(&mut self, stamp: Option<VectorClock>) {
+        match self {
+            Message::Request { clock, .. }
+            | Message::Permission { clock, .. }
+            | Message::Probe { clock, .. }
+            | Message::Leave { clock, .. } => *clock = stamp,
+        }
+    }
 }
 
 impl Message {
-    pub fn send_to(self, mut stream: &TcpStream) -> Result<()> {
-        stream.to_owned().write_all(&bincode::serialize(&self)?)?;
-        Ok(())
+    /// The resource the message is about, none for the messages about the node itself.
+    pub fn resource(&self) -> Option<&ResourceId> {
+        match self {
+            Message::Request { resource, .. }
+            | Message::Permission { resource, .. }
+            | Message::Probe { resource, .. } => Some(resource),
+            Message::Leave { .. } => None,
+        }
     }
 
-    pub fn receive_from(mut stream: &TcpStream) -> Result<Message> {
-        let mut buf = [0; 1024];
-        stream.read(&mut buf)?;
-        Ok(bincode::deserialize(&buf)?)
+    pub fn trace(&self) -> Option<&TraceContext> {
+        match self {
+            Message::Request { trace, .. } | Message::Permission { trace, .. } => trace.as_ref(),
+            Message::Probe { .. } | Message::Leave { .. } => None,
+        }
+    }
+
+    pub fn clock(&self) -> Option<&VectorClock> {
+        match self {
+            Message::Request { clock, .. }
+            | Message::Permission { clock, .. }
+            | Message::Probe { clock, .. }
+            | Message::Leave { clock, .. } => clock.as_ref(),
+        }
     }
 }
 
@@ -36,153 +130,602 @@ pub enum State {
     CriticalSection,
 }
 
-#[derive(Debug, Clone)]
-pub struct RicAgrawala {
+/// State of the algorithm for one of the resources.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResourceState {
     pub state: State,
-    pub timestamp: usize,
     pub last_request_timestamp: usize,
     pub prioritized: bool,
     pub awaited: HashSet<NodeId>,
+    /// Number of awaited permissions that may still be missing when entering the critical
+    /// section, zero unless a quorum fraction is set.
+    pub remaining_threshold: usize,
+    /// Requesters waiting for the critical section to be left, sorted by decreasing priority
+    /// class, then by arrival within a class.
     pub differed_permission: Vec<NodeId>,
+    /// Priority class of each differed request.
+    pub differed_priorities: HashMap<NodeId, u8>,
+    /// When the pending request for the critical section has been issued.
+    pub asked_at: Option<Instant>,
+    /// Time elapsed between the last request and the access to the critical section.
+    pub last_latency: Option<Duration>,
+    /// Number of nodes we granted access to while waiting for the critical section.
+    pub overtaken: usize,
+    /// Trace of the pending acquisition of the critical section.
+    pub trace: Option<TraceContext>,
+    /// Span that handled each differed request, for the permission to join its trace.
+    pub differed_traces: HashMap<NodeId, TraceContext>,
+    /// Initiators whose deadlock probe has been forwarded during the pending request.
+    pub probed: HashSet<NodeId>,
+    /// Whether one of the deadlock probes of the node came back during the pending request,
+    /// the node then waiting forever.
+    pub deadlocked: bool,
+    /// Date of the last request handled from each requester, its retransmissions being
+    /// granted again rather than differed twice.
+    pub handled_requests: HashMap<NodeId, usize>,
 }
 
-impl RicAgrawala {
-    fn differ_permission(&mut self, node: NodeId) {
-        debug!("🕣 {:?} permission has been differed", node);
-        self.differed_permission.push(node);
+impl ResourceState {
+    /// Returns whether enough permissions have been received to enter the critical section.
+    pub fn is_permitted(&self) -> bool {
+        self.awaited.len() <= self.remaining_threshold
     }
 
-    fn alter_on(&mut self, message: &Message) {
-        match message {
-            Message::Request { date, .. } => {
-                self.timestamp = date.clone().max(self.timestamp);
-                self.prioritized =
-                    self.state != State::Idling && self.last_request_timestamp < date.clone()
-            }
-            Message::Permission { authorizer } => {
-                self.awaited.remove(&authorizer);
-            }
+    /// Records the time elapsed since the pending request was issued as the latency of the
+    /// access, unless it has already been.
+    pub fn measure_latency(&mut self, resource: &str) {
+        if let Some(asked_at) = self.asked_at.take() {
+            let latency = asked_at.elapsed();
+            self.last_latency = Some(latency);
+            info!(
+                latency_us = latency.as_micros() as u64,
+                resource,
+                traceparent = self.trace.map(|t| t.traceparent()),
+                "⏱️ Every permission has been received"
+            );
         }
     }
 
-    pub fn handle(
-        &mut self,
-        message: Message,
-        config: Arc<(usize, NodeId, HashMap<NodeId, TcpStream>)>,
-        permission_signal: Arc<Condvar>,
-    ) -> Result<()> {
-        let (_, id, neighbours) = config.as_ref();
-        self.alter_on(&message);
-        match message {
-            Message::Request { requester, .. } => {
-                if self.prioritized {
-                    self.differ_permission(requester);
-                } else {
-                    Message::Permission {
-                        authorizer: id.clone(),
-                    }
-                    .send_to(neighbours.get(&requester).unwrap())?;
-                }
-            }
-            Message::Permission { .. } => {
-                if self.awaited.is_empty() {
-                    permission_signal.notify_all();
-                }
-            }
-        }
-        Ok(())
+    /// Differs the request of the requester until the critical section is left, behind the
+    /// differed requests of the same or a higher priority class.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use renraku_ricart_agrawala::algorithm::ResourceState;
+    /// # use renraku_shared::NodeId;
+    ///
+    /// let mut state = ResourceState::default();
+    /// state.differ(NodeId(1), 0);
+    /// state.differ(NodeId(2), 2);
+    /// state.differ(NodeId(3), 1);
+    /// state.differ(NodeId(4), 2);
+    /// assert_eq!(state.differed_permission, [2, 4, 3, 1].map(NodeId));
+    /// ```
+    pub fn differ(&mut self, requester: NodeId, priority: u8) {
+        let position = self
+            .differed_permission
+            .iter()
+            .position(|n| self.differed_priorities.get(n).copied().unwrap_or_default() < priority)
+            .unwrap_or(self.differed_permission.len());
+        self.differed_permission.insert(position, requester.clone());
+        self.differed_priorities.insert(requester, priority);
     }
 }
 
-impl Default for RicAgrawala {
+impl Default for ResourceState {
     fn default() -> Self {
         Self {
             state: State::Idling,
-            timestamp: 0,
             last_request_timestamp: 0,
             prioritized: false,
             awaited: HashSet::new(),
+            remaining_threshold: 0,
             differed_permission: Vec::new(),
+            differed_priorities: HashMap::new(),
+            asked_at: None,
+            last_latency: None,
+            overtaken: 0,
+            trace: None,
+            differed_traces: HashMap::new(),
+            probed: HashSet::new(),
+            deadlocked: false,
+            handled_requests: HashMap::new(),
         }
     }
 }
 
-pub fn ask_access(
-    mutex: Arc<Mutex<RicAgrawala>>,
-    config: Arc<(usize, NodeId, HashMap<NodeId, TcpStream>)>,
-) -> Result<()> {
-    // Then expect to receive a permission at some point
-    Ok(())
+/// Nodes whose permission is awaited before entering a critical section.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Scope {
+    /// Every other node of the system, as in the original algorithm, which requires a
+    /// complete graph.
+    #[default]
+    All,
+    /// Only the neighbours of the node in the graph.
+    Neighbours,
+}
+
+/// State of a node running Ricart-Agrawala's algorithm over several resources.
+///
+/// The Lamport clock is shared by every resource, while each resource has its own
+/// independent mutual exclusion state.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RicAgrawala {
+    pub timestamp: usize,
+    pub resources: HashMap<ResourceId, ResourceState>,
+    /// Nodes asked for their permission.
+    pub scope: Scope,
+    /// Fraction of the awaited permissions after which the critical section is entered,
+    /// every one of them being awaited when unset.
+    ///
+    /// Anything below `1.0` breaks mutual exclusion, which is only meant to study the trade-off.
+    pub quorum_fraction: Option<f64>,
+    /// Priority class of the requests of this node, see [`ResourceState::differ`].
+    pub priority: u8,
+    /// Nodes the requests of this node are queued for first, in this order, the others
+    /// following by identifier.
+    ///
+    /// Every neighbour has a writer thread of its own, see [`runtime`]: the order only tells
+    /// which queue gets its request first, not which neighbour receives it first.
+    pub contact_first: Vec<NodeId>,
+    /// Until when the messages received are left waiting in their streams rather than
+    /// handled, to simulate a slow node, if the node is paused.
+    pub paused_until: Option<Instant>,
+    /// Whether the messages of the neighbours are no longer received, the permissions awaited
+    /// then never coming.
+    pub disconnected: bool,
+    /// Neighbours that left the system, asking for no critical section anymore.
+    pub left: HashSet<NodeId>,
+    /// Nodes that joined the system once it was set up, whose permission is awaited as well
+    /// as the one of the members, see [`RicAgrawala::join`].
+    pub joined: HashSet<NodeId>,
 }
 
-pub fn free_access(
-    mutex: Arc<Mutex<RicAgrawala>>,
-    config: Arc<(usize, NodeId, HashMap<NodeId, TcpStream>)>,
-) -> Result<()> {
-    let (_, id, neighbours) = config.as_ref();
+impl RicAgrawala {
+    /// Returns the state of the given resource, if it has ever been used.
+    pub fn resource(&self, resource: &str) -> Option<&ResourceState> {
+        self.resources.get(resource)
+    }
+
+    pub(crate) fn resource_mut(&mut self, resource: &str) -> &mut ResourceState {
+        self.resources.entry(resource.to_string()).or_default()
+    }
+
+    /// Sorts the peers in the order their requests are queued, see
+    /// [`RicAgrawala::contact_first`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use renraku_ricart_agrawala::algorithm::RicAgrawala;
+    /// # use renraku_shared::NodeId;
+    ///
+    /// let node = RicAgrawala {
+    ///     contact_first: vec![NodeId(4), NodeId(2)],
+    ///     ..RicAgrawala::default()
+    /// };
+    /// let mut peers = vec![NodeId(1), NodeId(2), NodeId(3), NodeId(4)];
+    /// node.contact_order(&mut peers);
+    /// assert_eq!(peers, [4, 2, 1, 3].map(NodeId));
+    /// ```
+    pub fn contact_order(&self, peers: &mut [NodeId]) {
+        peers.sort_by_key(|peer| {
+            let rank = self.contact_first.iter().position(|first| first == peer);
+            (rank.unwrap_or(usize::MAX), peer.0)
+        });
+    }
+
+    pub(crate) fn outgoing(&self, messages: Vec<(NodeId, Message)>) -> Outgoing {
+        Outgoing {
+            clock: self.timestamp,
+            messages,
+        }
+    }
+
+    fn alter_on<C: Channel>(&mut self, message: &Message, config: &NodeConfig<C>) -> Result<()> {
+        match message {
+            Message::Request {
+                date,
+                requester,
+                resource,
+                ..
+            } => {
+                if *date > self.timestamp {
+                    self.timestamp = *date;
+                    config.save_clock(self.timestamp)?;
+                }
+                let state = self.resource_mut(resource);
+                // Requests issued at the same date are ordered by node identifier, while the
+                // clock of a node that joined late may lag behind the critical section
+                state.prioritized = state.state == State::CriticalSection
+                    || (state.state == State::Askin
+                        && (state.last_request_timestamp, config.id.0) < (*date, requester.0))
+            }
+            Message::Permission {
+                authorizer,
+                resource,
+                ..
+            } => {
+                self.resource_mut(resource).awaited.remove(authorizer);
+            }
+            Message::Probe { .. } | Message::Leave { .. } => {}
+        }
+        Ok(())
+    }
+
+    /// Merges the vector clock of a received message, then writes it to the event log and the
+    /// metrics, whether or not it is ignored afterwards.
+    fn received<C: Channel>(&self, message: &Message, config: &NodeConfig<C>) -> Result<()> {
+        config.observe(message.clock());
+        config.record(
+            message.sender(),
+            Direction::Received,
+            message.kind(),
+            self.timestamp,
+        )?;
+        Ok(())
+    }
 
-    let mut v = mutex.lock().unwrap();
-    v.state = State::Idling;
-    for m in v.differed_permission.iter() {
-        Message::Permission {
-            authorizer: id.clone(),
+    /// Takes into account a node that joined the system once it was set up, now a neighbour
+    /// of this one, see [`runtime::admit`].
+    ///
+    /// Its permission is awaited by every request from now on, the pending ones being sent to
+    /// it as they were issued. Its requests are differed while in the critical section, as
+    /// any other, whatever their date.
+    ///
+    /// A neighbour joining again restarted, forgetting about its requests and ours: its
+    /// differed requests are dropped, and its permission is awaited again by ours.
+    pub fn join<C: Channel>(&mut self, peer: NodeId, config: &NodeConfig<C>) -> Outgoing {
+        if !config.members.contains(&peer) {
+            self.joined.insert(peer.clone());
+        }
+        self.left.remove(&peer);
+        let priority = self.priority;
+        let mut messages = Vec::new();
+        for (resource, state) in self.resources.iter_mut() {
+            state.differed_permission.retain(|n| *n != peer);
+            state.differed_priorities.remove(&peer);
+            state.differed_traces.remove(&peer);
+            state.handled_requests.remove(&peer);
+            if state.state != State::Askin {
+                continue;
+            }
+            debug!("🆕 Asking {peer:?} for {resource} as well");
+            state.awaited.insert(peer.clone());
+            let request = Message::Request {
+                date: state.last_request_timestamp,
+                requester: config.id.clone(),
+                resource: resource.clone(),
+                priority,
+                trace: state.trace,
+                clock: None,
+            };
+            messages.push((peer.clone(), request));
         }
-        .send_to(neighbours.get(m).unwrap())?;
+        self.outgoing(messages)
     }
-    v.differed_permission.clear();
 
-    Ok(())
+    /// Handles a message received from a neighbour, returning the messages to send in response.
+    ///
+    /// Nothing is written to the neighbours here, so that the caller can send the returned
+    /// messages with [`dispatch`] once the state is unlocked.
+    pub fn handle<C: Channel>(
+        &mut self,
+        message: Message,
+        config: Arc<NodeConfig<C>>,
+        permission_signal: Arc<Condvar>,
+    ) -> Result<Outgoing> {
+        if let Message::Request {
+            date,
+            requester,
+            resource,
+            ..
+        } = &message
+        {
+            let handled = self
+                .resource(resource)
+                .and_then(|state| state.handled_requests.get(requester));
+            // Handling it again would grant or differ the same request twice
+            if handled == Some(date) {
+                self.received(&message, &config)?;
+                let differed = self
+                    .resource(resource)
+                    .is_some_and(|state| state.differed_permission.contains(requester));
+                if differed {
+                    debug!("🔁 Ignoring the retransmitted request of {requester:?} for {resource}, still differed");
+                    return Ok(self.outgoing(Vec::new()));
+                }
+                // The permission may have been lost, the requester still waiting for it
+                debug!(
+                    "🔁 Granting the retransmitted request of {requester:?} for {resource} again"
+                );
+                let permission = Message::Permission {
+                    authorizer: config.id.clone(),
+                    resource: resource.clone(),
+                    date: *date,
+                    trace: None,
+                    clock: None,
+                };
+                return Ok(self.outgoing(vec![(requester.clone(), permission)]));
+            }
+        }
+        if let Message::Permission {
+            authorizer,
+            resource,
+            date,
+            ..
+        } = &message
+        {
+            let pending = self
+                .resource(resource)
+                .is_some_and(|s| s.state == State::Askin && s.last_request_timestamp == *date);
+            // A request given up on must not let a late permission count for the next one
+            if !pending {
+                self.received(&message, &config)?;
+                debug!("🗑️ Ignoring the permission of {authorizer:?} for {resource}, granting a request given up on");
+                return Ok(self.outgoing(Vec::new()));
+            }
+        }
+        self.alter_on(&message, &config)?;
+        self.received(&message, &config)?;
+        // Handling the message joins the trace of the sender, if any
+        let (trace, span) = match message.trace() {
+            Some(trace) => {
+                let (child, span) = trace.child_span(message.kind());
+                (Some(child), span)
+            }
+            None => (None, Span::none()),
+        };
+        let _entered = span.enter();
+        let mut messages = Vec::new();
+        match message {
+            Message::Request {
+                date,
+                requester,
+                resource,
+                priority,
+                ..
+            } => {
+                let state = self.resource_mut(&resource);
+                state.handled_requests.insert(requester.clone(), date);
+                if state.prioritized {
+                    debug!(
+                        "🕣 {:?} permission for {} has been differed",
+                        requester, resource
+                    );
+                    if let Some(trace) = trace {
+                        state.differed_traces.insert(requester.clone(), trace);
+                    }
+                    state.differ(requester, priority);
+                } else {
+                    if state.state == State::Askin {
+                        state.overtaken += 1;
+                    }
+                    messages.push((
+                        requester,
+                        Message::Permission {
+                            authorizer: config.id.clone(),
+                            resource,
+                            date,
+                            trace,
+                            clock: None,
+                        },
+                    ));
+                }
+            }
+            Message::Permission { resource, .. } => {
+                let state = self.resource_mut(&resource);
+                if state.is_permitted() {
+                    state.measure_latency(&resource);
+                    permission_signal.notify_all();
+                }
+            }
+            Message::Probe { .. } => return Ok(self.on_probe(&config.id, message)),
+            Message::Leave { sender, .. } => {
+                debug!("👋 {sender:?} left the system");
+                self.left.insert(sender);
+                permission_signal.notify_all();
+            }
+        }
+        Ok(self.outgoing(messages))
+    }
 }
 
+pub type Outgoing = runtime::Outgoing<Message>;
+
 pub trait RicAgrawalaActor {
-    fn ask(&mut self, config: Arc<(usize, NodeId, HashMap<NodeId, TcpStream>)>) -> Result<()>;
+    fn ask<C: Channel>(&mut self, config: Arc<NodeConfig<C>>, resource: &str) -> Outgoing;
+
+    fn free<C: Channel>(&mut self, config: Arc<NodeConfig<C>>, resource: &str) -> Outgoing;
 
-    fn free(&mut self, config: Arc<(usize, NodeId, HashMap<NodeId, TcpStream>)>) -> Result<()>;
+    /// Gives up a pending request, as if the critical section had been entered then left.
+    fn abandon<C: Channel>(&mut self, config: Arc<NodeConfig<C>>, resource: &str) -> Outgoing;
+
+    /// Sends the pending request again to the nodes whose permission is still missing, in
+    /// case it got lost.
+    ///
+    /// The request keeps its date, so that the nodes that did receive it only send their
+    /// permission again, if they granted it already.
+    fn retransmit<C: Channel>(&mut self, config: Arc<NodeConfig<C>>, resource: &str) -> Outgoing;
 }
 
 impl<'a> RicAgrawalaActor for MutexGuard<'a, RicAgrawala> {
-    fn ask(&mut self, config: Arc<(usize, NodeId, HashMap<NodeId, TcpStream>)>) -> Result<()> {
-        let (nodes_count, id, neighbours) = config.as_ref();
-        self.state = State::Askin;
+    fn ask<C: Channel>(&mut self, config: Arc<NodeConfig<C>>, resource: &str) -> Outgoing {
         self.timestamp += 1;
-        self.last_request_timestamp = self.timestamp;
-        let timestamp = self.timestamp.clone();
-        let awaited = (1..nodes_count.clone() + 1)
-            .map(NodeId)
-            .filter(|n| n.0 != id.0)
-            .collect::<Vec<_>>();
-        for node in awaited.clone() {
-            self.awaited.insert(node.clone());
+        let timestamp = self.timestamp;
+        // The request is sent anyway, a restarted node then risking to reuse the timestamp
+        if let Err(e) = config.save_clock(timestamp) {
+            warn!("💾 Persisting the clock {timestamp}: {e}");
         }
-        debug!("⚙️ Asked for access, ready to receive a permission");
+        let quorum_fraction = self.quorum_fraction;
+        let priority = self.priority;
+        let mut awaited = match self.scope {
+            Scope::All => config
+                .others()
+                .chain(self.joined.iter())
+                .cloned()
+                .collect::<Vec<_>>(),
+            Scope::Neighbours => config.neighbours.keys().collect(),
+        };
+        self.contact_order(&mut awaited);
 
-        // Sends for each program waited a request for permission
-        for node in awaited.clone() {
-            Message::Request {
-                date: timestamp,
-                requester: id.clone(),
-            }
-            .send_to(neighbours.get(&node).unwrap())?;
+        if let Err(e) = config.record_local(LocalEvent::Ask(resource.to_string())) {
+            warn!("💾 Recording the request for {resource}: {e}");
         }
+        let state = self.resource_mut(resource);
+        state.state = State::Askin;
+        state.last_request_timestamp = timestamp;
+        state.asked_at = Some(Instant::now());
+        state.overtaken = 0;
+        state.probed.clear();
+        state.deadlocked = false;
+        state.awaited.extend(awaited.iter().cloned());
+        state.remaining_threshold = quorum_fraction.map_or(0, |fraction| {
+            let required = (fraction * state.awaited.len() as f64).ceil() as usize;
+            state.awaited.len().saturating_sub(required)
+        });
+        // Each acquisition is a trace of its own, joined by the nodes granting it
+        let trace = TraceContext::root();
+        state.trace = Some(trace);
+        debug!("⚙️ Asked for access to {resource}, ready to receive a permission");
+
+        // Sends for each program waited a request for permission
         debug!(
-            "❓ Asked for permission following neighbours: {:?}, should now wait for permission",
+            "❓ Asking for permission following neighbours: {:?}, should then wait for permission",
             awaited
         );
+        self.outgoing(
+            awaited
+                .into_iter()
+                .map(|node| {
+                    let request = Message::Request {
+                        date: timestamp,
+                        requester: config.id.clone(),
+                        resource: resource.to_string(),
+                        priority,
+                        trace: Some(trace),
+                        clock: None,
+                    };
+                    (node, request)
+                })
+                .collect(),
+        )
+    }
 
-        Ok(())
+    fn free<C: Channel>(&mut self, config: Arc<NodeConfig<C>>, resource: &str) -> Outgoing {
+        if let Err(e) = config.record_local(LocalEvent::Free(resource.to_string())) {
+            warn!("💾 Recording the release of {resource}: {e}");
+        }
+        let state = self.resource_mut(resource);
+        state.state = State::Idling;
+        state.trace = None;
+        // Permissions still missing after entering with a partial quorum are not waited for
+        state.awaited.clear();
+        let differed = std::mem::take(&mut state.differed_permission);
+        state.differed_priorities.clear();
+        let mut traces = std::mem::take(&mut state.differed_traces);
+        let dates = state.handled_requests.clone();
+        self.outgoing(
+            differed
+                .into_iter()
+                .map(|node| {
+                    let permission = Message::Permission {
+                        authorizer: config.id.clone(),
+                        resource: resource.to_string(),
+                        date: dates.get(&node).copied().unwrap_or_default(),
+                        trace: traces.remove(&node),
+                        clock: None,
+                    };
+                    (node, permission)
+                })
+                .collect(),
+        )
     }
 
-    fn free(&mut self, config: Arc<(usize, NodeId, HashMap<NodeId, TcpStream>)>) -> Result<()> {
-        let (_, id, neighbours) = config.as_ref();
+    fn abandon<C: Channel>(&mut self, config: Arc<NodeConfig<C>>, resource: &str) -> Outgoing {
+        let state = self.resource_mut(resource);
+        warn!(
+            "⌛ Abandoning the request for {resource}, still waiting for {:?}",
+            state.awaited
+        );
+        // Permissions received late are ignored, granting a request no longer pending
+        state.awaited.clear();
+        state.asked_at = None;
+        self.free(config, resource)
+    }
 
-        self.state = State::Idling;
-        for m in self.differed_permission.iter() {
-            Message::Permission {
-                authorizer: id.clone(),
+    fn retransmit<C: Channel>(&mut self, config: Arc<NodeConfig<C>>, resource: &str) -> Outgoing {
+        let priority = self.priority;
+        let messages = match self.resource(resource) {
+            Some(state) if state.state == State::Askin => {
+                let mut awaited: Vec<NodeId> = state.awaited.iter().cloned().collect();
+                self.contact_order(&mut awaited);
+                debug!("🔁 Sending the request for {resource} again to {awaited:?}");
+                awaited
+                    .into_iter()
+                    .map(|node| {
+                        let request = Message::Request {
+                            date: state.last_request_timestamp,
+                            requester: config.id.clone(),
+                            resource: resource.to_string(),
+                            priority,
+                            trace: state.trace,
+                            clock: None,
+                        };
+                        (node, request)
+                    })
+                    .collect()
             }
-            .send_to(neighbours.get(m).unwrap())?;
+            _ => Vec::new(),
+        };
+        self.outgoing(messages)
+    }
+}
+
+/// Waits until the node is no longer paused, returning the locked state.
+///
+/// The pause ends once its deadline is reached, or as soon as `paused_until` is cleared and
+/// the signal notified.
+pub fn wait_resumed<'a>(
+    mutex: &'a Mutex<RicAgrawala>,
+    signal: &Condvar,
+) -> MutexGuard<'a, RicAgrawala> {
+    let mut lock = mutex.lock().unwrap();
+    while let Some(until) = lock.paused_until {
+        let remaining = until.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            lock.paused_until = None;
+            break;
         }
+        lock = signal.wait_timeout(lock, remaining).unwrap().0;
+    }
+    lock
+}
 
-        Ok(())
+/// Waits until enough permissions for the resource have been received, every one of them
+/// unless a quorum fraction is set, returning the locked state.
+///
+/// Returns `None` if the timeout elapses first, the request being left pending. Returns the
+/// state right away once the node is [`RicAgrawala::disconnected`], whoever waits having to
+/// check for it.
+pub fn wait_permissions<'a>(
+    mutex: &'a Mutex<RicAgrawala>,
+    permission_signal: &Condvar,
+    resource: &str,
+    timeout: Option<Duration>,
+) -> Option<MutexGuard<'a, RicAgrawala>> {
+    let waiting = |v: &mut RicAgrawala| {
+        !v.disconnected && v.resource(resource).is_some_and(|r| !r.is_permitted())
+    };
+    let lock = mutex.lock().unwrap();
+    match timeout {
+        Some(timeout) => {
+            let (lock, result) = permission_signal
+                .wait_timeout_while(lock, timeout, waiting)
+                .unwrap();
+            (!result.timed_out()).then_some(lock)
+        }
+        None => Some(permission_signal.wait_while(lock, waiting).unwrap()),
     }
 }
@@ -0,0 +1,175 @@
+use std::{path::PathBuf, str::FromStr, time::Duration};
+
+use renraku_node::{runtime::Cycle, NodeArguments};
+use renraku_shared::NodeId;
+
+use crate::algorithm::Scope;
+
+#[derive(clap::Parser, Debug, Clone)]
+#[command(author, version, about, long_about = None)]
+pub struct Arguments {
+    #[command(flatten)]
+    pub node: NodeArguments,
+    #[command(flatten)]
+    pub cycle: Cycle,
+    /// Asks for the critical section on a fixed schedule rather than after random delays,
+    /// each node taking its turn in the order of its identifier, and holds it for exactly
+    /// `--max-hold-ms`.
+    #[arg(long)]
+    pub deterministic: bool,
+    /// Delay between the turns of two nodes in `--deterministic` mode.
+    #[arg(long, default_value_t = 1000)]
+    pub interval_ms: u64,
+    /// Gives up waiting for the critical section after this delay, sending back the differed
+    /// permissions. Nodes wait forever otherwise.
+    #[arg(long)]
+    pub enter_timeout_ms: Option<u64>,
+    /// Probes the other nodes for a deadlock once the node waited this long for the critical
+    /// section, reporting it when the wait-for edges form a cycle.
+    #[arg(long)]
+    pub deadlock_probe_ms: Option<u64>,
+    /// Sends the request for the critical section again to the nodes that have not answered
+    /// after this delay, then after twice as long each time, in case it got lost.
+    #[arg(long)]
+    pub retransmit_ms: Option<u64>,
+    /// Logs the nodes whose permission is missing once the node waited this long for the
+    /// critical section, to find out which one a hang is waiting for.
+    #[arg(long)]
+    pub watchdog_ms: Option<u64>,
+    /// Reads `pause <MS>` and `resume` lines from the standard input, the node leaving the
+    /// messages it receives waiting while paused, as a slow node would.
+    #[arg(long)]
+    pub pause_commands: bool,
+    /// Appends to this file how many nodes overtook this one each time it waited for the
+    /// critical section.
+    #[arg(long, value_name = "FILE")]
+    pub fairness_log: Option<PathBuf>,
+    /// Replays the messages recorded with `--record-dir` into this directory, printing the
+    /// state the node ends up in, rather than joining the system.
+    #[arg(long, value_name = "DIR")]
+    pub replay_dir: Option<PathBuf>,
+    /// Prints each message of a file of frames, such as the file a recording keeps for each
+    /// neighbour, rather than joining the system. Messages are decoded with `--codec`.
+    #[arg(long, value_name = "FILE")]
+    pub dump: Option<PathBuf>,
+    /// Nodes whose permission is awaited before entering the critical section.
+    #[arg(long, value_enum, default_value_t = Scope::All)]
+    pub scope: Scope,
+    /// Enters the critical section once this fraction of the awaited permissions has been
+    /// received, e.g. `0.5` for half of them.
+    ///
+    /// Anything below `1` breaks mutual exclusion, only use it to study the trade-off.
+    #[arg(long, value_name = "FRACTION", value_parser = parse_fraction)]
+    pub quorum_fraction: Option<f64>,
+    /// Priority class of the requests of this node: a node leaving the critical section
+    /// grants the differed requests of the highest classes first.
+    #[arg(long, value_name = "CLASS", default_value_t = 0)]
+    pub priority: u8,
+    /// Queues the requests of this node for these nodes first, in this order, e.g. `4,2`, the
+    /// others following by identifier. Each neighbour being written to by its own thread, this
+    /// orders the event log rather than when the requests are received.
+    #[arg(long, value_name = "IDS", value_delimiter = ',', value_parser = parse_node_id)]
+    pub contact_first: Vec<NodeId>,
+}
+
+/// Command read from the standard input with `--pause-commands`.
+///
+/// # Examples
+///
+/// ```
+/// # use renraku_ricart_agrawala::command::PauseCommand;
+/// use std::time::Duration;
+///
+/// assert_eq!("pause 250".parse(), Ok(PauseCommand::Pause(Duration::from_millis(250))));
+/// assert_eq!(" resume ".parse(), Ok(PauseCommand::Resume));
+/// assert!("pause".parse::<PauseCommand>().is_err());
+/// assert!("stop".parse::<PauseCommand>().is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PauseCommand {
+    /// Stops handling the messages received for the duration.
+    Pause(Duration),
+    /// Handles the messages received again.
+    Resume,
+}
+
+impl FromStr for PauseCommand {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s.split_whitespace().collect::<Vec<_>>().as_slice() {
+            ["pause", ms] => ms
+                .parse()
+                .map(|ms| PauseCommand::Pause(Duration::from_millis(ms)))
+                .map_err(|e| format!("{ms} is not a duration in milliseconds: {e}")),
+            ["resume"] => Ok(PauseCommand::Resume),
+            _ => Err(format!("expected `pause <MS>` or `resume`, got {s:?}")),
+        }
+    }
+}
+
+/// Parses a fraction within `(0, 1]`.
+///
+/// # Examples
+///
+/// ```
+/// # use renraku_ricart_agrawala::command::parse_fraction;
+///
+/// assert_eq!(parse_fraction("0.5"), Ok(0.5));
+/// assert_eq!(parse_fraction("1"), Ok(1.0));
+/// assert!(parse_fraction("0").is_err());
+/// assert!(parse_fraction("1.5").is_err());
+/// assert!(parse_fraction("half").is_err());
+/// ```
+pub fn parse_fraction(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(fraction) if fraction > 0.0 && fraction <= 1.0 => Ok(fraction),
+        Ok(fraction) => Err(format!("{fraction} is not within (0, 1]")),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Parses the identifier of a node, rejecting the reserved `0`.
+///
+/// # Examples
+///
+/// ```
+/// # use renraku_ricart_agrawala::command::parse_node_id;
+/// # use renraku_shared::NodeId;
+///
+/// assert_eq!(parse_node_id("4"), Ok(NodeId(4)));
+/// assert!(parse_node_id("0").is_err());
+/// assert!(parse_node_id("four").is_err());
+/// ```
+pub fn parse_node_id(s: &str) -> Result<NodeId, String> {
+    let id = s.trim().parse::<usize>().map_err(|e| e.to_string())?;
+    NodeId::new(id).ok_or_else(|| "nodes are numbered from 1".to_string())
+}
+
+/// Time since the node started at which it asks for the critical section for the `round`-th
+/// time in `--deterministic` mode: the `k`-th of the `members` asks at `k * interval`, then
+/// once every node had its turn.
+///
+/// # Examples
+///
+/// ```
+/// # use renraku_ricart_agrawala::command::scheduled;
+/// # use renraku_shared::NodeId;
+/// use std::time::Duration;
+///
+/// let interval = Duration::from_millis(100);
+/// let members = [NodeId(1), NodeId(2), NodeId(3)];
+/// assert_eq!(scheduled(&NodeId(2), &members, 0, interval), Duration::from_millis(200));
+/// assert_eq!(scheduled(&NodeId(1), &members, 1, interval), Duration::from_millis(400));
+///
+/// // Skipped identifiers leave no gap in the schedule
+/// let members = [NodeId(2), NodeId(4), NodeId(6)];
+/// assert_eq!(scheduled(&NodeId(6), &members, 0, interval), Duration::from_millis(300));
+/// ```
+pub fn scheduled(id: &NodeId, members: &[NodeId], round: usize, interval: Duration) -> Duration {
+    let turn = members
+        .iter()
+        .position(|member| member == id)
+        .map_or(0, |k| k + 1);
+    interval * (round * members.len() + turn) as u32
+}
@@ -0,0 +1,123 @@
+use renraku_shared::NodeId;
+use tracing::{debug, error, warn};
+
+use crate::algorithm::{Message, Outgoing, RicAgrawala, State};
+
+impl RicAgrawala {
+    /// Starts an edge-chasing deadlock detection for the pending request of the resource,
+    /// following Chandy-Misra-Haas, returning a probe for each node whose permission is
+    /// missing.
+    ///
+    /// A node waiting for the critical section waits for every node whose permission it is
+    /// missing. Once it has waited for too long, it sends a probe along each of these
+    /// wait-for edges. A node receiving a probe while itself waiting forwards it along its own
+    /// edges, once per initiator and request. The probe coming back to its initiator while it
+    /// is still waiting means the wait-for edges form a cycle: none of these nodes will ever
+    /// enter.
+    ///
+    /// Ricart-Agrawala's algorithm is free of deadlocks, a detection reveals a bug or a lost
+    /// message.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use renraku_ricart_agrawala::algorithm::{ResourceState, RicAgrawala, State};
+    /// # use renraku_shared::NodeId;
+    /// use std::collections::{HashMap, HashSet};
+    ///
+    /// // The first node waits for the second one, which waits for the first one
+    /// let waiting = |peer: usize| RicAgrawala {
+    ///     resources: HashMap::from([(
+    ///         "default".to_string(),
+    ///         ResourceState {
+    ///             state: State::Askin,
+    ///             awaited: HashSet::from([NodeId(peer)]),
+    ///             ..ResourceState::default()
+    ///         },
+    ///     )]),
+    ///     ..RicAgrawala::default()
+    /// };
+    /// let (mut first, mut second) = (waiting(2), waiting(1));
+    ///
+    /// let probes = first.probe(&NodeId(1), "default");
+    /// let (_, probe) = probes.messages[0].clone();
+    /// let forwarded = second.on_probe(&NodeId(2), probe);
+    /// let (_, probe) = forwarded.messages[0].clone();
+    /// assert!(first.on_probe(&NodeId(1), probe).messages.is_empty());
+    /// assert!(first.resources["default"].deadlocked);
+    /// ```
+    pub fn probe(&mut self, id: &NodeId, resource: &str) -> Outgoing {
+        let messages = match self.resource(resource) {
+            Some(state) if state.state == State::Askin => {
+                let mut awaited: Vec<NodeId> = state.awaited.iter().cloned().collect();
+                awaited.sort();
+                debug!("🔎 Probing {awaited:?} for a deadlock on {resource}");
+                probes(id, id, resource, awaited)
+            }
+            _ => Vec::new(),
+        };
+        self.outgoing(messages)
+    }
+
+    /// Handles a probe received from a neighbour, returning the probes to forward.
+    ///
+    /// Receiving back one of its own probes while still waiting marks the resource as
+    /// [`deadlocked`](crate::algorithm::ResourceState::deadlocked).
+    pub fn on_probe(&mut self, id: &NodeId, probe: Message) -> Outgoing {
+        let Message::Probe {
+            initiator,
+            target,
+            resource,
+            ..
+        } = probe
+        else {
+            return self.outgoing(Vec::new());
+        };
+        if &target != id {
+            warn!("🔎 Ignoring a probe of {initiator:?} meant for {target:?}");
+            return self.outgoing(Vec::new());
+        }
+        let state = self.resource_mut(&resource);
+        // Nodes that are not waiting break the chain of wait-for edges
+        if state.state != State::Askin || state.awaited.is_empty() {
+            return self.outgoing(Vec::new());
+        }
+        if &initiator == id {
+            state.deadlocked = true;
+            error!(
+                "💀 Deadlock on {resource}, the probe came back while waiting for {:?}",
+                state.awaited
+            );
+            return self.outgoing(Vec::new());
+        }
+        if !state.probed.insert(initiator.clone()) {
+            return self.outgoing(Vec::new());
+        }
+        let mut awaited: Vec<NodeId> = state.awaited.iter().cloned().collect();
+        awaited.sort();
+        let messages = probes(&initiator, id, &resource, awaited);
+        self.outgoing(messages)
+    }
+}
+
+/// Builds the probe of `initiator` that `sender` sends to each of the targets.
+fn probes(
+    initiator: &NodeId,
+    sender: &NodeId,
+    resource: &str,
+    targets: Vec<NodeId>,
+) -> Vec<(NodeId, Message)> {
+    targets
+        .into_iter()
+        .map(|target| {
+            let probe = Message::Probe {
+                initiator: initiator.clone(),
+                sender: sender.clone(),
+                target: target.clone(),
+                resource: resource.to_string(),
+                clock: None,
+            };
+            (target, probe)
+        })
+        .collect()
+}
@@ -0,0 +1,56 @@
+use std::io::{Read, Write};
+
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use renraku_node::DEFAULT_MAX_MESSAGE_BYTES;
+use renraku_shared::{
+    codec::{Codec, Format},
+    frame::read_frames,
+};
+
+use crate::algorithm::Message;
+
+/// A message read from a file of frames, along with where it stands in the file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DumpedMessage {
+    /// Position of the frame in the file, from 0.
+    pub index: usize,
+    /// Length of the payload of the frame, in bytes.
+    pub length: usize,
+    pub message: Message,
+}
+
+/// Decodes each message of a file of concatenated frames, as written for each neighbour by
+/// `--record-dir`, with the codec of the recorded node.
+pub fn decode_frames(
+    reader: impl Read,
+    codec: Format,
+) -> impl Iterator<Item = Result<DumpedMessage>> {
+    read_frames(reader).enumerate().map(move |(index, frame)| {
+        let frame = frame.wrap_err_with(|| format!("reading frame {index}"))?;
+        let message = codec
+            .decode(&frame, DEFAULT_MAX_MESSAGE_BYTES as u64)
+            .map_err(|e| eyre!(e))
+            .wrap_err_with(|| format!("deserializing frame {index}"))?;
+        Ok(DumpedMessage {
+            index,
+            length: frame.len(),
+            message,
+        })
+    })
+}
+
+/// Writes a line for each message of a file of frames, with its index and length, stopping
+/// at the first frame that cannot be decoded.
+pub fn dump(reader: impl Read, mut writer: impl Write, codec: Format) -> Result<usize> {
+    let mut count = 0;
+    for dumped in decode_frames(reader, codec) {
+        let DumpedMessage {
+            index,
+            length,
+            message,
+        } = dumped?;
+        writeln!(writer, "#{index} ({length} bytes) {message:?}")?;
+        count += 1;
+    }
+    Ok(count)
+}
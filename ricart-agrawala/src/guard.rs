@@ -0,0 +1,314 @@
+use std::{
+    fmt,
+    sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError},
+    time::{Duration, Instant},
+};
+
+use color_eyre::eyre::{bail, Result};
+use renraku_node::{
+    channel::{Channel, Stream},
+    record::LocalEvent,
+    NodeConfig,
+};
+use tracing::warn;
+
+use crate::algorithm::{dispatch, wait_permissions, Message, RicAgrawala, RicAgrawalaActor, State};
+
+/// User code run by a [`Node`] when entering or leaving a critical section, given the guard
+/// of the section.
+pub type Hook<C> = Box<dyn Fn(&CriticalSectionGuard<'_, C>) + Send + Sync>;
+
+/// A node running Ricart-Agrawala's algorithm, along with what its receiving thread shares.
+pub struct Node<C: Channel = Stream> {
+    pub state: Arc<Mutex<RicAgrawala>>,
+    /// Signaled whenever the last awaited permission for a resource is received, the node
+    /// resumes, a neighbour leaves, or the node stops receiving messages.
+    pub permission: Arc<Condvar>,
+    pub config: Arc<NodeConfig<C>>,
+    /// Run once the node entered a critical section.
+    on_enter: Option<Hook<C>>,
+    /// Run right before the node leaves a critical section, still holding it.
+    on_exit: Option<Hook<C>>,
+    /// How long the node waits for the critical section before probing for a deadlock.
+    probe_after: Option<Duration>,
+    /// How long the node waits for the critical section before sending its request again.
+    retransmit_after: Option<Duration>,
+}
+
+impl<C: Channel> fmt::Debug for Node<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Node")
+            .field("state", &self.state)
+            .field("permission", &self.permission)
+            .field("config", &self.config)
+            .field("on_enter", &self.on_enter.is_some())
+            .field("on_exit", &self.on_exit.is_some())
+            .field("probe_after", &self.probe_after)
+            .field("retransmit_after", &self.retransmit_after)
+            .finish()
+    }
+}
+
+impl<C: Channel> Node<C> {
+    pub fn new(config: Arc<NodeConfig<C>>, state: RicAgrawala) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(state)),
+            permission: Arc::new(Condvar::new()),
+            config,
+            on_enter: None,
+            on_exit: None,
+            probe_after: None,
+            retransmit_after: None,
+        }
+    }
+
+    /// Runs `hook` each time the node enters a critical section.
+    pub fn on_enter(
+        mut self,
+        hook: impl Fn(&CriticalSectionGuard<'_, C>) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_enter = Some(Box::new(hook));
+        self
+    }
+
+    /// Runs `hook` each time the node is about to leave a critical section, including when
+    /// unwinding from a panic.
+    pub fn on_exit(
+        mut self,
+        hook: impl Fn(&CriticalSectionGuard<'_, C>) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_exit = Some(Box::new(hook));
+        self
+    }
+
+    /// Sends deadlock probes along the wait-for edges once the node waited `after` for a
+    /// critical section, see [`RicAgrawala::probe`].
+    pub fn probe_after(mut self, after: Duration) -> Self {
+        self.probe_after = Some(after);
+        self
+    }
+
+    /// Sends the pending request again to the nodes whose permission is missing once the
+    /// node waited `after` for a critical section, then after twice as long each time, see
+    /// [`RicAgrawalaActor::retransmit`].
+    pub fn retransmit_after(mut self, after: Duration) -> Self {
+        self.retransmit_after = Some(after);
+        self
+    }
+
+    /// Stops handling the messages received for `duration`, as a slow node would, those
+    /// messages being handled in order once the node resumes.
+    pub fn pause(&self, duration: Duration) {
+        self.state.lock().unwrap().paused_until = Some(Instant::now() + duration);
+    }
+
+    /// Handles the messages received again, before the end of a pause.
+    pub fn resume(&self) {
+        self.state.lock().unwrap().paused_until = None;
+        self.permission.notify_all();
+    }
+
+    /// Asks for the resource and waits for every permission, returning a guard that frees the
+    /// resource once dropped.
+    pub fn enter(&self, resource: &str) -> Result<CriticalSectionGuard<'_, C>> {
+        Ok(self
+            .enter_within(resource, None)?
+            .expect("waiting without a timeout never gives up"))
+    }
+
+    /// Same as [`Node::enter`], giving up once `timeout` elapses: the request is then
+    /// abandoned and `None` returned.
+    pub fn enter_within(
+        &self,
+        resource: &str,
+        timeout: Option<Duration>,
+    ) -> Result<Option<CriticalSectionGuard<'_, C>>> {
+        let outgoing = self
+            .state
+            .lock()
+            .unwrap()
+            .ask(self.config.clone(), resource);
+        report(&self.config, "asking");
+        dispatch(outgoing, &self.config)?;
+
+        let Some(mut lock) = self.wait(resource, timeout)? else {
+            let outgoing = self
+                .state
+                .lock()
+                .unwrap()
+                .abandon(self.config.clone(), resource);
+            report(&self.config, "idle");
+            dispatch(outgoing, &self.config)?;
+            return Ok(None);
+        };
+        // Requests received from now on are differed, the node no longer being idle
+        if let Err(e) = self
+            .config
+            .record_local(LocalEvent::Enter(resource.to_string()))
+        {
+            warn!("💾 Recording the access to {resource}: {e}");
+        }
+        let state = lock.resources.get_mut(resource).expect("asked for");
+        // Nothing was awaited when no neighbour is asked, the latency being measured here
+        state.measure_latency(resource);
+        state.state = State::CriticalSection;
+        let overtaken = state.overtaken;
+        drop(lock);
+        report(&self.config, "critical_section");
+        if let Some(metrics) = &self.config.metrics {
+            metrics.critical_section();
+        }
+        if let Err(e) = self.config.witness(resource, true) {
+            warn!("👁️ Telling the controller about entering {resource}: {e}");
+        }
+        let guard = CriticalSectionGuard {
+            node: self,
+            resource: resource.to_string(),
+            overtaken,
+        };
+        if let Some(hook) = &self.on_enter {
+            hook(&guard);
+        }
+        Ok(Some(guard))
+    }
+
+    /// Tells the neighbours this node asks for no critical section anymore, then keeps
+    /// answering their requests until each of them left as well, so that none waits for a
+    /// permission that never comes.
+    pub fn leave(&self) -> Result<()> {
+        let messages = self
+            .config
+            .neighbours
+            .keys()
+            .map(|peer| {
+                let leave = Message::Leave {
+                    sender: self.config.id.clone(),
+                    clock: None,
+                };
+                (peer.clone(), leave)
+            })
+            .collect();
+        let outgoing = self.state.lock().unwrap().outgoing(messages);
+        dispatch(outgoing, &self.config)?;
+
+        let lock = self
+            .permission
+            .wait_while(self.state.lock().unwrap(), |state| {
+                !state.disconnected
+                    && !self
+                        .config
+                        .neighbours
+                        .keys()
+                        .all(|n| state.left.contains(&n))
+            })
+            .unwrap();
+        if lock.disconnected {
+            bail!("no longer receiving the messages of the neighbours, waiting for them to leave");
+        }
+        Ok(())
+    }
+
+    /// Waits for the permissions as [`wait_permissions`] does, probing for a deadlock and
+    /// retransmitting the request once the node waited long enough.
+    fn wait(
+        &self,
+        resource: &str,
+        timeout: Option<Duration>,
+    ) -> Result<Option<MutexGuard<'_, RicAgrawala>>> {
+        let started = Instant::now();
+        let mut probe_at = self.probe_after;
+        let mut backoff = self.retransmit_after;
+        let mut retransmit_at = self.retransmit_after;
+        loop {
+            let until = [timeout, probe_at, retransmit_at]
+                .into_iter()
+                .flatten()
+                .min();
+            let remaining = until.map(|until| until.saturating_sub(started.elapsed()));
+            if let Some(lock) = wait_permissions(&self.state, &self.permission, resource, remaining)
+            {
+                if lock.disconnected {
+                    bail!("no longer receiving the messages of the neighbours, waiting for {resource}");
+                }
+                return Ok(Some(lock));
+            }
+            let elapsed = started.elapsed();
+            if timeout.is_some_and(|timeout| elapsed >= timeout) {
+                return Ok(None);
+            }
+            if probe_at.is_some_and(|at| elapsed >= at) {
+                probe_at = None;
+                let outgoing = self.state.lock().unwrap().probe(&self.config.id, resource);
+                dispatch(outgoing, &self.config)?;
+            }
+            if let (Some(at), Some(delay)) = (retransmit_at, backoff) {
+                if elapsed >= at {
+                    // Each retransmission waits twice as long as the previous one
+                    backoff = Some(delay * 2);
+                    retransmit_at = Some(at + delay * 2);
+                    let outgoing = self
+                        .state
+                        .lock()
+                        .unwrap()
+                        .retransmit(self.config.clone(), resource);
+                    dispatch(outgoing, &self.config)?;
+                }
+            }
+        }
+    }
+}
+
+/// Access to a critical section, freed once dropped: the differed permissions are then sent,
+/// even when unwinding from a panic.
+#[derive(Debug)]
+pub struct CriticalSectionGuard<'a, C: Channel = Stream> {
+    node: &'a Node<C>,
+    resource: String,
+    overtaken: usize,
+}
+
+impl<'a, C: Channel> CriticalSectionGuard<'a, C> {
+    pub fn resource(&self) -> &str {
+        &self.resource
+    }
+
+    /// Number of nodes granted access while this node was waiting for the critical section.
+    pub fn overtaken(&self) -> usize {
+        self.overtaken
+    }
+}
+
+impl<'a, C: Channel> Drop for CriticalSectionGuard<'a, C> {
+    fn drop(&mut self) {
+        if let Some(hook) = &self.node.on_exit {
+            hook(self);
+        }
+        let Node { state, config, .. } = self.node;
+        // Told before the permissions are sent, the next node entering being told after
+        if let Err(e) = config.witness(&self.resource, false) {
+            warn!(
+                "👁️ Telling the controller about leaving {}: {e}",
+                self.resource
+            );
+        }
+        // A panic within the critical section must not keep the others waiting forever
+        let outgoing = state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .free(config.clone(), &self.resource);
+        report(config, "idle");
+        if let Err(e) = dispatch(outgoing, config) {
+            warn!(
+                "🔓 Sending the differed permissions for {}: {e}",
+                self.resource
+            );
+        }
+    }
+}
+
+/// Reports the state the node moved to, if its metrics are served.
+fn report<C: Channel>(config: &NodeConfig<C>, state: &str) {
+    if let Some(metrics) = &config.metrics {
+        metrics.set_state(state);
+    }
+}
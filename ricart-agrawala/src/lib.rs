@@ -0,0 +1,8 @@
+pub mod algorithm;
+pub mod command;
+pub mod deadlock;
+pub mod dump;
+pub mod guard;
+pub mod receiver;
+pub mod replay;
+pub mod watchdog;
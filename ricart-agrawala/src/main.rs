@@ -1,18 +1,24 @@
-pub mod algorithm;
-pub mod receiver;
-
 use std::{
-    sync::{Arc, Condvar, Mutex},
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    sync::Arc,
     thread::{self, sleep},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
-use algorithm::{ask_access, RicAgrawala, RicAgrawalaActor};
 use clap::Parser;
-use color_eyre::eyre::Result;
-use receiver::receive_thread;
-use renraku_node::NodeArguments;
-use tracing::{info, Level};
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use renraku_node::{record::Replay, runtime::jitter};
+use renraku_ricart_agrawala::{
+    algorithm::{RicAgrawala, DEFAULT_RESOURCE},
+    command::{scheduled, Arguments, PauseCommand},
+    dump::dump,
+    guard::Node,
+    receiver::receive_thread,
+    replay::replay,
+    watchdog::{log_stuck, watchdog},
+};
+use tracing::{info, warn, Level};
 
 fn main() -> Result<()> {
     color_eyre::install()?;
@@ -21,28 +27,141 @@ fn main() -> Result<()> {
         .init();
 
     // Node configuration
-    let configuration = renraku_node::configure(NodeArguments::try_parse()?)?;
+    let arguments = Arguments::try_parse()?;
+    if let Some(dir) = arguments.replay_dir {
+        let state = replay(Replay::open(dir)?)?;
+        println!("🔁 Replayed state: {state:#?}");
+        return Ok(());
+    }
+    if let Some(path) = arguments.dump {
+        let file = File::open(&path).wrap_err_with(|| format!("opening {}", path.display()))?;
+        let count = dump(
+            io::BufReader::new(file),
+            io::stdout().lock(),
+            arguments.node.codec,
+        )?;
+        println!("📜 Dumped {count} messages");
+        return Ok(());
+    }
+    let probe = arguments.node.controller_probe;
+    let max_cycles = arguments.node.max_cycles;
+    let configuration = renraku_node::configure(arguments.node)?;
+    if probe {
+        println!("🔍 {}", configuration.describe());
+        return Ok(());
+    }
+    let mut fairness_log = arguments
+        .fairness_log
+        .map(|path| OpenOptions::new().create(true).append(true).open(path))
+        .transpose()?;
 
     // Begins
-    let variables = Arc::from(Mutex::new(RicAgrawala::default()));
-    let permission = Arc::from(Condvar::new());
-    let configuration = Arc::new(configuration);
+    configuration.start_writers();
+    let timestamp = configuration.load_clock()?;
+    let node = Node::new(
+        Arc::new(configuration),
+        RicAgrawala {
+            timestamp,
+            scope: arguments.scope,
+            quorum_fraction: arguments.quorum_fraction,
+            priority: arguments.priority,
+            contact_first: arguments.contact_first,
+            ..RicAgrawala::default()
+        },
+    )
+    .on_enter(|guard| {
+        info!(
+            overtaken = guard.overtaken(),
+            "👍 Entering critical section"
+        )
+    })
+    .on_exit(|_| info!("👍 Leaving critical section and sending authorization to others"));
+    let node = match arguments.deadlock_probe_ms {
+        Some(ms) => node.probe_after(Duration::from_millis(ms)),
+        None => node,
+    };
+    let node = match arguments.retransmit_ms {
+        Some(ms) => node.retransmit_after(Duration::from_millis(ms)),
+        None => node,
+    };
 
-    let t = (variables.clone(), permission.clone(), configuration.clone());
-    thread::spawn(move || receive_thread(t.0, t.1, t.2));
+    let t = (
+        node.state.clone(),
+        node.permission.clone(),
+        node.config.clone(),
+    );
+    let receiver = thread::spawn(move || receive_thread(t.0, t.1, t.2));
+    if let Some(ms) = arguments.watchdog_ms {
+        watchdog(
+            Arc::downgrade(&node.state),
+            Duration::from_millis(ms),
+            log_stuck,
+        );
+    }
+    let node = Arc::new(node);
+    if arguments.pause_commands {
+        let node = node.clone();
+        thread::spawn(move || {
+            for line in io::stdin().lines().map_while(Result::ok) {
+                match line.parse() {
+                    Ok(PauseCommand::Pause(duration)) => {
+                        info!("⏸️ Pausing for {duration:?}");
+                        node.pause(duration)
+                    }
+                    Ok(PauseCommand::Resume) => {
+                        info!("▶️ Resuming");
+                        node.resume()
+                    }
+                    Err(e) => warn!("⏯️ Ignoring a command: {e}"),
+                }
+            }
+        });
+    }
 
+    let start = Instant::now();
+    let interval = Duration::from_millis(arguments.interval_ms);
+    let mut round = 0;
+    let mut cycles = 0;
     loop {
-        sleep(Duration::from_millis(rand::random::<u64>() % 5000));
-        let mut lock = variables.lock().unwrap();
-        // Ask for permission
-        lock.ask(configuration.clone())?;
-        drop(lock);
-        // Waits for permission
-        let mut lock = permission.wait(variables.lock().unwrap()).unwrap();
-        info!("👍 Entering critical section");
+        if arguments.deterministic {
+            let at = scheduled(&node.config.id, &node.config.members, round, interval);
+            sleep(at.saturating_sub(start.elapsed()));
+        } else {
+            sleep(jitter(arguments.cycle.max_wait_ms));
+        }
+        round += 1;
+        // Waits for permission, giving up after the timeout if any
+        let timeout = arguments.enter_timeout_ms.map(Duration::from_millis);
+        let guard = match node.enter_within(DEFAULT_RESOURCE, timeout) {
+            Ok(Some(guard)) => guard,
+            Ok(None) => continue,
+            // The receiving thread stopped, its error telling why
+            Err(_) if node.state.lock().unwrap().disconnected => {
+                return match receiver.join() {
+                    Ok(result) => result.wrap_err("receiving messages from the neighbours"),
+                    Err(_) => Err(eyre!("the receiving thread panicked")),
+                };
+            }
+            Err(e) => return Err(e),
+        };
+        if let Some(log) = fairness_log.as_mut() {
+            writeln!(log, "{} {}", node.config.id.0, guard.overtaken())?;
+        }
         // We are in critical section
-        sleep(Duration::from_millis(rand::random::<u64>() % 5000));
-        info!("👍 Leaving critical section and sending authorization to others");
-        lock.free(configuration.clone())?;
+        if arguments.deterministic {
+            sleep(Duration::from_millis(arguments.cycle.max_hold_ms));
+        } else {
+            sleep(jitter(arguments.cycle.max_hold_ms));
+        }
+        drop(guard);
+
+        cycles += 1;
+        if max_cycles == Some(cycles) {
+            info!("🏁 Leaving the system after {cycles} critical sections, once every neighbour is done");
+            node.leave()?;
+            // The permissions granted meanwhile have been queued, they are written before closing
+            node.config.shutdown();
+            return Ok(());
+        }
     }
 }
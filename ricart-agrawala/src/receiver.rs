@@ -1,45 +1,86 @@
 use std::{
-    collections::HashMap,
-    io::Read,
-    net::TcpStream,
-    sync::{Arc, Condvar, Mutex},
+    io,
+    sync::{Arc, Condvar, Mutex, PoisonError},
 };
 
 use color_eyre::eyre::Result;
+use renraku_node::{
+    runtime::{self, dispatch, select_with_controller},
+    ControllerLink, Neighbour, NodeConfig,
+};
 use renraku_shared::NodeId;
-use selecting::Selector;
-use tracing::{debug, info};
 
-use crate::algorithm::{Message, RicAgrawala};
+use crate::algorithm::{wait_resumed, Message, RicAgrawala};
+
+pub use renraku_node::runtime::SELECT_ATTEMPTS;
 
+/// Handles the messages of the neighbours, see [`receive_with`].
+///
+/// Nodes the controller tells joined the system are connected to as soon as it does, then
+/// handed to the algorithm, see [`RicAgrawala::join`].
 pub fn receive_thread(
     mutex: Arc<Mutex<RicAgrawala>>,
     permission_signal: Arc<Condvar>,
-    config: Arc<(usize, NodeId, HashMap<NodeId, TcpStream>)>,
+    config: Arc<NodeConfig>,
+) -> Result<()> {
+    let controller = config.controller.as_ref().map(ControllerLink::socket);
+    let select = |neighbours: &[(&NodeId, &Neighbour)]| {
+        let (readable, joining) = select_with_controller(neighbours, controller)?;
+        if joining {
+            runtime::admit(&config, true, |peer| {
+                mutex.lock().unwrap().join(peer, &config)
+            });
+        }
+        Ok(readable)
+    };
+    receive_with(mutex.clone(), permission_signal, config.clone(), select)
+}
+
+/// Handles the messages of the neighbours as [`receive_thread`] does, waiting for them with
+/// `select`, see [`runtime::receive_until`]. A neighbour whose stream closed stops the
+/// thread, unless the node [awaits it to rejoin](NodeConfig::awaits_rejoin).
+///
+/// Once it stops, even by panicking, the node is [`RicAgrawala::disconnected`] and whoever
+/// waits for permissions is woken up.
+pub fn receive_with(
+    mutex: Arc<Mutex<RicAgrawala>>,
+    permission_signal: Arc<Condvar>,
+    config: Arc<NodeConfig>,
+    select: impl FnMut(&[(&NodeId, &Neighbour)]) -> io::Result<Vec<NodeId>>,
 ) -> Result<()> {
-    let streams: Vec<&TcpStream> = config
-        .2
-        .iter()
-        .map(|(_, stream)| stream.to_owned())
-        .collect();
+    let _disconnect = Disconnect {
+        mutex: &mutex,
+        signal: &permission_signal,
+    };
+    runtime::receive_until(
+        &config,
+        config.awaits_rejoin(),
+        select,
+        |message: Message| {
+            // Messages received while paused wait, in order, for the node to resume
+            let outgoing = wait_resumed(&mutex, &permission_signal).handle(
+                message,
+                config.clone(),
+                permission_signal.clone(),
+            )?;
+            Ok(dispatch(outgoing, &config)?)
+        },
+    )
+}
 
-    loop {
-        // Select
-        let mut selector = Selector::new();
-        streams
-            .iter()
-            .for_each(|stream| selector.add_read(stream.to_owned()));
+/// Marks the node disconnected once dropped.
+struct Disconnect<'a> {
+    mutex: &'a Mutex<RicAgrawala>,
+    signal: &'a Condvar,
+}
 
-        let result = selector.select()?;
-        let mut v = mutex.lock().unwrap();
-        for stream in streams
-            .iter()
-            .filter(|s| result.is_read(s.to_owned().to_owned()))
-        {
-            let message = Message::receive_from(stream)?;
-            v.handle(message, config.clone(), permission_signal.clone())?;
-        }
+impl Drop for Disconnect<'_> {
+    fn drop(&mut self) {
+        // Without its receiving thread, the node would wait for permissions forever
+        self.mutex
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .disconnected = true;
+        self.signal.notify_all();
     }
-
-    Ok(())
 }
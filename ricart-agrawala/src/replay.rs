@@ -0,0 +1,47 @@
+use std::sync::{Arc, Condvar, Mutex};
+
+use color_eyre::eyre::{Result, WrapErr};
+use renraku_node::{
+    record::{LocalEvent, Replay, Replayed},
+    NodeError,
+};
+
+use crate::algorithm::{Message, RicAgrawala, RicAgrawalaActor, State};
+
+/// Feeds the recording back into the algorithm, in the order the node went through it,
+/// returning the state the node ends up in.
+///
+/// Recorded messages are handed to [`RicAgrawala::handle`], while the requests for the
+/// critical section the node issued itself, its accesses and its releases are done again.
+pub fn replay(replay: Replay) -> Result<RicAgrawala> {
+    let state = Mutex::new(RicAgrawala::default());
+    let signal = Arc::new(Condvar::new());
+    let events: Vec<_> = replay.events().collect();
+    let config = Arc::new(replay.config);
+    for (i, event) in events.into_iter().enumerate() {
+        let mut state = state.lock().unwrap();
+        // Messages sent are written to neighbours nobody reads
+        match event.wrap_err_with(|| format!("reading recorded event {i}"))? {
+            Replayed::Frame(peer, frame) => {
+                let neighbour = config
+                    .neighbours
+                    .get(&peer)
+                    .ok_or_else(|| NodeError::UnknownNeighbour(peer.clone()))?;
+                let message: Message = neighbour
+                    .decode(&frame)
+                    .wrap_err_with(|| format!("deserializing recorded frame {i} from {peer:?}"))?;
+                state.handle(message, config.clone(), signal.clone())?;
+            }
+            Replayed::Local(LocalEvent::Ask(resource)) => {
+                state.ask(config.clone(), &resource);
+            }
+            Replayed::Local(LocalEvent::Enter(resource)) => {
+                state.resource_mut(&resource).state = State::CriticalSection;
+            }
+            Replayed::Local(LocalEvent::Free(resource)) => {
+                state.free(config.clone(), &resource);
+            }
+        }
+    }
+    Ok(state.into_inner().unwrap())
+}
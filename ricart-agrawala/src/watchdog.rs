@@ -0,0 +1,103 @@
+use std::{
+    collections::HashSet,
+    sync::{Mutex, Weak},
+    thread::{self, sleep, JoinHandle},
+    time::Duration,
+};
+
+use renraku_shared::NodeId;
+use tracing::warn;
+
+use crate::algorithm::{ResourceId, RicAgrawala, State};
+
+impl RicAgrawala {
+    /// Returns the resources the node has been asking for longer than `threshold`, along with
+    /// the nodes whose permission is still missing, sorted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use renraku_ricart_agrawala::algorithm::{ResourceState, RicAgrawala, State};
+    /// # use renraku_shared::NodeId;
+    /// use std::{
+    ///     collections::{HashMap, HashSet},
+    ///     time::{Duration, Instant},
+    /// };
+    ///
+    /// let node = RicAgrawala {
+    ///     resources: HashMap::from([(
+    ///         "default".to_string(),
+    ///         ResourceState {
+    ///             state: State::Askin,
+    ///             awaited: HashSet::from([NodeId(3), NodeId(2)]),
+    ///             asked_at: Some(Instant::now() - Duration::from_secs(2)),
+    ///             ..ResourceState::default()
+    ///         },
+    ///     )]),
+    ///     ..RicAgrawala::default()
+    /// };
+    ///
+    /// assert_eq!(
+    ///     node.stuck(Duration::from_secs(1)),
+    ///     vec![("default".to_string(), vec![NodeId(2), NodeId(3)])]
+    /// );
+    /// assert!(node.stuck(Duration::from_secs(5)).is_empty());
+    /// ```
+    pub fn stuck(&self, threshold: Duration) -> Vec<(ResourceId, Vec<NodeId>)> {
+        let mut stuck: Vec<(ResourceId, Vec<NodeId>)> = self
+            .resources
+            .iter()
+            .filter(|(_, state)| state.state == State::Askin)
+            .filter(|(_, state)| state.asked_at.is_some_and(|at| at.elapsed() > threshold))
+            .map(|(resource, state)| {
+                let mut awaited: Vec<NodeId> = state.awaited.iter().cloned().collect();
+                awaited.sort();
+                (resource.clone(), awaited)
+            })
+            .collect();
+        stuck.sort();
+        stuck
+    }
+}
+
+/// Spawns a thread checking the state of the node every `threshold`, reporting each request
+/// pending for longer than that along with the nodes whose permission is still missing.
+///
+/// Each request is reported once. The thread stops once the state is dropped.
+pub fn watchdog(
+    state: Weak<Mutex<RicAgrawala>>,
+    threshold: Duration,
+    mut report: impl FnMut(&str, &[NodeId]) + Send + 'static,
+) -> JoinHandle<()> {
+    thread::spawn(move || {
+        // Requests already reported, by resource and date
+        let mut reported = HashSet::<(ResourceId, usize)>::new();
+        loop {
+            sleep(threshold);
+            let Some(state) = state.upgrade() else {
+                return;
+            };
+            let stuck: Vec<(ResourceId, usize, Vec<NodeId>)> = {
+                let lock = state.lock().unwrap();
+                lock.stuck(threshold)
+                    .into_iter()
+                    .map(|(resource, awaited)| {
+                        let date = lock.resources[&resource].last_request_timestamp;
+                        (resource, date, awaited)
+                    })
+                    .collect()
+            };
+            drop(state);
+            for (resource, date, awaited) in stuck {
+                if reported.insert((resource.clone(), date)) {
+                    report(&resource, &awaited);
+                }
+            }
+        }
+    })
+}
+
+/// Logs a request stuck waiting for the critical section, as reported by [`watchdog`].
+pub fn log_stuck(resource: &str, awaited: &[NodeId]) {
+    warn!("🐶 Still asking for {resource}, the permission of {awaited:?} is missing");
+}
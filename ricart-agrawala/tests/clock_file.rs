@@ -0,0 +1,59 @@
+mod common;
+
+use std::{
+    path::PathBuf,
+    sync::{Arc, Condvar, Mutex},
+};
+
+use common::{ask, deliver, free, mesh_with};
+use renraku_node::clock_file::ClockFile;
+use renraku_ricart_agrawala::algorithm::{Message, RicAgrawala, DEFAULT_RESOURCE};
+
+fn clock_path() -> PathBuf {
+    std::env::temp_dir().join(format!("renraku-clock-{}", std::process::id()))
+}
+
+#[test]
+fn restarted_node_resumes_from_the_persisted_clock() {
+    // Only the first node persists its clock
+    let configs = mesh_with(2, |mut config| {
+        if config.id.0 == 1 {
+            config.clock_file = Some(ClockFile::new(clock_path()));
+        }
+        config
+    });
+    let node = Mutex::new(RicAgrawala::default());
+    let other = Mutex::new(RicAgrawala {
+        timestamp: 6,
+        ..RicAgrawala::default()
+    });
+    let signal = Arc::new(Condvar::new());
+
+    // Each request moves the clock forward
+    for _ in 0..2 {
+        ask(&node, &configs[0], DEFAULT_RESOURCE);
+        deliver(&other, &configs[1], 1, &signal);
+        deliver(&node, &configs[0], 2, &signal);
+        free(&node, &configs[0], DEFAULT_RESOURCE);
+    }
+    assert_eq!(configs[0].load_clock().unwrap(), 2);
+
+    // And so does a request issued later than the clock of the node, which grants it
+    ask(&other, &configs[1], DEFAULT_RESOURCE);
+    deliver(&node, &configs[0], 2, &signal);
+    assert_eq!(configs[0].load_clock().unwrap(), 7);
+    deliver(&other, &configs[1], 1, &signal);
+
+    // The node crashes and restarts, its next request being later than any previous one
+    let restarted = Mutex::new(RicAgrawala {
+        timestamp: configs[0].load_clock().unwrap(),
+        ..RicAgrawala::default()
+    });
+    assert!(restarted.lock().unwrap().timestamp >= 7);
+    ask(&restarted, &configs[0], DEFAULT_RESOURCE);
+    match deliver(&other, &configs[1], 1, &signal) {
+        Message::Request { date, .. } => assert!(date > 7),
+        other => panic!("unexpected message {other:?}"),
+    }
+    std::fs::remove_file(clock_path()).unwrap();
+}
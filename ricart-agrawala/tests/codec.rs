@@ -0,0 +1,68 @@
+use renraku_node::{
+    channel::MemoryChannel,
+    record::{Recorder, Replay},
+    runtime::Message as _,
+    Neighbour,
+};
+use renraku_ricart_agrawala::algorithm::{Message, DEFAULT_RESOURCE};
+use renraku_shared::{
+    codec::{Codec, Format},
+    frame::read_frame,
+    NodeId,
+};
+
+fn request() -> Message {
+    Message::Request {
+        date: 3,
+        requester: NodeId(2),
+        resource: DEFAULT_RESOURCE.to_string(),
+        priority: 0,
+        trace: None,
+        clock: None,
+    }
+}
+
+#[test]
+fn messages_round_trip_through_a_json_codec() {
+    let (local, remote) = MemoryChannel::pair();
+    let (sender, receiver) = (
+        Neighbour::new(local).unwrap().with_codec(Format::Json),
+        Neighbour::new(remote).unwrap().with_codec(Format::Json),
+    );
+
+    request().send_to(&sender).unwrap();
+    assert_eq!(Message::receive_from(&receiver).unwrap(), request());
+
+    // What travels is plain JSON, which bincode cannot make sense of
+    request().send_to(&sender).unwrap();
+    let payload = read_frame(&mut receiver.channel().clone()).unwrap();
+    let value: serde_json::Value = serde_json::from_slice(&payload).unwrap();
+    assert_eq!(value["Request"]["requester"], 2);
+
+    request().send_to(&sender).unwrap();
+    let frame = receiver.receive_frame().unwrap();
+    let bincode = Neighbour::new(MemoryChannel::pair().0).unwrap();
+    assert!(bincode.decode::<Message>(&frame).is_err());
+}
+
+#[test]
+fn recordings_are_replayed_with_the_codec_they_were_written_in() {
+    let dir = std::env::temp_dir().join(format!("renraku-codec-{}", std::process::id()));
+    let members = [NodeId(1), NodeId(2)];
+    let recorder = Recorder::create(&dir, &NodeId(1), &members, Format::Json).unwrap();
+    recorder
+        .record(&NodeId(2), &Format::Json.encode(&request()).unwrap())
+        .unwrap();
+
+    let replay = Replay::open(&dir).unwrap();
+    let (peer, frame) = replay.frames().next().unwrap().unwrap();
+    let message: Message = replay
+        .config
+        .neighbours
+        .get(&peer)
+        .unwrap()
+        .decode(&frame)
+        .unwrap();
+    assert_eq!(message, request());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,87 @@
+#![allow(dead_code)]
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Condvar, Mutex},
+};
+
+use renraku_node::{
+    channel::{Channel, MemoryChannel},
+    runtime::Message as _,
+    Neighbour, NodeArguments, NodeConfig,
+};
+use renraku_ricart_agrawala::algorithm::{dispatch, Message, RicAgrawala, RicAgrawalaActor};
+use renraku_shared::NodeId;
+
+/// Builds the configurations of `n` nodes, each one connected to every other in memory.
+pub fn mesh(n: usize) -> Vec<Arc<NodeConfig<MemoryChannel>>> {
+    mesh_with(n, |config| config)
+}
+
+/// Builds a mesh as [`mesh`] does, altering each configuration before sharing it.
+pub fn mesh_with(
+    n: usize,
+    alter: fn(NodeConfig<MemoryChannel>) -> NodeConfig<MemoryChannel>,
+) -> Vec<Arc<NodeConfig<MemoryChannel>>> {
+    let edges: Vec<(usize, usize)> = (1..=n)
+        .flat_map(|i| (i + 1..=n).map(move |j| (i, j)))
+        .collect();
+    connect(n, &edges, alter)
+}
+
+/// Builds the configurations of `n` nodes, connected in memory along the given edges.
+pub fn connect(
+    n: usize,
+    edges: &[(usize, usize)],
+    alter: fn(NodeConfig<MemoryChannel>) -> NodeConfig<MemoryChannel>,
+) -> Vec<Arc<NodeConfig<MemoryChannel>>> {
+    let mut neighbours: Vec<HashMap<NodeId, Neighbour<MemoryChannel>>> =
+        (0..n).map(|_| HashMap::new()).collect();
+    for (i, j) in edges.iter().copied() {
+        let (a, b) = MemoryChannel::pair();
+        neighbours[i - 1].insert(NodeId(j), Neighbour::new(a).unwrap());
+        neighbours[j - 1].insert(NodeId(i), Neighbour::new(b).unwrap());
+    }
+    neighbours
+        .into_iter()
+        .enumerate()
+        .map(|(i, neighbours)| Arc::new(alter(NodeConfig::new(n, NodeId(i + 1), neighbours))))
+        .collect()
+}
+
+/// Receives the next message sent by `peer` and lets the node handle it.
+pub fn deliver<C: Channel>(
+    node: &Mutex<RicAgrawala>,
+    config: &Arc<NodeConfig<C>>,
+    peer: usize,
+    signal: &Arc<Condvar>,
+) -> Message {
+    let message = Message::receive_from(&config.neighbours.get(&NodeId(peer)).unwrap()).unwrap();
+    let outgoing = node
+        .lock()
+        .unwrap()
+        .handle(message.clone(), config.clone(), signal.clone())
+        .unwrap();
+    dispatch(outgoing, config).unwrap();
+    message
+}
+
+/// Asks for the resource, sending the requests once the node is unlocked.
+pub fn ask<C: Channel>(node: &Mutex<RicAgrawala>, config: &Arc<NodeConfig<C>>, resource: &str) {
+    let outgoing = node.lock().unwrap().ask(config.clone(), resource);
+    dispatch(outgoing, config).unwrap();
+}
+
+/// Frees the resource, sending the differed permissions once the node is unlocked.
+pub fn free<C: Channel>(node: &Mutex<RicAgrawala>, config: &Arc<NodeConfig<C>>, resource: &str) {
+    let outgoing = node.lock().unwrap().free(config.clone(), resource);
+    dispatch(outgoing, config).unwrap();
+}
+
+/// Arguments of a node registering to the given controller, with default settings.
+pub fn arguments(controller: &str) -> NodeArguments {
+    NodeArguments {
+        controller: controller.to_string(),
+        ..NodeArguments::default()
+    }
+}
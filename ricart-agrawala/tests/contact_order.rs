@@ -0,0 +1,38 @@
+mod common;
+
+use std::sync::Mutex;
+
+use common::mesh;
+use renraku_ricart_agrawala::algorithm::{RicAgrawala, RicAgrawalaActor, DEFAULT_RESOURCE};
+use renraku_shared::NodeId;
+
+#[test]
+fn requests_are_queued_for_the_configured_peers_first() {
+    let configs = mesh(5);
+    let node = Mutex::new(RicAgrawala {
+        contact_first: vec![NodeId(5), NodeId(3)],
+        ..RicAgrawala::default()
+    });
+
+    let outgoing = node
+        .lock()
+        .unwrap()
+        .ask(configs[0].clone(), DEFAULT_RESOURCE);
+    let recipients: Vec<NodeId> = outgoing.messages.into_iter().map(|(to, _)| to).collect();
+    assert_eq!(recipients, [5, 3, 2, 4].map(NodeId));
+
+    // Retransmissions follow the same order, among the peers that have not answered
+    node.lock()
+        .unwrap()
+        .resources
+        .get_mut(DEFAULT_RESOURCE)
+        .unwrap()
+        .awaited
+        .remove(&NodeId(3));
+    let outgoing = node
+        .lock()
+        .unwrap()
+        .retransmit(configs[0].clone(), DEFAULT_RESOURCE);
+    let recipients: Vec<NodeId> = outgoing.messages.into_iter().map(|(to, _)| to).collect();
+    assert_eq!(recipients, [5, 2, 4].map(NodeId));
+}
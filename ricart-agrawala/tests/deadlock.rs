@@ -0,0 +1,81 @@
+mod common;
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Condvar, Mutex},
+};
+
+use common::{deliver, mesh};
+use renraku_ricart_agrawala::algorithm::{
+    dispatch, Message, ResourceState, RicAgrawala, State, DEFAULT_RESOURCE,
+};
+use renraku_shared::NodeId;
+
+/// A node waiting for the permission of the given peers only.
+fn waiting_for(peers: &[usize]) -> Mutex<RicAgrawala> {
+    Mutex::new(RicAgrawala {
+        resources: HashMap::from([(
+            DEFAULT_RESOURCE.to_string(),
+            ResourceState {
+                state: State::Askin,
+                awaited: peers.iter().copied().map(NodeId).collect::<HashSet<_>>(),
+                ..ResourceState::default()
+            },
+        )]),
+        ..RicAgrawala::default()
+    })
+}
+
+fn deadlocked(node: &Mutex<RicAgrawala>) -> bool {
+    node.lock().unwrap().resources[DEFAULT_RESOURCE].deadlocked
+}
+
+#[test]
+fn probe_going_around_a_wait_for_cycle_is_detected() {
+    let configs = mesh(3);
+    // 1 waits for 2, which waits for 3, which waits for 1
+    let nodes = [waiting_for(&[2]), waiting_for(&[3]), waiting_for(&[1])];
+    let signal = Arc::new(Condvar::new());
+
+    let probes = nodes[0].lock().unwrap().probe(&NodeId(1), DEFAULT_RESOURCE);
+    dispatch(probes, &configs[0]).unwrap();
+    for (node, from) in [(1, 1), (2, 2), (0, 3)] {
+        let probe = deliver(&nodes[node], &configs[node], from, &signal);
+        assert!(matches!(
+            probe,
+            Message::Probe { initiator: NodeId(1), sender, target, .. }
+                if sender == NodeId(from) && target == NodeId(node + 1)
+        ));
+    }
+
+    assert!(deadlocked(&nodes[0]));
+    assert!(!deadlocked(&nodes[1]) && !deadlocked(&nodes[2]));
+    // The probe stops once back to its initiator
+    assert!(configs
+        .iter()
+        .flat_map(|config| config.neighbours.values())
+        .all(|neighbour| neighbour.channel().pending() == 0));
+}
+
+#[test]
+fn probe_stops_at_a_node_that_is_not_waiting() {
+    let configs = mesh(3);
+    // 1 waits for 2, which waits for 3, which is idle
+    let nodes = [
+        waiting_for(&[2]),
+        waiting_for(&[3]),
+        Mutex::new(RicAgrawala::default()),
+    ];
+    let signal = Arc::new(Condvar::new());
+
+    let probes = nodes[0].lock().unwrap().probe(&NodeId(1), DEFAULT_RESOURCE);
+    dispatch(probes, &configs[0]).unwrap();
+    deliver(&nodes[1], &configs[1], 1, &signal);
+    deliver(&nodes[2], &configs[2], 2, &signal);
+
+    assert!(configs
+        .iter()
+        .flat_map(|config| config.neighbours.values())
+        .all(|neighbour| neighbour.channel().pending() == 0));
+    assert!(!deadlocked(&nodes[0]));
+}
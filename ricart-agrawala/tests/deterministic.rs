@@ -0,0 +1,82 @@
+use std::{
+    fs,
+    net::UdpSocket,
+    process::{Child, Command},
+    thread,
+    time::{Duration, Instant},
+};
+
+use renraku_coordinator::{
+    setup::{serve, LogFormat},
+    Graph,
+};
+
+#[test]
+fn nodes_enter_in_turn_in_deterministic_mode() {
+    let entries = enter_in_turn("p edge 3 3\ne 1 2\ne 2 3\ne 1 3\n", false, "numbered");
+    assert_eq!(entries[..6], ["1", "2", "3", "1", "2", "3"]);
+}
+
+#[test]
+fn nodes_keeping_sparse_vertex_ids_enter_in_turn() {
+    // Nodes asking every other node must not wait for 1, 3 and 5, which do not exist
+    let entries = enter_in_turn("p edge 6 3\ne 2 4\ne 4 6\ne 2 6\n", true, "sparse");
+    assert_eq!(entries[..6], ["2", "4", "6", "2", "4", "6"]);
+}
+
+/// Runs a node binary in `--deterministic` mode for each vertex of the graph, returning the
+/// identifiers of the first nodes to enter the critical section, in order.
+fn enter_in_turn(graph: &str, keep_vertex_ids: bool, name: &str) -> Vec<String> {
+    let graph: Graph = graph.parse().unwrap();
+    let count = graph.vertices.len();
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let controller = socket.local_addr().unwrap().to_string();
+    let coordinator = thread::spawn(move || {
+        serve(
+            &socket,
+            &graph,
+            count,
+            None,
+            false,
+            keep_vertex_ids,
+            LogFormat::Human,
+        )
+        .unwrap()
+    });
+
+    // Every node appends to the same log as it enters the critical section
+    let log = std::env::temp_dir().join(format!(
+        "renraku-deterministic-{name}-{}",
+        std::process::id()
+    ));
+    let mut nodes: Vec<Child> = (0..count)
+        .map(|_| {
+            Command::new(env!("CARGO_BIN_EXE_renraku_ricart_agrawala"))
+                .args(["--controller", &controller, "--deterministic"])
+                .args(["--interval-ms", "300", "--max-hold-ms", "50"])
+                .args(["--fairness-log", log.to_str().unwrap()])
+                .spawn()
+                .unwrap()
+        })
+        .collect();
+    assert!(coordinator.join().unwrap().is_success());
+
+    let deadline = Instant::now() + Duration::from_secs(20);
+    let entries = loop {
+        let entries: Vec<String> = fs::read_to_string(&log)
+            .unwrap_or_default()
+            .lines()
+            .map(|line| line.split_whitespace().next().unwrap().to_string())
+            .collect();
+        if entries.len() >= 2 * count || Instant::now() > deadline {
+            break entries;
+        }
+        thread::sleep(Duration::from_millis(50));
+    };
+    for node in nodes.iter_mut() {
+        node.kill().unwrap();
+        node.wait().unwrap();
+    }
+    fs::remove_file(&log).unwrap();
+    entries
+}
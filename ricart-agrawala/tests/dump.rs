@@ -0,0 +1,72 @@
+use std::{fs::File, process::Command};
+
+use renraku_ricart_agrawala::{
+    algorithm::{Message, DEFAULT_RESOURCE},
+    dump::{decode_frames, DumpedMessage},
+};
+use renraku_shared::{codec::Format, frame::write_frame, NodeId};
+
+#[test]
+fn file_of_three_frames_is_decoded_in_order() {
+    let messages = vec![
+        Message::Request {
+            date: 1,
+            requester: NodeId(2),
+            resource: DEFAULT_RESOURCE.to_string(),
+            priority: 0,
+            trace: None,
+            clock: None,
+        },
+        Message::Permission {
+            authorizer: NodeId(3),
+            resource: DEFAULT_RESOURCE.to_string(),
+            date: 1,
+            trace: None,
+            clock: None,
+        },
+        Message::Probe {
+            initiator: NodeId(1),
+            sender: NodeId(2),
+            target: NodeId(3),
+            resource: "printer".to_string(),
+            clock: None,
+        },
+    ];
+    let path = std::env::temp_dir().join(format!("renraku-dump-{}.frames", std::process::id()));
+    let mut file = File::create(&path).unwrap();
+    let mut lengths = Vec::new();
+    for message in messages.iter() {
+        let payload = bincode::serialize(message).unwrap();
+        lengths.push(payload.len());
+        write_frame(&mut file, &payload).unwrap();
+    }
+    drop(file);
+
+    let dumped: Vec<DumpedMessage> = decode_frames(File::open(&path).unwrap(), Format::Bincode)
+        .map(Result::unwrap)
+        .collect();
+    let expected: Vec<DumpedMessage> = messages
+        .into_iter()
+        .zip(lengths)
+        .enumerate()
+        .map(|(index, (message, length))| DumpedMessage {
+            index,
+            length,
+            message,
+        })
+        .collect();
+    assert_eq!(dumped, expected);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_renraku_ricart_agrawala"))
+        .args(["--dump", path.to_str().unwrap()])
+        .output()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 4, "{stdout}");
+    assert!(lines[0].starts_with(&format!("#0 ({} bytes) Request {{", expected[0].length)));
+    assert!(lines[2].starts_with("#2 ("), "{stdout}");
+    assert_eq!(lines[3], "📜 Dumped 3 messages");
+}
@@ -0,0 +1,65 @@
+mod common;
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Barrier, Mutex,
+    },
+    thread,
+};
+
+use common::{deliver, mesh};
+use renraku_ricart_agrawala::{
+    algorithm::{RicAgrawala, DEFAULT_RESOURCE},
+    guard::Node,
+};
+
+const NODES: usize = 4;
+const ROUNDS: usize = 1000;
+
+#[test]
+fn no_two_nodes_are_ever_in_critical_section_at_once() {
+    let nodes: Vec<Node<_>> = mesh(NODES)
+        .into_iter()
+        .map(|config| Node::new(config, RicAgrawala::default()))
+        .collect();
+    let barrier = Barrier::new(NODES);
+    // Set by whoever is in critical section, which must never find it already set
+    let inside = Mutex::new(false);
+    let (entries, violations) = (AtomicUsize::new(0), AtomicUsize::new(0));
+
+    thread::scope(|scope| {
+        for node in nodes.iter() {
+            // Each round, a node receives the request of each peer and its permission
+            for peer in node.config.neighbours.keys().map(|peer| peer.0) {
+                scope.spawn(move || {
+                    for _ in 0..2 * ROUNDS {
+                        deliver(&node.state, &node.config, peer, &node.permission);
+                    }
+                });
+            }
+
+            let (barrier, inside) = (&barrier, &inside);
+            let (entries, violations) = (&entries, &violations);
+            scope.spawn(move || {
+                for _ in 0..ROUNDS {
+                    // Every node asks at the same time, for the requests to be concurrent
+                    barrier.wait();
+                    let guard = node.enter(DEFAULT_RESOURCE).unwrap();
+                    // Counted rather than asserted, so that the other nodes are not left
+                    // waiting at the barrier
+                    if std::mem::replace(&mut *inside.lock().unwrap(), true) {
+                        violations.fetch_add(1, Ordering::SeqCst);
+                    }
+                    thread::yield_now();
+                    *inside.lock().unwrap() = false;
+                    entries.fetch_add(1, Ordering::SeqCst);
+                    drop(guard);
+                }
+            });
+        }
+    });
+
+    assert_eq!(violations.load(Ordering::SeqCst), 0);
+    assert_eq!(entries.load(Ordering::SeqCst), NODES * ROUNDS);
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use std::sync::{Arc, Condvar, Mutex};
+
+use common::{ask, deliver, free, mesh};
+use renraku_ricart_agrawala::algorithm::{RicAgrawala, State, DEFAULT_RESOURCE};
+
+#[test]
+fn overtaking_is_bounded_under_contention() {
+    const CYCLES: usize = 20;
+    let configs = mesh(3);
+    let nodes: Vec<Mutex<RicAgrawala>> = (0..3).map(|_| Mutex::default()).collect();
+    let signal = Arc::new(Condvar::new());
+    let mut entries = [0; 3];
+    let mut inside: Option<usize> = None;
+
+    for _ in 0..10_000 {
+        if entries.iter().all(|e| *e == CYCLES) && inside.is_none() {
+            break;
+        }
+        for (i, (node, config)) in nodes.iter().zip(configs.iter()).enumerate() {
+            let state = node
+                .lock()
+                .unwrap()
+                .resource(DEFAULT_RESOURCE)
+                .cloned()
+                .unwrap_or_default();
+            if inside == Some(i) {
+                inside = None;
+                free(node, config, DEFAULT_RESOURCE);
+            } else if state.state == State::Idling && entries[i] < CYCLES {
+                ask(node, config, DEFAULT_RESOURCE);
+            } else if state.state == State::Askin && state.awaited.is_empty() && inside.is_none() {
+                assert!(
+                    state.overtaken <= 2,
+                    "node {} has been overtaken {} times",
+                    i + 1,
+                    state.overtaken
+                );
+                entries[i] += 1;
+                inside = Some(i);
+            }
+        }
+
+        // Every node handles one pending message from each of its neighbours
+        for (node, config) in nodes.iter().zip(configs.iter()) {
+            for (peer, neighbour) in config.neighbours.iter() {
+                if neighbour.channel().pending() > 0 {
+                    deliver(node, config, peer.0, &signal);
+                }
+            }
+        }
+
+        // Two nodes holding every permission would both be allowed in
+        let allowed = nodes
+            .iter()
+            .filter_map(|n| n.lock().unwrap().resource(DEFAULT_RESOURCE).cloned())
+            .filter(|r| r.state == State::Askin && r.awaited.is_empty())
+            .count();
+        assert!(allowed <= 1);
+    }
+
+    assert_eq!(entries, [CYCLES; 3]);
+}
@@ -0,0 +1,146 @@
+mod common;
+
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::{Arc, Mutex},
+    thread::{self, sleep},
+    time::{Duration, Instant},
+};
+
+use common::{ask, deliver, mesh};
+use renraku_node::channel::MemoryChannel;
+use renraku_ricart_agrawala::{
+    algorithm::{Message, RicAgrawala, DEFAULT_RESOURCE},
+    guard::{CriticalSectionGuard, Node},
+};
+
+/// Lets the first node enter the critical section, the second one asking for it meanwhile,
+/// then leaves it with `leave` and checks the second node is granted access.
+fn contend(leave: impl FnOnce(CriticalSectionGuard<'_, MemoryChannel>)) {
+    let configs = mesh(2);
+    let first = Node::new(configs[0].clone(), RicAgrawala::default());
+    let second = Node::new(configs[1].clone(), RicAgrawala::default());
+
+    thread::scope(|scope| {
+        // The second node grants the request of the first one
+        scope.spawn(|| {
+            deliver(&second.state, &second.config, 1, &second.permission);
+            deliver(&first.state, &first.config, 2, &first.permission);
+        });
+        let guard = first.enter(DEFAULT_RESOURCE).unwrap();
+
+        // While the first node is within the critical section, its permission is differed
+        ask(&second.state, &second.config, DEFAULT_RESOURCE);
+        deliver(&first.state, &first.config, 2, &first.permission);
+        leave(guard);
+    });
+
+    assert!(matches!(
+        deliver(&second.state, &second.config, 1, &second.permission),
+        Message::Permission { .. }
+    ));
+    assert!(second.state.lock().unwrap().resources[DEFAULT_RESOURCE]
+        .awaited
+        .is_empty());
+}
+
+#[test]
+fn leaving_early_releases_differed_permissions() {
+    fn critical_work(guard: CriticalSectionGuard<'_, MemoryChannel>) -> Result<(), String> {
+        let _guard = guard;
+        let work: Result<(), String> = Err("nothing to do".to_string());
+        work?;
+        unreachable!("the work failed")
+    }
+    contend(|guard| assert!(critical_work(guard).is_err()));
+}
+
+#[test]
+fn panicking_releases_differed_permissions() {
+    contend(|guard| {
+        let result = panic::catch_unwind(AssertUnwindSafe(move || {
+            let _guard = guard;
+            panic!("failure within the critical section");
+        }));
+        assert!(result.is_err());
+    });
+}
+
+#[test]
+fn hooks_fire_once_per_cycle() {
+    let configs = mesh(2);
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let (entered, left) = (events.clone(), events.clone());
+    let node = Node::new(configs[0].clone(), RicAgrawala::default())
+        .on_enter(move |guard| {
+            entered
+                .lock()
+                .unwrap()
+                .push(("enter", guard.resource().to_string()))
+        })
+        .on_exit(move |guard| {
+            left.lock()
+                .unwrap()
+                .push(("exit", guard.resource().to_string()))
+        });
+    let other = Node::new(configs[1].clone(), RicAgrawala::default());
+
+    let cycles = 3;
+    thread::scope(|scope| {
+        // The other node grants each request
+        scope.spawn(|| {
+            for _ in 0..cycles {
+                deliver(&other.state, &other.config, 1, &other.permission);
+                deliver(&node.state, &node.config, 2, &node.permission);
+            }
+        });
+        for cycle in 1..=cycles {
+            let guard = node.enter(DEFAULT_RESOURCE).unwrap();
+            assert_eq!(events.lock().unwrap().len(), 2 * cycle - 1);
+            drop(guard);
+        }
+    });
+
+    let cycle = [("enter", DEFAULT_RESOURCE), ("exit", DEFAULT_RESOURCE)];
+    let expected: Vec<_> = (0..cycles)
+        .flat_map(|_| cycle.map(|(event, resource)| (event, resource.to_string())))
+        .collect();
+    assert_eq!(*events.lock().unwrap(), expected);
+}
+
+#[test]
+fn entering_measures_the_latency_of_the_request() {
+    let configs = mesh(2);
+    let node = Node::new(configs[0].clone(), RicAgrawala::default());
+    let other = Node::new(configs[1].clone(), RicAgrawala::default());
+    let delay = Duration::from_millis(50);
+
+    let asked_at = Instant::now();
+    thread::scope(|scope| {
+        // The other node takes its time to grant the request
+        scope.spawn(|| {
+            sleep(delay);
+            deliver(&other.state, &other.config, 1, &other.permission);
+            deliver(&node.state, &node.config, 2, &node.permission);
+        });
+        let guard = node.enter(DEFAULT_RESOURCE).unwrap();
+        let elapsed = asked_at.elapsed();
+        let latency = node.state.lock().unwrap().resources[DEFAULT_RESOURCE]
+            .last_latency
+            .unwrap();
+        assert!(latency >= delay && latency <= elapsed, "{latency:?}");
+        drop(guard);
+    });
+}
+
+#[test]
+fn entering_without_neighbours_measures_the_latency() {
+    let configs = mesh(1);
+    let node = Node::new(configs[0].clone(), RicAgrawala::default());
+
+    let guard = node.enter(DEFAULT_RESOURCE).unwrap();
+    let state = node.state.lock().unwrap().resources[DEFAULT_RESOURCE].clone();
+    assert!(state.last_latency.is_some());
+    assert_eq!(state.asked_at, None);
+    drop(guard);
+}
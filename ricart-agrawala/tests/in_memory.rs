@@ -0,0 +1,125 @@
+mod common;
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Condvar, Mutex},
+};
+
+use common::{ask, deliver, free};
+use renraku_node::{
+    channel::MemoryChannel,
+    event::{Direction, Event, EventLog},
+    Neighbour, NodeConfig,
+};
+use renraku_ricart_agrawala::algorithm::{Message, RicAgrawala, DEFAULT_RESOURCE};
+use renraku_shared::NodeId;
+
+fn config(
+    id: usize,
+    peer: usize,
+    channel: MemoryChannel,
+    event_log: Option<EventLog>,
+) -> Arc<NodeConfig<MemoryChannel>> {
+    Arc::new(NodeConfig {
+        node_count: 2,
+        members: vec![NodeId(1), NodeId(2)],
+        id: NodeId(id),
+        neighbours: HashMap::from([(NodeId(peer), Neighbour::new(channel).unwrap())]).into(),
+        quorum: vec![NodeId(1), NodeId(2)],
+        role: None,
+        labels: HashMap::new(),
+        placement: None,
+        event_log,
+        vector_clock: None,
+        clock_file: None,
+        controller: None,
+        metrics: None,
+        recorder: None,
+    })
+}
+
+#[test]
+fn two_nodes_take_turns_in_critical_section() {
+    let (a, b) = MemoryChannel::pair();
+    let (first_config, second_config) =
+        (config(1, 2, a.clone(), None), config(2, 1, b.clone(), None));
+    let (first, second) = (
+        Mutex::new(RicAgrawala::default()),
+        Mutex::new(RicAgrawala::default()),
+    );
+    let signal = Arc::new(Condvar::new());
+
+    // The first node asks, and is granted access by the idle second node
+    ask(&first, &first_config, DEFAULT_RESOURCE);
+    assert!(matches!(
+        deliver(&second, &second_config, 1, &signal),
+        Message::Request { .. }
+    ));
+    assert!(matches!(
+        deliver(&first, &first_config, 2, &signal),
+        Message::Permission { .. }
+    ));
+    assert!(first.lock().unwrap().resources[DEFAULT_RESOURCE]
+        .awaited
+        .is_empty());
+
+    // While the first node is in critical section, the request of the second is differed
+    ask(&second, &second_config, DEFAULT_RESOURCE);
+    deliver(&first, &first_config, 2, &signal);
+    assert_eq!(
+        first.lock().unwrap().resources[DEFAULT_RESOURCE].differed_permission,
+        vec![NodeId(2)]
+    );
+    assert_eq!(b.pending(), 0);
+    assert!(!second.lock().unwrap().resources[DEFAULT_RESOURCE]
+        .awaited
+        .is_empty());
+
+    // Leaving the critical section hands the permission over
+    free(&first, &first_config, DEFAULT_RESOURCE);
+    assert!(matches!(
+        deliver(&second, &second_config, 1, &signal),
+        Message::Permission { .. }
+    ));
+    assert!(second.lock().unwrap().resources[DEFAULT_RESOURCE]
+        .awaited
+        .is_empty());
+}
+
+#[test]
+fn request_and_permission_are_recorded_in_event_log() {
+    let path = std::env::temp_dir().join(format!("renraku-events-{}.ndjson", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let (a, b) = MemoryChannel::pair();
+    let first_config = config(1, 2, a, Some(EventLog::open(&path).unwrap()));
+    let second_config = config(2, 1, b, None);
+    let (first, second) = (
+        Mutex::new(RicAgrawala::default()),
+        Mutex::new(RicAgrawala::default()),
+    );
+    let signal = Arc::new(Condvar::new());
+
+    ask(&first, &first_config, DEFAULT_RESOURCE);
+    deliver(&second, &second_config, 1, &signal);
+    deliver(&first, &first_config, 2, &signal);
+
+    let events: Vec<Event> = std::fs::read_to_string(&path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    std::fs::remove_file(&path).unwrap();
+
+    let summary: Vec<_> = events
+        .iter()
+        .map(|e| (e.node, e.peer, e.direction, e.message.as_str(), e.clock))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (1, 2, Direction::Sent, "Request", 1),
+            (1, 2, Direction::Received, "Permission", 1),
+        ]
+    );
+}
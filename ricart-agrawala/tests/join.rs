@@ -0,0 +1,146 @@
+mod common;
+
+use std::{
+    sync::{mpsc, Arc},
+    thread::{self, sleep, JoinHandle},
+    time::Duration,
+};
+
+use renraku_coordinator::{run, CoordinatorOptions, Graph, TopologyReport};
+use renraku_node::{configure, NodeArguments};
+use renraku_ricart_agrawala::{
+    algorithm::{RicAgrawala, DEFAULT_RESOURCE},
+    guard::Node,
+    receiver::receive_thread,
+};
+use renraku_shared::NodeId;
+
+/// Arguments of a node waiting for its neighbours to rejoin once they restarted.
+fn arguments(controller: &str) -> NodeArguments {
+    NodeArguments {
+        rejoin: true,
+        ..common::arguments(controller)
+    }
+}
+
+/// Configures a node against the controller, then starts its receiving thread.
+fn start(args: NodeArguments) -> Arc<Node> {
+    let config = configure(args).unwrap();
+    config.start_writers();
+    let node = Arc::new(Node::new(Arc::new(config), RicAgrawala::default()));
+    let t = (
+        node.state.clone(),
+        node.permission.clone(),
+        node.config.clone(),
+    );
+    thread::spawn(move || receive_thread(t.0, t.1, t.2));
+    node
+}
+
+/// Runs a resident coordinator on a complete graph of `count` nodes along with a node for
+/// each vertex, returning the address nodes register on and the nodes sorted by identifier.
+fn launch(count: usize) -> (String, JoinHandle<TopologyReport>, Vec<Arc<Node>>) {
+    let (listening, address) = mpsc::channel();
+    let options = CoordinatorOptions {
+        address: "127.0.0.1:0".parse().unwrap(),
+        listening: Some(listening),
+        resident: Some(Duration::from_secs(2)),
+        ..CoordinatorOptions::default()
+    };
+    let coordinator = thread::spawn(move || run(Graph::complete(count), options).unwrap());
+    let controller = address.recv().unwrap().to_string();
+
+    let nodes: Vec<_> = (0..count)
+        .map(|_| {
+            let args = arguments(&controller);
+            thread::spawn(move || start(args))
+        })
+        .collect();
+    let mut nodes: Vec<Arc<Node>> = nodes.into_iter().map(|n| n.join().unwrap()).collect();
+    nodes.sort_by_key(|n| n.config.id.clone());
+    (controller, coordinator, nodes)
+}
+
+/// Enters the critical section on another thread, leaving it right away.
+fn enter_and_leave(node: &Arc<Node>) -> JoinHandle<()> {
+    let node = node.clone();
+    thread::spawn(move || drop(node.enter(DEFAULT_RESOURCE).unwrap()))
+}
+
+#[test]
+fn late_node_joins_and_enters_the_critical_section() {
+    let (controller, coordinator, nodes) = launch(3);
+
+    // The first node holds the critical section while the last one joins
+    let guard = nodes[0].enter(DEFAULT_RESOURCE).unwrap();
+    let late = start(arguments(&controller));
+    assert_eq!(late.config.id, NodeId(4));
+    assert_eq!(late.config.neighbours.len(), 3);
+
+    let asking = enter_and_leave(&late);
+    sleep(Duration::from_millis(300));
+    assert!(
+        !asking.is_finished(),
+        "the late node entered alongside the first one"
+    );
+    drop(guard);
+    asking.join().unwrap();
+
+    // The others ask for its permission from then on
+    for node in nodes.iter() {
+        assert_eq!(node.config.neighbours.len(), 3);
+        assert!(node.state.lock().unwrap().joined.contains(&NodeId(4)));
+        enter_and_leave(node).join().unwrap();
+    }
+
+    let report = coordinator.join().unwrap();
+    assert!(report.is_success());
+    assert_eq!(report.summary.nodes, 4);
+}
+
+#[test]
+fn restarted_node_rejoins_its_neighbours() {
+    let (controller, coordinator, nodes) = launch(3);
+    enter_and_leave(&nodes[2]).join().unwrap();
+
+    // The streams of the third node are killed, as if its process was
+    for neighbour in nodes[2].config.neighbours.values() {
+        neighbour.shutdown().unwrap();
+    }
+    let asking = enter_and_leave(&nodes[0]);
+    sleep(Duration::from_millis(300));
+    assert!(
+        !asking.is_finished(),
+        "entered without the permission of the third node"
+    );
+
+    let restarted = start(NodeArguments {
+        desired_id: Some(3),
+        ..arguments(&controller)
+    });
+    assert_eq!(restarted.config.id, NodeId(3));
+    assert_eq!(restarted.config.neighbours.len(), 2);
+    asking.join().unwrap();
+    enter_and_leave(&restarted).join().unwrap();
+    for node in nodes[..2].iter() {
+        assert!(!node.state.lock().unwrap().disconnected);
+        enter_and_leave(node).join().unwrap();
+    }
+
+    let report = coordinator.join().unwrap();
+    assert!(report.is_success());
+    assert_eq!(report.summary.nodes, 3);
+}
+
+#[test]
+fn neighbour_out_of_sync_still_stops_the_receiver() {
+    let (_controller, _coordinator, nodes) = launch(2);
+
+    // A length prefix announcing 4 GiB, over a stream that is still alive
+    let to_first = nodes[1].config.neighbours.get(&NodeId(1)).unwrap();
+    to_first.send(&u32::MAX.to_le_bytes()).unwrap();
+    sleep(Duration::from_millis(300));
+
+    assert!(nodes[0].state.lock().unwrap().disconnected);
+    assert!(!nodes[1].state.lock().unwrap().disconnected);
+}
@@ -0,0 +1,70 @@
+use std::{fs, net::UdpSocket, process::Command, thread};
+
+use renraku_coordinator::{
+    setup::{serve, LogFormat},
+    Graph,
+};
+
+#[test]
+fn node_exits_after_the_configured_cycles() {
+    // A lone node needs no permission to enter the critical section
+    let graph: Graph = "p edge 1 0\n".parse().unwrap();
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let controller = socket.local_addr().unwrap().to_string();
+    let coordinator = thread::spawn(move || {
+        serve(&socket, &graph, 1, None, false, false, LogFormat::Human).unwrap()
+    });
+
+    let log = std::env::temp_dir().join(format!("renraku-max-cycles-{}", std::process::id()));
+    let status = Command::new(env!("CARGO_BIN_EXE_renraku_ricart_agrawala"))
+        .args(["--controller", &controller, "--max-cycles", "3"])
+        .args(["--max-wait-ms", "10", "--max-hold-ms", "10"])
+        .args(["--fairness-log", log.to_str().unwrap()])
+        .status()
+        .unwrap();
+    assert!(coordinator.join().unwrap().is_success());
+
+    assert!(status.success());
+    assert_eq!(fs::read_to_string(&log).unwrap().lines().count(), 3);
+    fs::remove_file(&log).unwrap();
+}
+
+#[test]
+fn node_done_first_answers_the_others_until_they_are_done() {
+    let graph: Graph = "p edge 2 1\ne 1 2\n".parse().unwrap();
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let controller = socket.local_addr().unwrap().to_string();
+    let coordinator = thread::spawn(move || {
+        serve(&socket, &graph, 2, None, false, false, LogFormat::Human).unwrap()
+    });
+
+    // The first node to be done still has to grant the three requests of the other one
+    let nodes: Vec<_> = [1, 4]
+        .into_iter()
+        .map(|cycles| {
+            let controller = controller.clone();
+            thread::spawn(move || {
+                let log = std::env::temp_dir().join(format!(
+                    "renraku-max-cycles-{}-{cycles}",
+                    std::process::id()
+                ));
+                let status = Command::new(env!("CARGO_BIN_EXE_renraku_ricart_agrawala"))
+                    .args(["--controller", &controller])
+                    .args(["--max-cycles", &cycles.to_string()])
+                    .args(["--max-wait-ms", "10", "--max-hold-ms", "10"])
+                    .args(["--fairness-log", log.to_str().unwrap()])
+                    .status()
+                    .unwrap();
+                let entered = fs::read_to_string(&log).unwrap().lines().count();
+                fs::remove_file(&log).unwrap();
+                (status, cycles, entered)
+            })
+        })
+        .collect();
+    for node in nodes {
+        let (status, cycles, entered) = node.join().unwrap();
+        assert!(status.success(), "the node running {cycles} cycles failed");
+        assert_eq!(entered, cycles);
+    }
+    assert!(coordinator.join().unwrap().is_success());
+}
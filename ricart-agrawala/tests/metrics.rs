@@ -0,0 +1,66 @@
+mod common;
+
+use std::{
+    io::{Read, Write},
+    net::{SocketAddr, TcpStream},
+    sync::Arc,
+    thread,
+};
+
+use common::{deliver, mesh_with};
+use renraku_node::metrics::Metrics;
+use renraku_ricart_agrawala::{
+    algorithm::{RicAgrawala, DEFAULT_RESOURCE},
+    guard::Node,
+};
+
+/// Requests the metrics served on the address, returning the body of the response.
+fn scrape(address: SocketAddr) -> String {
+    let mut stream = TcpStream::connect(address).unwrap();
+    stream
+        .write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{response}");
+    response.split("\r\n\r\n").nth(1).unwrap().to_string()
+}
+
+#[test]
+fn endpoint_serves_the_metrics_of_the_node() {
+    let configs = mesh_with(2, |mut config| {
+        config.metrics = Some(Arc::new(Metrics::default()));
+        config
+    });
+    let metrics = configs[0].metrics.as_ref().unwrap();
+    let address = metrics.serve("127.0.0.1:0".parse().unwrap()).unwrap();
+    let first = Node::new(configs[0].clone(), RicAgrawala::default());
+    let second = Node::new(configs[1].clone(), RicAgrawala::default());
+
+    let body = scrape(address);
+    assert!(body.contains("renraku_lamport_clock 0\n"), "{body}");
+    assert!(body.contains("renraku_state{state=\"idle\"} 1\n"), "{body}");
+
+    thread::scope(|scope| {
+        scope.spawn(|| {
+            deliver(&second.state, &second.config, 1, &second.permission);
+            deliver(&first.state, &first.config, 2, &first.permission);
+        });
+        let guard = first.enter(DEFAULT_RESOURCE).unwrap();
+
+        let body = scrape(address);
+        for name in [
+            "# TYPE renraku_messages_total counter",
+            "renraku_messages_total{direction=\"sent\",type=\"Request\"} 1",
+            "renraku_messages_total{direction=\"received\",type=\"Permission\"} 1",
+            "renraku_lamport_clock ",
+            "renraku_critical_sections_total 1",
+            "renraku_state{state=\"critical_section\"} 1",
+        ] {
+            assert!(body.contains(name), "{name} missing from {body}");
+        }
+        drop(guard);
+    });
+
+    assert!(scrape(address).contains("renraku_state{state=\"idle\"} 1\n"));
+}
@@ -0,0 +1,51 @@
+mod common;
+
+use std::sync::{Arc, Condvar, Mutex};
+
+use common::{ask, deliver, mesh};
+use renraku_ricart_agrawala::algorithm::{
+    Message, RicAgrawala, RicAgrawalaActor, State, DEFAULT_RESOURCE,
+};
+use renraku_shared::NodeId;
+
+#[test]
+fn higher_priority_differed_requests_are_granted_first() {
+    let configs = mesh(4);
+    let holder = Mutex::new(RicAgrawala::default());
+    let signal = Arc::new(Condvar::new());
+
+    // The first node holds the critical section while the others ask for it
+    ask(&holder, &configs[0], DEFAULT_RESOURCE);
+    holder
+        .lock()
+        .unwrap()
+        .resources
+        .get_mut(DEFAULT_RESOURCE)
+        .unwrap()
+        .state = State::CriticalSection;
+    for (peer, priority) in [(2, 0), (3, 2), (4, 1)] {
+        let requester = Mutex::new(RicAgrawala {
+            priority,
+            ..RicAgrawala::default()
+        });
+        ask(&requester, &configs[peer - 1], DEFAULT_RESOURCE);
+        assert!(matches!(
+            deliver(&holder, &configs[0], peer, &signal),
+            Message::Request { priority: p, .. } if p == priority
+        ));
+    }
+
+    let outgoing = holder
+        .lock()
+        .unwrap()
+        .free(configs[0].clone(), DEFAULT_RESOURCE);
+    let granted: Vec<NodeId> = outgoing
+        .messages
+        .into_iter()
+        .map(|(peer, message)| {
+            assert!(matches!(message, Message::Permission { .. }));
+            peer
+        })
+        .collect();
+    assert_eq!(granted, [3, 4, 2].map(NodeId));
+}
@@ -0,0 +1,57 @@
+use std::{
+    net::UdpSocket,
+    process::{Command, Output},
+    thread,
+};
+
+use renraku_coordinator::{
+    setup::{serve, LogFormat},
+    Graph,
+};
+
+#[test]
+fn probe_exits_once_connected_to_neighbours() {
+    let graph: Graph = "p edge 3 2\ne 1 2\ne 2 3\n".parse().unwrap();
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let controller = socket.local_addr().unwrap().to_string();
+    let coordinator = thread::spawn(move || {
+        serve(&socket, &graph, 3, None, false, false, LogFormat::Human).unwrap()
+    });
+
+    let nodes: Vec<_> = (0..3)
+        .map(|_| {
+            let controller = controller.clone();
+            thread::spawn(move || {
+                Command::new(env!("CARGO_BIN_EXE_renraku_ricart_agrawala"))
+                    .args(["--controller", &controller, "--controller-probe"])
+                    .output()
+                    .unwrap()
+            })
+        })
+        .collect();
+    let outputs: Vec<Output> = nodes.into_iter().map(|n| n.join().unwrap()).collect();
+    assert!(coordinator.join().unwrap().is_success());
+
+    let mut reports: Vec<String> = outputs
+        .iter()
+        .map(|output| {
+            assert!(output.status.success());
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let report = stdout.lines().find(|l| l.contains("🔍")).unwrap();
+            // Peer addresses vary from one run to another
+            report
+                .split(" (")
+                .map(|part| part.split_once(')').map_or(part, |(_, rest)| rest))
+                .collect()
+        })
+        .collect();
+    reports.sort();
+    assert_eq!(
+        reports,
+        vec![
+            "🔍 Node #1 of 3, neighbours: [2], quorum: [1, 2, 3]",
+            "🔍 Node #2 of 3, neighbours: [1, 3], quorum: [1, 2]",
+            "🔍 Node #3 of 3, neighbours: [2], quorum: [1, 3]",
+        ]
+    );
+}
@@ -0,0 +1,58 @@
+mod common;
+
+use std::{
+    sync::{Arc, Condvar, Mutex},
+    time::Duration,
+};
+
+use common::{ask, deliver, mesh};
+use renraku_ricart_agrawala::algorithm::{wait_permissions, RicAgrawala, DEFAULT_RESOURCE};
+use renraku_shared::NodeId;
+
+#[test]
+fn entry_happens_once_the_fraction_of_permissions_is_received() {
+    let configs = mesh(5);
+    let node = Mutex::new(RicAgrawala {
+        quorum_fraction: Some(0.5),
+        ..RicAgrawala::default()
+    });
+    let others: Vec<Mutex<RicAgrawala>> = (1..5).map(|_| Mutex::default()).collect();
+    let signal = Arc::new(Condvar::new());
+    let timeout = Some(Duration::from_millis(50));
+
+    // Half of the 4 awaited permissions are required, 2 may be missing
+    ask(&node, &configs[0], DEFAULT_RESOURCE);
+    assert_eq!(
+        node.lock().unwrap().resources[DEFAULT_RESOURCE].remaining_threshold,
+        2
+    );
+    for (other, config) in others.iter().zip(&configs[1..]).take(2) {
+        assert!(wait_permissions(&node, &signal, DEFAULT_RESOURCE, timeout).is_none());
+        deliver(other, config, 1, &signal);
+        deliver(&node, &configs[0], config.id.0, &signal);
+    }
+
+    let lock =
+        wait_permissions(&node, &signal, DEFAULT_RESOURCE, timeout).expect("the threshold is met");
+    let mut missing: Vec<NodeId> = lock.resources[DEFAULT_RESOURCE]
+        .awaited
+        .iter()
+        .cloned()
+        .collect();
+    missing.sort();
+    assert_eq!(missing, vec![NodeId(4), NodeId(5)]);
+}
+
+#[test]
+fn every_permission_is_awaited_by_default() {
+    let configs = mesh(3);
+    let node = Mutex::new(RicAgrawala::default());
+    let other = Mutex::new(RicAgrawala::default());
+    let signal = Arc::new(Condvar::new());
+
+    ask(&node, &configs[0], DEFAULT_RESOURCE);
+    deliver(&other, &configs[1], 1, &signal);
+    deliver(&node, &configs[0], 2, &signal);
+    let timeout = Some(Duration::from_millis(50));
+    assert!(wait_permissions(&node, &signal, DEFAULT_RESOURCE, timeout).is_none());
+}
@@ -0,0 +1,153 @@
+mod common;
+
+use std::{
+    collections::HashMap,
+    io,
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Condvar, Mutex},
+    thread,
+    time::Duration,
+};
+
+use common::ask;
+use renraku_node::{runtime::Message as _, Neighbour, NodeConfig};
+use renraku_ricart_agrawala::{
+    algorithm::{Message, RicAgrawala, DEFAULT_RESOURCE},
+    guard::Node,
+    receiver::{receive_thread, receive_with, SELECT_ATTEMPTS},
+};
+use renraku_shared::NodeId;
+
+fn pair() -> (Arc<NodeConfig>, Arc<NodeConfig>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let first = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let (second, _) = listener.accept().unwrap();
+    let config = |id, peer, stream: TcpStream| {
+        let neighbours = HashMap::from([(NodeId(peer), Neighbour::new(stream.into()).unwrap())]);
+        Arc::new(NodeConfig::new(2, NodeId(id), neighbours))
+    };
+    (config(1, 2, first), config(2, 1, second))
+}
+
+#[test]
+fn selector_errors_are_retried_then_reported() {
+    let (node, peer) = pair();
+    let peer_state = Mutex::new(RicAgrawala::default());
+    ask(&peer_state, &peer, DEFAULT_RESOURCE);
+
+    // A transient failure, the request of the peer, then a selector that keeps failing
+    let mut calls = 0;
+    let select = |neighbours: &[(&NodeId, &Neighbour)]| {
+        calls += 1;
+        match calls {
+            2 => Ok(neighbours.iter().map(|(peer, _)| (*peer).clone()).collect()),
+            _ => Err(io::Error::other("injected failure")),
+        }
+    };
+    let result = receive_with(
+        Arc::new(Mutex::new(RicAgrawala::default())),
+        Arc::new(Condvar::new()),
+        node,
+        select,
+    );
+
+    assert_eq!(calls, 2 + SELECT_ATTEMPTS);
+    let report = format!("{:?}", result.unwrap_err());
+    assert!(report.contains("[NodeId(2)]"), "{report}");
+    assert!(report.contains("injected failure"), "{report}");
+    // The request received in between has been granted
+    let permission = Message::receive_from(&peer.neighbours.get(&NodeId(1)).unwrap()).unwrap();
+    assert!(matches!(permission, Message::Permission { .. }));
+}
+
+#[test]
+fn frames_read_together_are_all_handled() {
+    let (node, peer) = pair();
+    let peer_state = Mutex::new(RicAgrawala::default());
+    // Three requests sitting in the stream before the node reads anything
+    for resource in ["a", "b", "c"] {
+        ask(&peer_state, &peer, resource);
+    }
+
+    // The stream is only reported readable once, the selector then failing for good
+    let mut calls = 0;
+    let select = |neighbours: &[(&NodeId, &Neighbour)]| {
+        calls += 1;
+        match calls {
+            1 => Ok(neighbours.iter().map(|(peer, _)| (*peer).clone()).collect()),
+            _ => Err(io::Error::other("injected failure")),
+        }
+    };
+    let state = Arc::new(Mutex::new(RicAgrawala::default()));
+    let result = receive_with(state.clone(), Arc::new(Condvar::new()), node, select);
+
+    assert!(result.is_err());
+    let mut granted: Vec<String> = (0..3)
+        .map(|_| {
+            let permission =
+                Message::receive_from(&peer.neighbours.get(&NodeId(1)).unwrap()).unwrap();
+            permission.resource().unwrap().clone()
+        })
+        .collect();
+    granted.sort();
+    assert_eq!(granted, ["a", "b", "c"]);
+}
+
+#[test]
+fn messages_received_while_paused_are_handled_in_order_once_resumed() {
+    let (config, peer) = pair();
+    let node = Node::new(config, RicAgrawala::default());
+    node.pause(Duration::from_secs(60));
+    let peer_state = Mutex::new(RicAgrawala::default());
+
+    thread::scope(|scope| {
+        let receiver = scope.spawn(|| {
+            receive_thread(
+                node.state.clone(),
+                node.permission.clone(),
+                node.config.clone(),
+            )
+        });
+        for resource in ["a", "b", "c"] {
+            ask(&peer_state, &peer, resource);
+        }
+        thread::sleep(Duration::from_millis(200));
+        assert!(node.state.lock().unwrap().resources.is_empty());
+
+        node.resume();
+        let granted: Vec<String> = (0..3)
+            .map(|_| {
+                let permission =
+                    Message::receive_from(&peer.neighbours.get(&NodeId(1)).unwrap()).unwrap();
+                permission.resource().unwrap().clone()
+            })
+            .collect();
+        assert_eq!(granted, ["a", "b", "c"]);
+
+        // Without a way to dial the peer again, losing it stops the receiving thread
+        peer.shutdown();
+        assert!(receiver.join().unwrap().is_err());
+    });
+}
+
+#[test]
+fn entering_fails_once_the_receiver_stopped() {
+    let (config, _peer) = pair();
+    let node = Node::new(config, RicAgrawala::default());
+
+    thread::scope(|scope| {
+        let receiver = scope.spawn(|| {
+            let select = |_: &[(&NodeId, &Neighbour)]| Err(io::Error::other("injected failure"));
+            receive_with(
+                node.state.clone(),
+                node.permission.clone(),
+                node.config.clone(),
+                select,
+            )
+        });
+        // The permission of the peer never comes, the node being woken up rather than waiting
+        assert!(node.enter(DEFAULT_RESOURCE).is_err());
+        assert!(receiver.join().unwrap().is_err());
+    });
+    assert!(node.state.lock().unwrap().disconnected);
+}
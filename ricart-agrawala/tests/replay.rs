@@ -0,0 +1,116 @@
+mod common;
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Condvar, Mutex},
+};
+
+use common::{ask, deliver, free};
+use renraku_node::{
+    channel::MemoryChannel,
+    record::{Recorder, Replay},
+    Neighbour, NodeConfig,
+};
+use renraku_ricart_agrawala::{
+    algorithm::{RicAgrawala, State, DEFAULT_RESOURCE},
+    replay::replay,
+};
+use renraku_shared::{codec::Format, NodeId};
+
+#[test]
+fn replaying_a_recording_reproduces_the_final_state() {
+    let dir = std::env::temp_dir().join(format!("renraku-replay-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let recorder = Arc::new(
+        Recorder::create(&dir, &NodeId(2), &[NodeId(1), NodeId(2)], Format::Bincode).unwrap(),
+    );
+
+    let (a, b) = MemoryChannel::pair();
+    let first = Arc::new(NodeConfig::new(
+        2,
+        NodeId(1),
+        HashMap::from([(NodeId(2), Neighbour::new(a).unwrap())]),
+    ));
+    let recorded = Neighbour::new(b).unwrap().recorded_by(recorder, NodeId(1));
+    let second = Arc::new(NodeConfig::new(
+        2,
+        NodeId(2),
+        HashMap::from([(NodeId(1), recorded)]),
+    ));
+    let nodes = (
+        Mutex::new(RicAgrawala::default()),
+        Mutex::new(RicAgrawala::default()),
+    );
+    let signal = Arc::new(Condvar::new());
+
+    // The second node only answers the requests of the first one
+    for _ in 0..3 {
+        ask(&nodes.0, &first, DEFAULT_RESOURCE);
+        deliver(&nodes.1, &second, 1, &signal);
+        deliver(&nodes.0, &first, 2, &signal);
+        free(&nodes.0, &first, DEFAULT_RESOURCE);
+    }
+
+    let replayed = replay(Replay::open(&dir).unwrap()).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(replayed.timestamp, 3);
+    assert_eq!(replayed, *nodes.1.lock().unwrap());
+}
+
+#[test]
+fn replaying_a_recording_asks_again_whenever_the_node_asked() {
+    let dir = std::env::temp_dir().join(format!("renraku-replay-ask-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let recorder = Arc::new(
+        Recorder::create(&dir, &NodeId(2), &[NodeId(1), NodeId(2)], Format::Bincode).unwrap(),
+    );
+
+    let (a, b) = MemoryChannel::pair();
+    let first = Arc::new(NodeConfig::new(
+        2,
+        NodeId(1),
+        HashMap::from([(NodeId(2), Neighbour::new(a).unwrap())]),
+    ));
+    let second = Arc::new(
+        NodeConfig::new(
+            2,
+            NodeId(2),
+            HashMap::from([(NodeId(1), Neighbour::new(b).unwrap())]),
+        )
+        .recorded_by(recorder),
+    );
+    let nodes = (
+        Mutex::new(RicAgrawala::default()),
+        Mutex::new(RicAgrawala::default()),
+    );
+    let signal = Arc::new(Condvar::new());
+
+    // Both nodes ask at once, the first one going first, then the second one asks again
+    ask(&nodes.1, &second, DEFAULT_RESOURCE);
+    ask(&nodes.0, &first, DEFAULT_RESOURCE);
+    deliver(&nodes.1, &second, 1, &signal);
+    deliver(&nodes.0, &first, 2, &signal);
+    deliver(&nodes.0, &first, 2, &signal);
+    free(&nodes.0, &first, DEFAULT_RESOURCE);
+    deliver(&nodes.1, &second, 1, &signal);
+    free(&nodes.1, &second, DEFAULT_RESOURCE);
+    ask(&nodes.1, &second, DEFAULT_RESOURCE);
+
+    let replayed = replay(Replay::open(&dir).unwrap()).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    let recorded = nodes.1.lock().unwrap();
+    assert_eq!(replayed.timestamp, 2);
+    assert_eq!(replayed.timestamp, recorded.timestamp);
+    let (replayed, recorded) = (
+        &replayed.resources[DEFAULT_RESOURCE],
+        &recorded.resources[DEFAULT_RESOURCE],
+    );
+    assert_eq!(replayed.state, State::Askin);
+    assert_eq!(replayed.state, recorded.state);
+    assert_eq!(
+        replayed.last_request_timestamp,
+        recorded.last_request_timestamp
+    );
+    assert_eq!(replayed.awaited, recorded.awaited);
+    assert_eq!(replayed.handled_requests, recorded.handled_requests);
+}
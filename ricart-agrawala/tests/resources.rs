@@ -0,0 +1,60 @@
+mod common;
+
+use std::sync::{Arc, Condvar, Mutex};
+
+use common::{ask, deliver, free, mesh};
+use renraku_node::runtime::Message as _;
+use renraku_ricart_agrawala::algorithm::{Message, RicAgrawala};
+use renraku_shared::NodeId;
+
+#[test]
+fn resources_are_locked_independently() {
+    let configs = mesh(3);
+    let nodes: Vec<Mutex<RicAgrawala>> = (0..3).map(|_| Mutex::default()).collect();
+    let signal = Arc::new(Condvar::new());
+
+    // Node 1 enters the critical section of "a"
+    ask(&nodes[0], &configs[0], "a");
+    deliver(&nodes[1], &configs[1], 1, &signal);
+    deliver(&nodes[2], &configs[2], 1, &signal);
+    deliver(&nodes[0], &configs[0], 2, &signal);
+    deliver(&nodes[0], &configs[0], 3, &signal);
+    assert!(nodes[0].lock().unwrap().resources["a"].awaited.is_empty());
+
+    // Node 2 is still allowed into "b" while "a" is held
+    ask(&nodes[1], &configs[1], "b");
+    deliver(&nodes[0], &configs[0], 2, &signal);
+    deliver(&nodes[2], &configs[2], 2, &signal);
+    let permission = deliver(&nodes[1], &configs[1], 1, &signal);
+    assert!(matches!(permission, Message::Permission { .. }));
+    assert_eq!(permission.sender(), &NodeId(1));
+    assert_eq!(permission.resource().unwrap(), "b");
+    deliver(&nodes[1], &configs[1], 3, &signal);
+    assert!(nodes[1].lock().unwrap().resources["b"].awaited.is_empty());
+
+    // Node 3 asking for "a" has to wait for node 1, but not for node 2 which holds "b"
+    ask(&nodes[2], &configs[2], "a");
+    deliver(&nodes[0], &configs[0], 3, &signal);
+    deliver(&nodes[1], &configs[1], 3, &signal);
+    deliver(&nodes[2], &configs[2], 2, &signal);
+    assert_eq!(
+        nodes[0].lock().unwrap().resources["a"].differed_permission,
+        vec![NodeId(3)]
+    );
+    assert!(nodes[1].lock().unwrap().resources["b"]
+        .differed_permission
+        .is_empty());
+    assert_eq!(
+        nodes[2].lock().unwrap().resources["a"]
+            .awaited
+            .iter()
+            .collect::<Vec<_>>(),
+        vec![&NodeId(1)]
+    );
+
+    // Leaving "a" hands it over to node 3, "b" being untouched
+    free(&nodes[0], &configs[0], "a");
+    deliver(&nodes[2], &configs[2], 1, &signal);
+    assert!(nodes[2].lock().unwrap().resources["a"].awaited.is_empty());
+    assert!(nodes[1].lock().unwrap().resources["b"].awaited.is_empty());
+}
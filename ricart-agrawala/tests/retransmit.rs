@@ -0,0 +1,128 @@
+mod common;
+
+use std::sync::{Arc, Condvar, Mutex};
+
+use common::{ask, deliver, free, mesh, mesh_with};
+use renraku_node::{channel::MemoryChannel, runtime::Message as _, NodeConfig};
+use renraku_ricart_agrawala::algorithm::{
+    dispatch, Message, RicAgrawala, RicAgrawalaActor, State, DEFAULT_RESOURCE,
+};
+use renraku_shared::NodeId;
+
+fn retransmit(node: &Mutex<RicAgrawala>, config: &Arc<NodeConfig<MemoryChannel>>) {
+    let outgoing = node
+        .lock()
+        .unwrap()
+        .retransmit(config.clone(), DEFAULT_RESOURCE);
+    dispatch(outgoing, config).unwrap();
+}
+
+fn pending(config: &NodeConfig<MemoryChannel>, peer: usize) -> usize {
+    config
+        .neighbours
+        .get(&NodeId(peer))
+        .unwrap()
+        .channel()
+        .pending()
+}
+
+#[test]
+fn dropped_request_is_retransmitted_and_granted_once() {
+    let configs = mesh(2);
+    let (first, second) = (
+        Mutex::new(RicAgrawala::default()),
+        Mutex::new(RicAgrawala::default()),
+    );
+    let signal = Arc::new(Condvar::new());
+
+    // The request never makes it to the second node
+    ask(&first, &configs[0], DEFAULT_RESOURCE);
+    Message::receive_from(&configs[1].neighbours.get(&NodeId(1)).unwrap()).unwrap();
+
+    retransmit(&first, &configs[0]);
+    assert!(matches!(
+        deliver(&second, &configs[1], 1, &signal),
+        Message::Request { date: 1, .. }
+    ));
+    deliver(&first, &configs[0], 2, &signal);
+    assert!(first.lock().unwrap().resources[DEFAULT_RESOURCE].is_permitted());
+
+    // Nothing is left to retransmit once every permission has been received
+    retransmit(&first, &configs[0]);
+    assert_eq!(pending(&configs[1], 1), 0);
+}
+
+#[test]
+fn retransmitted_request_is_not_differed_twice() {
+    let configs = mesh(2);
+    let (first, second) = (
+        Mutex::new(RicAgrawala::default()),
+        Mutex::new(RicAgrawala::default()),
+    );
+    let signal = Arc::new(Condvar::new());
+
+    // The second node is within the critical section when both copies of the request arrive
+    ask(&second, &configs[1], DEFAULT_RESOURCE);
+    deliver(&first, &configs[0], 2, &signal);
+    deliver(&second, &configs[1], 1, &signal);
+    second
+        .lock()
+        .unwrap()
+        .resources
+        .get_mut(DEFAULT_RESOURCE)
+        .unwrap()
+        .state = State::CriticalSection;
+    ask(&first, &configs[0], DEFAULT_RESOURCE);
+    retransmit(&first, &configs[0]);
+    deliver(&second, &configs[1], 1, &signal);
+    deliver(&second, &configs[1], 1, &signal);
+    assert_eq!(
+        second.lock().unwrap().resources[DEFAULT_RESOURCE].differed_permission,
+        vec![NodeId(1)]
+    );
+
+    // Leaving the critical section grants the request exactly once
+    free(&second, &configs[1], DEFAULT_RESOURCE);
+    assert!(matches!(
+        deliver(&first, &configs[0], 2, &signal),
+        Message::Permission { .. }
+    ));
+    assert_eq!(pending(&configs[0], 2), 0);
+}
+
+#[test]
+fn retransmitted_request_is_granted_again_when_the_permission_was_lost() {
+    let configs = mesh_with(2, NodeConfig::with_vector_clock);
+    let (first, second) = (
+        Mutex::new(RicAgrawala::default()),
+        Mutex::new(RicAgrawala::default()),
+    );
+    let signal = Arc::new(Condvar::new());
+
+    // The request is granted, but the permission never makes it to the first node
+    ask(&first, &configs[0], DEFAULT_RESOURCE);
+    deliver(&second, &configs[1], 1, &signal);
+    Message::receive_from(&configs[0].neighbours.get(&NodeId(2)).unwrap()).unwrap();
+
+    retransmit(&first, &configs[0]);
+    deliver(&second, &configs[1], 1, &signal);
+    // The retransmitted copy is received all the same, its clock merged into the node's
+    let clock = |config: &NodeConfig<MemoryChannel>| {
+        config
+            .vector_clock
+            .as_ref()
+            .unwrap()
+            .lock()
+            .unwrap()
+            .clone()
+    };
+    assert_eq!(
+        clock(&configs[1]).get(&NodeId(1)),
+        clock(&configs[0]).get(&NodeId(1))
+    );
+    assert!(matches!(
+        deliver(&first, &configs[0], 2, &signal),
+        Message::Permission { date: 1, .. }
+    ));
+    assert!(first.lock().unwrap().resources[DEFAULT_RESOURCE].is_permitted());
+}
@@ -0,0 +1,48 @@
+mod common;
+
+use std::{
+    collections::HashSet,
+    sync::{Arc, Condvar, Mutex},
+};
+
+use common::{ask, connect, deliver};
+use renraku_ricart_agrawala::algorithm::{RicAgrawala, Scope, DEFAULT_RESOURCE};
+use renraku_shared::NodeId;
+
+fn awaited(node: &Mutex<RicAgrawala>) -> HashSet<usize> {
+    node.lock().unwrap().resources[DEFAULT_RESOURCE]
+        .awaited
+        .iter()
+        .map(|n| n.0)
+        .collect()
+}
+
+#[test]
+fn neighbour_scope_only_awaits_graph_neighbours() {
+    // A path 1 - 2 - 3: the ends are not connected to each other
+    let configs = connect(3, &[(1, 2), (2, 3)], |config| config);
+    let nodes: Vec<Mutex<RicAgrawala>> = (0..3)
+        .map(|_| {
+            Mutex::new(RicAgrawala {
+                scope: Scope::Neighbours,
+                ..RicAgrawala::default()
+            })
+        })
+        .collect();
+    let signal = Arc::new(Condvar::new());
+
+    ask(&nodes[0], &configs[0], DEFAULT_RESOURCE);
+    assert_eq!(awaited(&nodes[0]), HashSet::from([2]));
+    ask(&nodes[1], &configs[1], DEFAULT_RESOURCE);
+    assert_eq!(awaited(&nodes[1]), HashSet::from([1, 3]));
+
+    // The permission of its only neighbour is enough for the first node
+    deliver(&nodes[1], &configs[1], 1, &signal);
+    deliver(&nodes[0], &configs[0], 2, &signal);
+    deliver(&nodes[0], &configs[0], 2, &signal);
+    assert!(awaited(&nodes[0]).is_empty());
+    assert_eq!(
+        nodes[0].lock().unwrap().resources[DEFAULT_RESOURCE].differed_permission,
+        vec![NodeId(2)]
+    );
+}
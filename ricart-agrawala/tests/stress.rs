@@ -0,0 +1,85 @@
+use std::{
+    collections::{HashMap, HashSet},
+    net::{TcpListener, TcpStream},
+    sync::{mpsc, Arc, Condvar, Mutex},
+    thread,
+    time::Duration,
+};
+
+use renraku_node::{Neighbour, NodeConfig};
+use renraku_ricart_agrawala::{
+    algorithm::{dispatch, RicAgrawala, RicAgrawalaActor},
+    receiver::receive_thread,
+};
+use renraku_shared::NodeId;
+
+const RESOURCES: usize = 500;
+const ROUNDS: usize = 20;
+
+/// Repeatedly asks for every resource at once, freeing each of them as soon as it is granted.
+fn run(config: Arc<NodeConfig>) {
+    let state = Arc::new(Mutex::new(RicAgrawala::default()));
+    let signal = Arc::new(Condvar::new());
+    let t = (state.clone(), signal.clone(), config.clone());
+    thread::spawn(move || receive_thread(t.0, t.1, t.2));
+
+    for _ in 0..ROUNDS {
+        let mut pending: HashSet<String> = (0..RESOURCES).map(|r| r.to_string()).collect();
+        for resource in pending.iter() {
+            let outgoing = state.lock().unwrap().ask(config.clone(), resource);
+            dispatch(outgoing, &config).unwrap();
+        }
+        while !pending.is_empty() {
+            let granted: Vec<String> = {
+                let lock = signal
+                    .wait_while(state.lock().unwrap(), |s| {
+                        !pending.iter().any(|r| s.resources[r].awaited.is_empty())
+                    })
+                    .unwrap();
+                pending
+                    .iter()
+                    .filter(|r| lock.resources[*r].awaited.is_empty())
+                    .cloned()
+                    .collect()
+            };
+            for resource in granted {
+                let outgoing = state.lock().unwrap().free(config.clone(), &resource);
+                dispatch(outgoing, &config).unwrap();
+                pending.remove(&resource);
+            }
+        }
+    }
+}
+
+#[test]
+fn heavy_mutual_traffic_does_not_deadlock() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let first = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let (second, _) = listener.accept().unwrap();
+    let configs = [
+        NodeConfig::new(
+            2,
+            NodeId(1),
+            HashMap::from([(NodeId(2), Neighbour::new(first.into()).unwrap())]),
+        ),
+        NodeConfig::new(
+            2,
+            NodeId(2),
+            HashMap::from([(NodeId(1), Neighbour::new(second.into()).unwrap())]),
+        ),
+    ];
+
+    let (done, finished) = mpsc::channel();
+    for config in configs {
+        let done = done.clone();
+        thread::spawn(move || {
+            run(Arc::new(config));
+            done.send(()).unwrap();
+        });
+    }
+    for _ in 0..2 {
+        finished
+            .recv_timeout(Duration::from_secs(60))
+            .expect("nodes are deadlocked");
+    }
+}
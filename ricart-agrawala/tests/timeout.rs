@@ -0,0 +1,87 @@
+mod common;
+
+use std::{
+    sync::{Arc, Condvar, Mutex},
+    time::Duration,
+};
+
+use common::{ask, deliver, mesh};
+use renraku_node::runtime::Message as _;
+use renraku_ricart_agrawala::algorithm::{
+    dispatch, wait_permissions, Message, RicAgrawala, RicAgrawalaActor, State, DEFAULT_RESOURCE,
+};
+use renraku_shared::NodeId;
+
+#[test]
+fn missing_peer_makes_the_attempt_time_out() {
+    let configs = mesh(3);
+    let nodes: Vec<Mutex<RicAgrawala>> = (0..3).map(|_| Mutex::default()).collect();
+    let signal = Arc::new(Condvar::new());
+
+    // Node 3 has crashed: it never handles the requests of the others
+    ask(&nodes[0], &configs[0], DEFAULT_RESOURCE);
+    ask(&nodes[1], &configs[1], DEFAULT_RESOURCE);
+    deliver(&nodes[1], &configs[1], 1, &signal);
+    deliver(&nodes[0], &configs[0], 2, &signal);
+    deliver(&nodes[0], &configs[0], 2, &signal);
+    assert_eq!(
+        nodes[0].lock().unwrap().resources[DEFAULT_RESOURCE].differed_permission,
+        vec![NodeId(2)]
+    );
+
+    let timeout = Some(Duration::from_millis(50));
+    assert!(wait_permissions(&nodes[0], &signal, DEFAULT_RESOURCE, timeout).is_none());
+
+    // Giving up hands the differed permission over
+    let outgoing = nodes[0]
+        .lock()
+        .unwrap()
+        .abandon(configs[0].clone(), DEFAULT_RESOURCE);
+    dispatch(outgoing, &configs[0]).unwrap();
+    let state = nodes[0].lock().unwrap().resources[DEFAULT_RESOURCE].clone();
+    assert_eq!(state.state, State::Idling);
+    assert!(state.awaited.is_empty() && state.differed_permission.is_empty());
+    let permission = deliver(&nodes[1], &configs[1], 1, &signal);
+    assert!(matches!(permission, Message::Permission { .. }));
+    assert_eq!(permission.sender(), &NodeId(1));
+    assert_eq!(permission.resource().unwrap(), DEFAULT_RESOURCE);
+}
+
+#[test]
+fn late_permission_does_not_count_for_the_next_request() {
+    let configs = mesh(2);
+    let nodes: Vec<Mutex<RicAgrawala>> = (0..2).map(|_| Mutex::default()).collect();
+    let signal = Arc::new(Condvar::new());
+
+    // Node 1 gives up on its first request before node 2 handled it
+    ask(&nodes[0], &configs[0], DEFAULT_RESOURCE);
+    let timeout = Some(Duration::from_millis(50));
+    assert!(wait_permissions(&nodes[0], &signal, DEFAULT_RESOURCE, timeout).is_none());
+    let outgoing = nodes[0]
+        .lock()
+        .unwrap()
+        .abandon(configs[0].clone(), DEFAULT_RESOURCE);
+    dispatch(outgoing, &configs[0]).unwrap();
+
+    // Node 2 asks, grants the request given up on, then differs the next one of node 1
+    ask(&nodes[1], &configs[1], DEFAULT_RESOURCE);
+    deliver(&nodes[1], &configs[1], 1, &signal);
+    ask(&nodes[0], &configs[0], DEFAULT_RESOURCE);
+    deliver(&nodes[1], &configs[1], 1, &signal);
+    assert_eq!(
+        nodes[1].lock().unwrap().resources[DEFAULT_RESOURCE].differed_permission,
+        vec![NodeId(1)]
+    );
+
+    // The request of node 2, then the permission granting the first request of node 1
+    deliver(&nodes[0], &configs[0], 2, &signal);
+    let late = deliver(&nodes[0], &configs[0], 2, &signal);
+    assert!(matches!(late, Message::Permission { date: 1, .. }));
+    let state = nodes[0].lock().unwrap().resources[DEFAULT_RESOURCE].clone();
+    assert_eq!(state.last_request_timestamp, 2);
+    assert!(!state.is_permitted());
+
+    // Node 2 enters with the permission of node 1, which is still kept waiting
+    deliver(&nodes[1], &configs[1], 1, &signal);
+    assert!(nodes[1].lock().unwrap().resources[DEFAULT_RESOURCE].is_permitted());
+}
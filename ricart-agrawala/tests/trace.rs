@@ -0,0 +1,62 @@
+mod common;
+
+use std::sync::{Arc, Condvar, Mutex};
+
+use common::{ask, deliver, free, mesh};
+use renraku_node::{runtime::Message as _, trace::TraceContext};
+use renraku_ricart_agrawala::algorithm::{Message, RicAgrawala, DEFAULT_RESOURCE};
+use renraku_shared::NodeId;
+
+#[test]
+fn trace_context_round_trips() {
+    let configs = mesh(2);
+    let trace = TraceContext::root();
+    let request = Message::Request {
+        date: 1,
+        requester: NodeId(1),
+        resource: DEFAULT_RESOURCE.into(),
+        priority: 0,
+        trace: Some(trace),
+        clock: None,
+    };
+
+    request
+        .clone()
+        .send_to(&configs[0].neighbours.get(&NodeId(2)).unwrap())
+        .unwrap();
+    let received = Message::receive_from(&configs[1].neighbours.get(&NodeId(1)).unwrap()).unwrap();
+    assert_eq!(received, request);
+    assert_eq!(received.trace(), Some(&trace));
+}
+
+#[test]
+fn permissions_join_the_trace_of_the_request() {
+    let configs = mesh(3);
+    let nodes: Vec<Mutex<RicAgrawala>> = (0..3).map(|_| Mutex::default()).collect();
+    let signal = Arc::new(Condvar::new());
+
+    // Node 2 holds the resource, so the permission of node 3 is differed
+    ask(&nodes[1], &configs[1], DEFAULT_RESOURCE);
+    deliver(&nodes[0], &configs[0], 2, &signal);
+    deliver(&nodes[2], &configs[2], 2, &signal);
+    deliver(&nodes[1], &configs[1], 1, &signal);
+    deliver(&nodes[1], &configs[1], 3, &signal);
+
+    ask(&nodes[0], &configs[0], DEFAULT_RESOURCE);
+    let request = deliver(&nodes[2], &configs[2], 1, &signal);
+    deliver(&nodes[1], &configs[1], 1, &signal);
+    let trace = request.trace().copied().unwrap();
+    assert_eq!(
+        nodes[0].lock().unwrap().resources[DEFAULT_RESOURCE].trace,
+        Some(trace)
+    );
+
+    let granted = deliver(&nodes[0], &configs[0], 3, &signal);
+    free(&nodes[1], &configs[1], DEFAULT_RESOURCE);
+    let differed = deliver(&nodes[0], &configs[0], 2, &signal);
+    for permission in [granted, differed] {
+        let context = permission.trace().unwrap();
+        assert_eq!(context.trace_id, trace.trace_id);
+        assert_ne!(context.span_id, trace.span_id);
+    }
+}
@@ -0,0 +1,50 @@
+#![cfg(unix)]
+
+use std::{
+    fs,
+    net::UdpSocket,
+    process::{Command, Output},
+    thread,
+};
+
+use renraku_coordinator::{
+    setup::{serve, LogFormat},
+    Graph,
+};
+
+#[test]
+fn nodes_given_a_socket_directory_connect_over_unix_domain_sockets() {
+    let dir = std::env::temp_dir().join(format!("renraku-uds-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let graph: Graph = "p edge 2 1\ne 1 2\n".parse().unwrap();
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let controller = socket.local_addr().unwrap().to_string();
+    let coordinator = thread::spawn(move || {
+        serve(&socket, &graph, 2, None, false, false, LogFormat::Human).unwrap()
+    });
+
+    let nodes: Vec<_> = (0..2)
+        .map(|_| {
+            let (controller, dir) = (controller.clone(), dir.clone());
+            thread::spawn(move || {
+                Command::new(env!("CARGO_BIN_EXE_renraku_ricart_agrawala"))
+                    .args(["--controller", &controller, "--controller-probe"])
+                    .arg("--socket-dir")
+                    .arg(&dir)
+                    .output()
+                    .unwrap()
+            })
+        })
+        .collect();
+    let outputs: Vec<Output> = nodes.into_iter().map(|n| n.join().unwrap()).collect();
+    assert!(coordinator.join().unwrap().is_success());
+
+    // Each node reports the socket its neighbour was reached at
+    for output in outputs {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(output.status.success(), "{stdout}");
+        let report = stdout.lines().find(|l| l.contains("🔍")).unwrap();
+        assert!(report.contains(&format!("({}", dir.display())), "{report}");
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
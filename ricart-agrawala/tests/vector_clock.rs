@@ -0,0 +1,40 @@
+mod common;
+
+use std::sync::{Arc, Condvar, Mutex};
+
+use common::{ask, deliver, mesh, mesh_with};
+use renraku_node::NodeConfig;
+use renraku_ricart_agrawala::algorithm::{Message, RicAgrawala, DEFAULT_RESOURCE};
+
+#[test]
+fn permissions_are_causally_ordered_after_the_request() {
+    let configs = mesh_with(3, NodeConfig::with_vector_clock);
+    let nodes: Vec<Mutex<RicAgrawala>> = (0..3).map(|_| Mutex::default()).collect();
+    let signal = Arc::new(Condvar::new());
+
+    ask(&nodes[0], &configs[0], DEFAULT_RESOURCE);
+    let to_second = deliver(&nodes[1], &configs[1], 1, &signal);
+    let to_third = deliver(&nodes[2], &configs[2], 1, &signal);
+    let from_second = deliver(&nodes[0], &configs[0], 2, &signal);
+    let from_third = deliver(&nodes[0], &configs[0], 3, &signal);
+
+    let clock = |message: &Message| message.clock().cloned().unwrap();
+    // Requests are sent one after the other by the same node
+    assert!(clock(&to_second).happens_before(&clock(&to_third)));
+    // Each permission follows the request it answers
+    assert!(clock(&to_second).happens_before(&clock(&from_second)));
+    assert!(clock(&to_third).happens_before(&clock(&from_third)));
+    // While the nodes granting access did not hear from each other
+    assert!(clock(&from_second).concurrent_with(&clock(&from_third)));
+}
+
+#[test]
+fn messages_are_not_stamped_by_default() {
+    let configs = mesh(2);
+    let nodes: Vec<Mutex<RicAgrawala>> = (0..2).map(|_| Mutex::default()).collect();
+    let signal = Arc::new(Condvar::new());
+
+    ask(&nodes[0], &configs[0], DEFAULT_RESOURCE);
+    assert_eq!(deliver(&nodes[1], &configs[1], 1, &signal).clock(), None);
+    assert_eq!(deliver(&nodes[0], &configs[0], 2, &signal).clock(), None);
+}
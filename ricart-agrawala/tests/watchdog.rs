@@ -0,0 +1,44 @@
+mod common;
+
+use std::{
+    sync::{mpsc, Arc, Condvar, Mutex},
+    time::Duration,
+};
+
+use common::{ask, deliver, mesh};
+use renraku_ricart_agrawala::{
+    algorithm::{Message, RicAgrawala, DEFAULT_RESOURCE},
+    watchdog::watchdog,
+};
+use renraku_shared::NodeId;
+
+#[test]
+fn watchdog_reports_the_missing_permission() {
+    let configs = mesh(3);
+    let nodes: Vec<Arc<Mutex<RicAgrawala>>> = (0..3).map(|_| Arc::default()).collect();
+    let signal = Arc::new(Condvar::new());
+    let (sender, reports) = mpsc::channel();
+    let watching = watchdog(
+        Arc::downgrade(&nodes[0]),
+        Duration::from_millis(50),
+        move |resource, awaited| {
+            sender
+                .send((resource.to_string(), awaited.to_vec()))
+                .unwrap()
+        },
+    );
+
+    // Node 2 grants the request, node 3 never handles it
+    ask(&nodes[0], &configs[0], DEFAULT_RESOURCE);
+    deliver(&nodes[1], &configs[1], 1, &signal);
+    let permission = deliver(&nodes[0], &configs[0], 2, &signal);
+    assert!(matches!(permission, Message::Permission { .. }));
+
+    let report = reports.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(report, (DEFAULT_RESOURCE.to_string(), vec![NodeId(3)]));
+    // The same request is only reported once
+    assert!(reports.recv_timeout(Duration::from_millis(200)).is_err());
+
+    drop(nodes);
+    watching.join().unwrap();
+}
@@ -0,0 +1,87 @@
+mod common;
+
+use std::{
+    sync::{mpsc, Arc},
+    thread::{self, sleep},
+    time::Duration,
+};
+
+use common::arguments;
+use renraku_coordinator::{run, witness::Witness, CoordinatorOptions, Graph};
+use renraku_node::configure;
+use renraku_ricart_agrawala::{
+    algorithm::{RicAgrawala, DEFAULT_RESOURCE},
+    guard::Node,
+    receiver::receive_thread,
+};
+
+/// Runs 3 nodes witnessed by the coordinator, the first two of them entering the critical
+/// section once, one shortly after the other, and returns what the coordinator witnessed.
+fn witnessed(quorum_fraction: Option<f64>) -> Witness {
+    let (listening, address) = mpsc::channel();
+    let options = CoordinatorOptions {
+        address: "127.0.0.1:0".parse().unwrap(),
+        listening: Some(listening),
+        witness: Some(Duration::from_millis(500)),
+        ..CoordinatorOptions::default()
+    };
+    let coordinator = thread::spawn(move || run(Graph::complete(3), options).unwrap());
+    let controller = address.recv().unwrap().to_string();
+
+    let nodes: Vec<_> = (0..3)
+        .map(|_| {
+            let mut args = arguments(&controller);
+            args.witness = true;
+            thread::spawn(move || {
+                let config = configure(args).unwrap();
+                let id = config.id.0;
+                let node = Node::new(
+                    Arc::new(config),
+                    RicAgrawala {
+                        quorum_fraction,
+                        ..RicAgrawala::default()
+                    },
+                );
+                let t = (
+                    node.state.clone(),
+                    node.permission.clone(),
+                    node.config.clone(),
+                );
+                thread::spawn(move || receive_thread(t.0, t.1, t.2));
+                // The third node only grants permissions
+                if id < 3 {
+                    sleep(Duration::from_millis(50) * id as u32);
+                    let guard = node.enter(DEFAULT_RESOURCE).unwrap();
+                    sleep(Duration::from_millis(300));
+                    drop(guard);
+                }
+            })
+        })
+        .collect();
+    for node in nodes {
+        node.join().unwrap();
+    }
+
+    let report = coordinator.join().unwrap();
+    let witness = report.witness.clone().unwrap();
+    assert_eq!(report.is_success(), witness.violations.is_empty());
+    witness
+}
+
+#[test]
+fn witness_sees_nodes_take_turns() {
+    let witness = witnessed(None);
+
+    assert_eq!(witness.entries, 2);
+    assert!(witness.violations.is_empty());
+}
+
+#[test]
+fn witness_detects_a_violation_of_mutual_exclusion() {
+    // Half of the permissions are enough, the third node granting both requests
+    let witness = witnessed(Some(0.5));
+
+    assert_eq!(witness.entries, 2);
+    assert_eq!(witness.violations.len(), 1);
+    assert_eq!(witness.violations[0].inside.len(), 2);
+}
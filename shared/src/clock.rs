@@ -0,0 +1,96 @@
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+
+use crate::NodeId;
+
+/// Vector clock of a node, holding the number of events it knows each node went through.
+///
+/// Unlike a scalar Lamport clock, comparing two vector clocks tells whether the events they
+/// stamp are causally related or concurrent. The entry of a node is indexed by its
+/// [`NodeId`], node `1` being the first entry.
+///
+/// # Examples
+///
+/// ```
+/// # use renraku_shared::{clock::VectorClock, NodeId};
+///
+/// let (mut first, mut second) = (VectorClock::new(2), VectorClock::new(2));
+///
+/// // The first node sends a message, which the second node receives
+/// first.tick(&NodeId(1));
+/// let sent = first.clone();
+/// second.merge(&sent);
+/// second.tick(&NodeId(2));
+/// assert!(sent.happens_before(&second));
+/// assert!(!second.happens_before(&sent));
+///
+/// // Events both nodes go through without communicating are concurrent
+/// first.tick(&NodeId(1));
+/// assert!(first.concurrent_with(&second));
+/// assert!(!first.happens_before(&second) && !second.happens_before(&first));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct VectorClock(pub Vec<usize>);
+
+impl VectorClock {
+    /// Creates the clock of a node within a system of `node_count` nodes, before any event.
+    pub fn new(node_count: usize) -> Self {
+        Self(vec![0; node_count])
+    }
+
+    /// Returns the number of events the clock knows the node went through.
+    pub fn get(&self, id: &NodeId) -> usize {
+        id.0.checked_sub(1)
+            .and_then(|index| self.0.get(index))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Counts an event of the node, growing the clock if the node is beyond its entries.
+    pub fn tick(&mut self, id: &NodeId) {
+        let index = id.0.saturating_sub(1);
+        if self.0.len() <= index {
+            self.0.resize(index + 1, 0);
+        }
+        self.0[index] += 1;
+    }
+
+    /// Takes into account every event the other clock knows of, as when receiving a message
+    /// stamped with it.
+    pub fn merge(&mut self, other: &VectorClock) {
+        if self.0.len() < other.0.len() {
+            self.0.resize(other.0.len(), 0);
+        }
+        self.0
+            .iter_mut()
+            .zip(other.0.iter())
+            .for_each(|(own, other)| *own = (*own).max(*other));
+    }
+
+    /// Returns whether the event stamped with this clock causally precedes the one stamped
+    /// with the other.
+    pub fn happens_before(&self, other: &VectorClock) -> bool {
+        self.compare(other) == Some(Ordering::Less)
+    }
+
+    /// Returns whether neither event causally precedes the other.
+    pub fn concurrent_with(&self, other: &VectorClock) -> bool {
+        self.compare(other).is_none()
+    }
+
+    /// Orders clocks causally, missing entries counting as `0`: clocks of concurrent events
+    /// are not comparable.
+    fn compare(&self, other: &Self) -> Option<Ordering> {
+        let length = self.0.len().max(other.0.len());
+        let entry = |clock: &Self, index: usize| clock.0.get(index).copied().unwrap_or(0);
+        (0..length).try_fold(Ordering::Equal, |ordering, index| {
+            match (ordering, entry(self, index).cmp(&entry(other, index))) {
+                (ordering, Ordering::Equal) => Some(ordering),
+                (Ordering::Equal, entry) => Some(entry),
+                (ordering, entry) if ordering == entry => Some(ordering),
+                _ => None,
+            }
+        })
+    }
+}
@@ -0,0 +1,132 @@
+use std::{error::Error, fmt, str::FromStr};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::frame::decode;
+
+/// Error raised by a [`Codec`], whatever the format.
+pub type CodecError = Box<dyn Error + Send + Sync>;
+
+/// Turns messages into payloads and back, so that the format they travel in can be swapped.
+///
+/// [`Bincode`] is the codec used unless another one is given, the nodes choosing theirs with
+/// a [`Format`].
+///
+/// # Examples
+///
+/// ```
+/// # use renraku_shared::{codec::{Bincode, Codec}, NodeId};
+///
+/// let payload = Bincode.encode(&NodeId(7)).unwrap();
+/// assert_eq!(Bincode.decode::<NodeId>(&payload, 64).unwrap(), NodeId(7));
+/// assert!(Bincode.decode::<NodeId>(&payload, 4).is_err());
+/// ```
+pub trait Codec {
+    fn encode<T: Serialize + ?Sized>(&self, value: &T) -> Result<Vec<u8>, CodecError>;
+
+    /// Decodes a payload, failing rather than reading more than `limit` bytes.
+    fn decode<T: DeserializeOwned>(&self, payload: &[u8], limit: u64) -> Result<T, CodecError>;
+}
+
+/// The default [`Codec`], a compact binary format.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Bincode;
+
+impl Codec for Bincode {
+    fn encode<T: Serialize + ?Sized>(&self, value: &T) -> Result<Vec<u8>, CodecError> {
+        Ok(bincode::serialize(value)?)
+    }
+
+    fn decode<T: DeserializeOwned>(&self, payload: &[u8], limit: u64) -> Result<T, CodecError> {
+        Ok(decode(payload, limit)?)
+    }
+}
+
+/// A [`Codec`] writing JSON, readable but larger and slower to decode than [`Bincode`].
+///
+/// # Examples
+///
+/// ```
+/// # use renraku_shared::{codec::{Codec, Json}, NodeId};
+///
+/// let payload = Json.encode(&NodeId(7)).unwrap();
+/// assert_eq!(payload, b"7");
+/// assert_eq!(Json.decode::<NodeId>(&payload, 64).unwrap(), NodeId(7));
+/// assert!(Json.decode::<NodeId>(&payload, 0).is_err());
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Json;
+
+impl Codec for Json {
+    fn encode<T: Serialize + ?Sized>(&self, value: &T) -> Result<Vec<u8>, CodecError> {
+        Ok(serde_json::to_vec(value)?)
+    }
+
+    fn decode<T: DeserializeOwned>(&self, payload: &[u8], limit: u64) -> Result<T, CodecError> {
+        if payload.len() as u64 > limit {
+            return Err(format!("{} bytes exceed the limit of {limit}", payload.len()).into());
+        }
+        Ok(serde_json::from_slice(payload)?)
+    }
+}
+
+/// The codec the nodes exchange their messages with, chosen when starting them.
+///
+/// # Examples
+///
+/// ```
+/// # use renraku_shared::{codec::{Codec, Format}, NodeId};
+///
+/// let format: Format = "json".parse().unwrap();
+/// assert_eq!(format, Format::Json);
+/// assert_eq!(format.to_string(), "json");
+/// assert_eq!(Format::default(), Format::Bincode);
+/// assert!("cbor".parse::<Format>().is_err());
+///
+/// let payload = format.encode(&NodeId(7)).unwrap();
+/// assert_eq!(format.decode::<NodeId>(&payload, 64).unwrap(), NodeId(7));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    #[default]
+    Bincode,
+    Json,
+}
+
+impl Codec for Format {
+    fn encode<T: Serialize + ?Sized>(&self, value: &T) -> Result<Vec<u8>, CodecError> {
+        match self {
+            Format::Bincode => Bincode.encode(value),
+            Format::Json => Json.encode(value),
+        }
+    }
+
+    fn decode<T: DeserializeOwned>(&self, payload: &[u8], limit: u64) -> Result<T, CodecError> {
+        match self {
+            Format::Bincode => Bincode.decode(payload, limit),
+            Format::Json => Json.decode(payload, limit),
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Format::Bincode => write!(f, "bincode"),
+            Format::Json => write!(f, "json"),
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bincode" => Ok(Format::Bincode),
+            "json" => Ok(Format::Json),
+            other => Err(format!("{other:?} is neither bincode nor json")),
+        }
+    }
+}
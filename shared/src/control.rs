@@ -0,0 +1,205 @@
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    clock::VectorClock,
+    frame::{decode, frame, unframe},
+    NodeId, Placement,
+};
+
+/// Messages exchanged between the coordinator and the nodes over UDP during the setup.
+///
+/// A node says [`ControlMessage::Hello`], which the coordinator acknowledges right away with
+/// [`ControlMessage::Registered`]. Once every node registered, it replies, in order, with
+/// [`ControlMessage::Assign`], [`ControlMessage::IncomingCount`], [`ControlMessage::Outgoing`],
+/// [`ControlMessage::Quorum`], [`ControlMessage::Role`], [`ControlMessage::Labels`],
+/// [`ControlMessage::Placement`] and [`ControlMessage::NeighbourCount`]. Each message is sent
+/// as a single framed datagram.
+///
+/// Nodes witnessed by the coordinator tell it whenever they enter or leave a critical section,
+/// with [`ControlMessage::Entered`] and [`ControlMessage::Left`], stamped with their vector
+/// clock so that the coordinator orders them causally rather than as they arrive.
+///
+/// A coordinator keeping on admitting nodes once the system is set up configures a node
+/// saying hello late as any other, then tells each of its neighbours where it listens with
+/// [`ControlMessage::Join`], for them to connect to it. A node restarting says hello again
+/// the same way, its neighbours replacing their stream to it.
+///
+/// # Examples
+///
+/// ```
+/// # use renraku_shared::{clock::VectorClock, control::ControlMessage, NodeId, Placement};
+/// use std::net::SocketAddr;
+///
+/// let messages = [
+///     ControlMessage::Hello { listen_port: 4242, capacity: 1, desired_id: None, advertise: None },
+///     ControlMessage::Hello {
+///         listen_port: 4243,
+///         capacity: 2,
+///         desired_id: Some(NodeId(3)),
+///         advertise: Some("203.0.113.7:30443".parse::<SocketAddr>().unwrap().into()),
+///     },
+///     ControlMessage::Registered,
+///     ControlMessage::Assign { id: NodeId(2), members: vec![NodeId(1), NodeId(2), NodeId(3)] },
+///     ControlMessage::IncomingCount(1),
+///     ControlMessage::Outgoing(vec![
+///         "127.0.0.1:4001".parse::<SocketAddr>().unwrap().into(),
+///         "[::1]:4003".parse::<SocketAddr>().unwrap().into(),
+///     ]),
+///     ControlMessage::Quorum(vec![NodeId(1), NodeId(2)]),
+///     ControlMessage::Role(Some("producer".into())),
+///     ControlMessage::Role(None),
+///     ControlMessage::Labels(vec![(NodeId(1), "fast".into())]),
+///     ControlMessage::Placement(Placement::Sink),
+///     ControlMessage::NeighbourCount(3),
+///     ControlMessage::Entered {
+///         id: NodeId(2),
+///         resource: "default".into(),
+///         clock: VectorClock(vec![0, 1]),
+///     },
+///     ControlMessage::Left {
+///         id: NodeId(2),
+///         resource: "default".into(),
+///         clock: VectorClock(vec![0, 2]),
+///     },
+///     ControlMessage::Join {
+///         id: NodeId(4),
+///         address: "127.0.0.1:4004".parse::<SocketAddr>().unwrap().into(),
+///     },
+/// ];
+/// for message in messages {
+///     let datagram = message.encode().unwrap();
+///     assert_eq!(ControlMessage::decode(&datagram).unwrap(), message);
+///     assert!(ControlMessage::decode(&datagram[..datagram.len() - 1]).is_err());
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ControlMessage {
+    /// Registers a node, along with the port it listens on for its neighbours, the capacity
+    /// of its machine relative to the other nodes, and the identifier it would like to be
+    /// given, if any.
+    ///
+    /// A node behind a NAT or a port mapping advertises the address its neighbours have to
+    /// connect to, which replaces the source of the hello and the port it listens on.
+    Hello {
+        listen_port: u16,
+        capacity: usize,
+        desired_id: Option<NodeId>,
+        advertise: Option<WireAddr>,
+    },
+    /// Acknowledges a hello, telling the node this controller is alive.
+    Registered,
+    /// Identifier assigned to the node, and the identifier of every node in the system in
+    /// increasing order.
+    Assign { id: NodeId, members: Vec<NodeId> },
+    /// Number of neighbours that will connect to the node.
+    IncomingCount(usize),
+    /// Listener addresses of the neighbours the node has to connect to.
+    Outgoing(Vec<WireAddr>),
+    /// Voting quorum of the node, used by quorum-based algorithms.
+    Quorum(Vec<NodeId>),
+    /// Role the graph tags the node with, if any.
+    Role(Option<String>),
+    /// Label of each link of the node the graph labels, along with the neighbour at its other
+    /// end.
+    Labels(Vec<(NodeId, String)>),
+    /// Where the node stands in the directed topology.
+    Placement(Placement),
+    /// Number of neighbours the node ends up with, as given by the degree of its vertex, for
+    /// it to check none of them went missing.
+    NeighbourCount(usize),
+    /// The node entered the critical section of the resource, along with its vector clock
+    /// once it did.
+    Entered {
+        id: NodeId,
+        resource: String,
+        clock: VectorClock,
+    },
+    /// The node left the critical section of the resource, along with its vector clock once
+    /// it did.
+    Left {
+        id: NodeId,
+        resource: String,
+        clock: VectorClock,
+    },
+    /// A node joined the system once it was set up, or restarted, listening on the address
+    /// for the node told about it to connect to.
+    Join { id: NodeId, address: WireAddr },
+}
+
+impl ControlMessage {
+    /// Serializes the message into a datagram.
+    pub fn encode(&self) -> bincode::Result<Vec<u8>> {
+        Ok(frame(&bincode::serialize(self)?)?)
+    }
+
+    /// Deserializes a datagram built by [`ControlMessage::encode`].
+    pub fn decode(datagram: &[u8]) -> bincode::Result<Self> {
+        Self::decode_with_limit(datagram, u64::MAX)
+    }
+
+    /// Deserializes a datagram built by [`ControlMessage::encode`], reading at most `limit`
+    /// bytes of payload.
+    pub fn decode_with_limit(datagram: &[u8], limit: u64) -> bincode::Result<Self> {
+        decode(unframe(datagram)?, limit)
+    }
+}
+
+/// Address of a listener as sent over the control channel: the bytes of its IP along with
+/// its port.
+///
+/// The layout is spelled out here rather than left to the serde implementation of
+/// [`SocketAddr`], so that a coordinator and nodes built against different versions of
+/// bincode or serde still agree on it. The flow information and scope of IPv6 addresses are
+/// not sent.
+///
+/// # Examples
+///
+/// ```
+/// # use renraku_shared::control::WireAddr;
+/// use std::net::SocketAddr;
+///
+/// for addr in ["127.0.0.1:4001", "[2001:db8::1]:4242"] {
+///     let addr: SocketAddr = addr.parse().unwrap();
+///     let bytes = bincode::serialize(&WireAddr::from(addr)).unwrap();
+///     let wire: WireAddr = bincode::deserialize(&bytes).unwrap();
+///     assert_eq!(SocketAddr::from(wire), addr);
+/// }
+///
+/// // The variant, then the bytes of the IP and the port, in little endian
+/// let addr: SocketAddr = "10.0.0.1:4001".parse().unwrap();
+/// assert_eq!(
+///     bincode::serialize(&WireAddr::from(addr)).unwrap(),
+///     [0, 0, 0, 0, 10, 0, 0, 1, 0xa1, 0x0f]
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WireAddr {
+    V4 { ip: [u8; 4], port: u16 },
+    V6 { ip: [u8; 16], port: u16 },
+}
+
+impl From<SocketAddr> for WireAddr {
+    fn from(addr: SocketAddr) -> Self {
+        match addr {
+            SocketAddr::V4(addr) => WireAddr::V4 {
+                ip: addr.ip().octets(),
+                port: addr.port(),
+            },
+            SocketAddr::V6(addr) => WireAddr::V6 {
+                ip: addr.ip().octets(),
+                port: addr.port(),
+            },
+        }
+    }
+}
+
+impl From<WireAddr> for SocketAddr {
+    fn from(addr: WireAddr) -> Self {
+        match addr {
+            WireAddr::V4 { ip, port } => SocketAddrV4::new(Ipv4Addr::from(ip), port).into(),
+            WireAddr::V6 { ip, port } => SocketAddrV6::new(Ipv6Addr::from(ip), port, 0, 0).into(),
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use std::io::{self, Read, Write};
+
+use bincode::Options;
+use serde::de::DeserializeOwned;
+
+/// Length of the header preceding the payload of each frame.
+pub const HEADER_LEN: usize = 4;
+
+/// Writes a frame made of the length of the payload (as a little-endian `u32`) followed by
+/// the payload itself, so the receiving end knows exactly how many bytes to read.
+pub fn write_frame(writer: &mut impl Write, payload: &[u8]) -> io::Result<()> {
+    let length = u32::try_from(payload.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "frame is too large"))?;
+    writer.write_all(&length.to_le_bytes())?;
+    writer.write_all(payload)
+}
+
+/// Reads a whole frame written by [`write_frame`], however fragmented the reads are.
+///
+/// # Examples
+///
+/// ```
+/// # use renraku_shared::frame::{read_frame, write_frame};
+/// use std::io::{self, Read, Write};
+///
+/// /// A stream that only transfers one byte per call.
+/// struct Trickle(Vec<u8>);
+///
+/// impl Write for Trickle {
+///     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+///         self.0.extend(buf.first());
+///         Ok(buf.len().min(1))
+///     }
+///     fn flush(&mut self) -> io::Result<()> {
+///         Ok(())
+///     }
+/// }
+///
+/// impl Read for Trickle {
+///     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+///         if self.0.is_empty() || buf.is_empty() {
+///             return Ok(0);
+///         }
+///         buf[0] = self.0.remove(0);
+///         Ok(1)
+///     }
+/// }
+///
+/// let mut stream = Trickle(Vec::new());
+/// write_frame(&mut stream, b"first").unwrap();
+/// write_frame(&mut stream, b"second").unwrap();
+///
+/// assert_eq!(read_frame(&mut stream).unwrap(), b"first");
+/// assert_eq!(read_frame(&mut stream).unwrap(), b"second");
+/// ```
+pub fn read_frame(reader: &mut impl Read) -> io::Result<Vec<u8>> {
+    let length = read_frame_len(reader)?;
+    let mut payload = vec![0; length];
+    reader.read_exact(&mut payload)?;
+    Ok(payload)
+}
+
+/// Reads the header of the next frame written by [`write_frame`], returning the length of
+/// its payload, which is left to be read.
+///
+/// Allows a reader to check the announced length before allocating for the payload.
+///
+/// # Examples
+///
+/// ```
+/// # use renraku_shared::frame::{read_frame_len, write_frame};
+/// use std::io::Read;
+///
+/// let mut stream = Vec::new();
+/// write_frame(&mut stream, b"payload").unwrap();
+///
+/// let mut reader = stream.as_slice();
+/// assert_eq!(read_frame_len(&mut reader).unwrap(), 7);
+/// let mut payload = Vec::new();
+/// reader.read_to_end(&mut payload).unwrap();
+/// assert_eq!(payload, b"payload");
+/// ```
+pub fn read_frame_len(reader: &mut impl Read) -> io::Result<usize> {
+    let mut length = [0; HEADER_LEN];
+    reader.read_exact(&mut length)?;
+    Ok(u32::from_le_bytes(length) as usize)
+}
+
+/// Returns the length of the payload announced by the header at the start of the buffer, or
+/// `None` when the buffer does not hold a whole header yet.
+///
+/// # Examples
+///
+/// ```
+/// # use renraku_shared::frame::{frame, peek_frame_len, HEADER_LEN};
+///
+/// let frame = frame(b"payload").unwrap();
+/// assert_eq!(peek_frame_len(&frame), Some(7));
+/// assert_eq!(peek_frame_len(&frame[..HEADER_LEN]), Some(7));
+/// assert_eq!(peek_frame_len(&frame[..HEADER_LEN - 1]), None);
+/// ```
+pub fn peek_frame_len(buffer: &[u8]) -> Option<usize> {
+    let (length, _) = buffer.split_first_chunk::<HEADER_LEN>()?;
+    Some(u32::from_le_bytes(*length) as usize)
+}
+
+/// Reads the frames written one after the other by [`write_frame`] until the end of the
+/// stream, such as a file a node recorded.
+///
+/// A stream ending in the middle of a frame yields an error.
+///
+/// # Examples
+///
+/// ```
+/// # use renraku_shared::frame::{read_frames, write_frame};
+///
+/// let mut file = Vec::new();
+/// write_frame(&mut file, b"first").unwrap();
+/// write_frame(&mut file, b"second").unwrap();
+///
+/// let frames: Vec<Vec<u8>> = read_frames(file.as_slice()).map(Result::unwrap).collect();
+/// assert_eq!(frames, vec![b"first".to_vec(), b"second".to_vec()]);
+///
+/// let mut frames = read_frames(&file[..file.len() - 1]);
+/// assert!(frames.next().unwrap().is_ok());
+/// assert!(frames.next().unwrap().is_err());
+/// assert!(frames.next().is_none());
+/// ```
+pub fn read_frames<R: Read>(mut reader: R) -> impl Iterator<Item = io::Result<Vec<u8>>> {
+    let mut failed = false;
+    std::iter::from_fn(move || {
+        if failed {
+            return None;
+        }
+        // The stream may only end right between two frames
+        let mut first = [0];
+        loop {
+            match reader.read(&mut first) {
+                Ok(0) => return None,
+                Ok(_) => break,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    failed = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+        let frame = read_frame(&mut first.chain(&mut reader));
+        failed = frame.is_err();
+        Some(frame)
+    })
+}
+
+/// Deserializes a payload written by `bincode::serialize`, failing rather than reading more
+/// than `limit` bytes.
+///
+/// Lengths embedded within the payload are checked against the limit before allocating,
+/// so a malformed payload cannot make the receiving end allocate an arbitrary amount.
+///
+/// # Examples
+///
+/// ```
+/// # use renraku_shared::frame::decode;
+///
+/// let payload = bincode::serialize(&vec![1u8; 16]).unwrap();
+/// assert_eq!(decode::<Vec<u8>>(&payload, 64).unwrap(), vec![1u8; 16]);
+/// assert!(decode::<Vec<u8>>(&payload, 8).is_err());
+///
+/// // A vector announcing billions of elements within a few bytes
+/// let forged = u64::MAX.to_le_bytes();
+/// assert!(decode::<Vec<u64>>(&forged, 1 << 20).is_err());
+/// ```
+pub fn decode<T: DeserializeOwned>(payload: &[u8], limit: u64) -> bincode::Result<T> {
+    bincode::options()
+        .with_fixint_encoding()
+        .allow_trailing_bytes()
+        .with_limit(limit)
+        // Limits are only enforced when deserializing from a reader, not from a slice
+        .deserialize_from(payload)
+}
+
+/// Builds a single frame holding the payload, to be sent as one datagram.
+pub fn frame(payload: &[u8]) -> io::Result<Vec<u8>> {
+    let mut frame = Vec::with_capacity(payload.len() + HEADER_LEN);
+    write_frame(&mut frame, payload)?;
+    Ok(frame)
+}
+
+/// Extracts the payload of a datagram built by [`frame`].
+///
+/// The datagram must hold exactly one whole frame: a truncated payload, or trailing bytes
+/// after it, are reported as an error rather than being deserialized into garbage.
+///
+/// # Examples
+///
+/// ```
+/// # use renraku_shared::frame::{frame, unframe};
+/// use std::net::SocketAddr;
+///
+/// let addr: SocketAddr = "[2001:db8::1]:4242".parse().unwrap();
+/// let datagram = frame(&bincode::serialize(&addr).unwrap()).unwrap();
+///
+/// let payload = unframe(&datagram).unwrap();
+/// assert_eq!(bincode::deserialize::<SocketAddr>(payload).unwrap(), addr);
+/// assert!(unframe(&datagram[..datagram.len() - 1]).is_err());
+/// ```
+pub fn unframe(datagram: &[u8]) -> io::Result<&[u8]> {
+    let invalid = |reason: String| io::Error::new(io::ErrorKind::InvalidData, reason);
+    let length = peek_frame_len(datagram).ok_or_else(|| {
+        invalid(format!(
+            "datagram of {} bytes has no length",
+            datagram.len()
+        ))
+    })?;
+    let payload = &datagram[HEADER_LEN..];
+    if payload.len() != length {
+        return Err(invalid(format!(
+            "datagram holds {} bytes of payload, {} were announced",
+            payload.len(),
+            length
+        )));
+    }
+    Ok(payload)
+}
@@ -1,5 +1,10 @@
 use serde::{Deserialize, Serialize};
 
+pub mod clock;
+pub mod codec;
+pub mod control;
+pub mod frame;
+
 /// Represents the identifier for a node within the distributed system.
 ///
 /// A [`NodeId`] is utilized to uniquely identify a node and can be considered
@@ -12,9 +17,28 @@ use serde::{Deserialize, Serialize};
 ///
 /// let node_id = NodeId(42);
 /// ```
-#[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
+///
+/// Nodes are numbered from 1, as the vertices of graph files are: `0` is reserved and never
+/// identifies a node.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct NodeId(pub usize);
 
+impl NodeId {
+    /// Returns the identifier, or `None` for the reserved `0`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use renraku_shared::NodeId;
+    ///
+    /// assert_eq!(NodeId::new(3), Some(NodeId(3)));
+    /// assert_eq!(NodeId::new(0), None);
+    /// ```
+    pub fn new(id: usize) -> Option<Self> {
+        (id != 0).then_some(Self(id))
+    }
+}
+
 /// Represents a directed connection between two nodes within the distributed system.
 ///
 /// A [`Connection`] signifies a directional link between two nodes or processes.
@@ -23,16 +47,73 @@ pub struct NodeId(pub usize);
 ///
 /// When loaded, the node with the lowest [`NodeId`] value is considered as the origin (node 0),
 /// and the node with the highest [`NodeId`] value is the destination (node 1), preventing potential
-/// interblocking scenarios.
+/// interblocking scenarios. [`Connection::new`] orders an undirected edge this way.
 ///
 /// # Examples
 ///
 /// ```
 /// # use renraku_shared::{Connection, NodeId};
 ///
-/// let node_id_1 = NodeId(0);
-/// let node_id_2 = NodeId(1);
+/// let node_id_1 = NodeId(1);
+/// let node_id_2 = NodeId(2);
 /// let connection = Connection(node_id_1, node_id_2);
 /// ```
-#[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct Connection(pub NodeId, pub NodeId);
+
+impl Connection {
+    /// Creates the connection of an undirected edge, from its lowest node to its highest.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use renraku_shared::{Connection, NodeId};
+    ///
+    /// assert_eq!(Connection::new(NodeId(3), NodeId(1)), Connection(NodeId(1), NodeId(3)));
+    /// assert_eq!(Connection::new(NodeId(1), NodeId(3)), Connection(NodeId(1), NodeId(3)));
+    /// ```
+    pub fn new(a: NodeId, b: NodeId) -> Self {
+        if a <= b {
+            Self(a, b)
+        } else {
+            Self(b, a)
+        }
+    }
+}
+
+/// Where a node stands in the directed topology, according to the direction of its connections:
+/// each of them goes from the node dialing it to the node accepting it.
+///
+/// # Examples
+///
+/// ```
+/// # use renraku_shared::Placement;
+///
+/// assert_eq!(Placement::new(0, 2), Placement::Source);
+/// assert_eq!(Placement::new(1, 0), Placement::Sink);
+/// assert_eq!(Placement::new(1, 2), Placement::Internal);
+/// assert_eq!(Placement::new(0, 0), Placement::Isolated);
+/// ```
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Placement {
+    /// Only dials its neighbours.
+    Source,
+    /// Only accepts connections from its neighbours.
+    Sink,
+    /// Both dials and accepts connections.
+    Internal,
+    /// Has no neighbour at all.
+    Isolated,
+}
+
+impl Placement {
+    /// Classifies a node from the number of connections it accepts and dials.
+    pub fn new(incoming: usize, outgoing: usize) -> Self {
+        match (incoming, outgoing) {
+            (0, 0) => Self::Isolated,
+            (0, _) => Self::Source,
+            (_, 0) => Self::Sink,
+            _ => Self::Internal,
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use std::collections::VecDeque;
+
+use renraku_node::runtime::{self, Exclusion};
+use renraku_shared::NodeId;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum Message {
+    /// Broadcasts the `seq`-th request of the requester for the critical section.
+    Request { seq: usize, requester: NodeId },
+    /// Hands the privilege over to the recipient.
+    ///
+    /// `queue` holds the nodes waiting for the token, and `last` the sequence number of the
+    /// last request granted to each node, in the order of the members of the system.
+    Token {
+        queue: VecDeque<NodeId>,
+        last: Vec<usize>,
+        sender: NodeId,
+    },
+}
+
+impl runtime::Message for Message {
+    fn kind(&self) -> &'static str {
+        match self {
+            Message::Request { .. } => "Request",
+            Message::Token { .. } => "Token",
+        }
+    }
+
+    fn sender(&self) -> &NodeId {
+        match self {
+            Message::Request { requester, .. } => requester,
+            Message::Token { sender, .. } => sender,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum State {
+    Idling,
+    Asking,
+    CriticalSection,
+}
+
+/// The privilege to enter the critical section, held by a single node at a time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    /// Nodes whose request is pending, in the order they will be given the token.
+    pub queue: VecDeque<NodeId>,
+    /// Sequence number of the last request granted to each node, in the order of the members.
+    pub last: Vec<usize>,
+}
+
+/// Messages to send to the neighbours, along with the sequence number of the node's own
+/// requests, written in the event log as its clock.
+pub type Outgoing = runtime::Outgoing<Message>;
+
+/// State of a node running Suzuki-Kasami's token-based mutual exclusion algorithm.
+///
+/// A node asks for the critical section by broadcasting a request numbered with the count of
+/// its requests. Only the holder of the token may enter the critical section, and it hands the
+/// token over to the next pending request when leaving it, which takes at most `N` messages
+/// per critical section. Every node must be a neighbour of every other, and the token starts
+/// on the node with the lowest identifier.
+#[derive(Debug, Clone)]
+pub struct SuzukiKasami {
+    pub id: NodeId,
+    /// Every node of the system, this one included, in increasing order.
+    pub members: Vec<NodeId>,
+    pub state: State,
+    /// Highest sequence number received from each node, in the order of the members.
+    pub requested: Vec<usize>,
+    /// The token, if this node holds it.
+    pub token: Option<Token>,
+}
+
+impl SuzukiKasami {
+    pub fn new(id: NodeId, mut members: Vec<NodeId>) -> Self {
+        members.sort();
+        let token = (members.first() == Some(&id)).then(|| Token {
+            queue: VecDeque::new(),
+            last: vec![0; members.len()],
+        });
+        Self {
+            id,
+            state: State::Idling,
+            requested: vec![0; members.len()],
+            members,
+            token,
+        }
+    }
+
+    /// Index of the entries of the node in the sequence numbers.
+    fn index(&self, id: &NodeId) -> usize {
+        self.members
+            .binary_search(id)
+            .unwrap_or_else(|_| panic!("{id:?} is not a member"))
+    }
+
+    /// Asks for the critical section, which is entered right away when holding the token.
+    pub fn ask(&mut self) -> Outgoing {
+        if self.token.is_some() {
+            debug!("🔓 Already holding the token");
+            self.state = State::CriticalSection;
+            return self.outgoing(Vec::new());
+        }
+        self.state = State::Asking;
+        let seq = {
+            let index = self.index(&self.id);
+            let seq = &mut self.requested[index];
+            *seq += 1;
+            *seq
+        };
+        debug!("❓ Broadcasting our request #{seq}");
+
+        let messages = self
+            .members
+            .iter()
+            .filter(|n| **n != self.id)
+            .map(|n| {
+                let request = Message::Request {
+                    seq,
+                    requester: self.id.clone(),
+                };
+                (n.clone(), request)
+            })
+            .collect();
+        self.outgoing(messages)
+    }
+
+    /// Leaves the critical section, handing the token over to the next pending request.
+    pub fn free(&mut self) -> Outgoing {
+        self.state = State::Idling;
+        let own = self.index(&self.id);
+        let Some(token) = self.token.as_mut() else {
+            return self.outgoing(Vec::new());
+        };
+        token.last[own] = self.requested[own];
+        // Queues every node with a request that has not been granted yet
+        for (i, requested) in self.requested.iter().enumerate() {
+            let node = &self.members[i];
+            if *requested == token.last[i] + 1 && !token.queue.contains(node) {
+                token.queue.push_back(node.clone());
+            }
+        }
+        let messages = token
+            .queue
+            .pop_front()
+            .map(|next| self.send_token(next))
+            .into_iter()
+            .collect();
+        self.outgoing(messages)
+    }
+
+    pub fn handle(&mut self, message: Message) -> Outgoing {
+        let mut messages = Vec::new();
+        match message {
+            Message::Request { seq, requester } => {
+                let Ok(i) = self.members.binary_search(&requester) else {
+                    warn!("📨 Dropping the request of {requester:?}, which is not a member");
+                    return self.outgoing(messages);
+                };
+                self.requested[i] = self.requested[i].max(seq);
+                // An idle holder hands the token over to a request that has not been granted
+                if self.state == State::Idling
+                    && self
+                        .token
+                        .as_ref()
+                        .is_some_and(|t| self.requested[i] == t.last[i] + 1)
+                {
+                    messages.push(self.send_token(requester));
+                }
+            }
+            Message::Token { queue, last, .. } => {
+                debug!("🔓 Received the token");
+                self.token = Some(Token { queue, last });
+                self.state = State::CriticalSection;
+            }
+        }
+        self.outgoing(messages)
+    }
+
+    fn send_token(&mut self, recipient: NodeId) -> (NodeId, Message) {
+        let Token { queue, last } = self.token.take().expect("the token is held");
+        debug!("🎫 Handing the token over to {recipient:?}");
+        let token = Message::Token {
+            queue,
+            last,
+            sender: self.id.clone(),
+        };
+        (recipient, token)
+    }
+
+    /// Number of requests issued by this node, written in the event log as its clock.
+    pub fn clock(&self) -> usize {
+        self.requested[self.index(&self.id)]
+    }
+
+    fn outgoing(&self, messages: Vec<(NodeId, Message)>) -> Outgoing {
+        Outgoing {
+            clock: self.clock(),
+            messages,
+        }
+    }
+}
+
+impl Exclusion for SuzukiKasami {
+    type Message = Message;
+
+    fn ask(&mut self) -> Outgoing {
+        SuzukiKasami::ask(self)
+    }
+
+    fn free(&mut self) -> Outgoing {
+        SuzukiKasami::free(self)
+    }
+
+    fn handle(&mut self, message: Message) -> Outgoing {
+        SuzukiKasami::handle(self, message)
+    }
+
+    fn clock(&self) -> usize {
+        SuzukiKasami::clock(self)
+    }
+
+    fn in_critical_section(&self) -> bool {
+        self.state == State::CriticalSection
+    }
+}
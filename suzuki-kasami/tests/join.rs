@@ -0,0 +1,78 @@
+use std::{
+    sync::{mpsc, Arc, Condvar, Mutex},
+    thread::{self, sleep},
+    time::Duration,
+};
+
+use renraku_coordinator::{run, CoordinatorOptions, Graph};
+use renraku_node::{
+    configure,
+    runtime::{dispatch, receive_thread},
+    NodeArguments, NodeConfig,
+};
+use renraku_suzuki_kasami::algorithm::{State, SuzukiKasami};
+
+/// State of a running node, along with what its receiving thread shares.
+type Running = (Arc<Mutex<SuzukiKasami>>, Arc<Condvar>, Arc<NodeConfig>);
+
+fn arguments(controller: &str) -> NodeArguments {
+    NodeArguments {
+        controller: controller.to_string(),
+        ..NodeArguments::default()
+    }
+}
+
+#[test]
+fn members_refuse_a_late_node() {
+    let (listening, address) = mpsc::channel();
+    let options = CoordinatorOptions {
+        address: "127.0.0.1:0".parse().unwrap(),
+        listening: Some(listening),
+        resident: Some(Duration::from_secs(1)),
+        ..CoordinatorOptions::default()
+    };
+    let coordinator = thread::spawn(move || run(Graph::complete(2), options).unwrap());
+    let controller = address.recv().unwrap().to_string();
+
+    let nodes: Vec<_> = (0..2)
+        .map(|_| {
+            let args = arguments(&controller);
+            thread::spawn(move || {
+                let config = configure(args).unwrap();
+                config.start_writers();
+                let state = SuzukiKasami::new(config.id.clone(), config.members.clone());
+                let node = (
+                    Arc::new(Mutex::new(state)),
+                    Arc::new(Condvar::new()),
+                    Arc::new(config),
+                );
+                let t = node.clone();
+                thread::spawn(move || receive_thread(t.0, t.1, t.2));
+                node
+            })
+        })
+        .collect();
+    let mut nodes: Vec<Running> = nodes.into_iter().map(|n| n.join().unwrap()).collect();
+    nodes.sort_by_key(|n| n.2.id.clone());
+
+    // Nobody connects to the late node, which keeps waiting for its neighbours
+    let args = arguments(&controller);
+    let late = thread::spawn(move || configure(args));
+    sleep(Duration::from_millis(500));
+    assert!(!late.is_finished());
+
+    // The members keep on running without it, the token going from one to the other
+    let (mutex, permission, config) = &nodes[1];
+    assert_eq!(config.neighbours.len(), 1);
+    let outgoing = mutex.lock().unwrap().ask();
+    dispatch(outgoing, config).unwrap();
+    let (_state, timeout) = permission
+        .wait_timeout_while(mutex.lock().unwrap(), Duration::from_secs(10), |v| {
+            v.state != State::CriticalSection
+        })
+        .unwrap();
+    assert!(!timeout.timed_out());
+
+    let report = coordinator.join().unwrap();
+    assert_eq!(report.summary.nodes, 3);
+}
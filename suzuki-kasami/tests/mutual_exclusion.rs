@@ -0,0 +1,122 @@
+use std::collections::{HashMap, VecDeque};
+
+use renraku_shared::NodeId;
+use renraku_suzuki_kasami::algorithm::{Message, Outgoing, State, SuzukiKasami};
+
+const NODES: usize = 4;
+const CYCLES: usize = 20;
+
+/// Channels between each pair of nodes, counting every message sent.
+#[derive(Default)]
+struct Network {
+    channels: HashMap<(usize, usize), VecDeque<Message>>,
+    sent: usize,
+}
+
+impl Network {
+    fn send(&mut self, from: usize, outgoing: Outgoing) {
+        for (to, message) in outgoing.messages {
+            self.sent += 1;
+            self.channels
+                .entry((from, to.0))
+                .or_default()
+                .push_back(message);
+        }
+    }
+
+    fn pending(&self) -> Vec<(usize, usize)> {
+        let mut pending: Vec<(usize, usize)> = self
+            .channels
+            .iter()
+            .filter(|(_, c)| !c.is_empty())
+            .map(|(k, _)| *k)
+            .collect();
+        pending.sort();
+        pending
+    }
+
+    fn tokens_in_flight(&self) -> usize {
+        self.channels
+            .values()
+            .flatten()
+            .filter(|m| matches!(m, Message::Token { .. }))
+            .count()
+    }
+}
+
+#[test]
+fn single_token_grants_mutual_exclusion() {
+    let mut nodes: Vec<SuzukiKasami> = (1..=NODES)
+        .map(|i| SuzukiKasami::new(NodeId(i), (1..=NODES).map(NodeId).collect()))
+        .collect();
+    let mut network = Network::default();
+    let mut entries = [0; NODES];
+    let mut held_for = [0; NODES];
+    let mut seed: u64 = 42;
+
+    for _ in 0..100_000 {
+        if entries.iter().all(|e| *e == CYCLES) && network.pending().is_empty() {
+            break;
+        }
+        seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+
+        for (i, node) in nodes.iter_mut().enumerate() {
+            let outgoing = match node.state {
+                State::Idling if entries[i] < CYCLES => node.ask(),
+                State::CriticalSection => {
+                    held_for[i] += 1;
+                    if held_for[i] < 3 {
+                        continue;
+                    }
+                    held_for[i] = 0;
+                    entries[i] += 1;
+                    node.free()
+                }
+                _ => continue,
+            };
+            network.send(i + 1, outgoing);
+        }
+
+        // Delivers the head of a pseudo-randomly chosen non-empty channel
+        let pending = network.pending();
+        if !pending.is_empty() {
+            let (from, to) = pending[(seed >> 33) as usize % pending.len()];
+            let message = network
+                .channels
+                .get_mut(&(from, to))
+                .unwrap()
+                .pop_front()
+                .unwrap();
+            let outgoing = nodes[to - 1].handle(message);
+            network.send(to, outgoing);
+        }
+
+        let inside = nodes
+            .iter()
+            .filter(|n| n.state == State::CriticalSection)
+            .count();
+        assert!(inside <= 1, "{inside} nodes are in the critical section");
+        let tokens =
+            nodes.iter().filter(|n| n.token.is_some()).count() + network.tokens_in_flight();
+        assert_eq!(tokens, 1, "the token has been duplicated or lost");
+    }
+
+    assert_eq!(entries, [CYCLES; NODES]);
+    // A broadcast request and the token at most, per critical section
+    assert!(network.sent <= NODES * NODES * CYCLES);
+}
+
+#[test]
+fn requests_of_non_members_are_dropped() {
+    let mut holder = SuzukiKasami::new(NodeId(1), vec![NodeId(1), NodeId(2)]);
+
+    let outgoing = holder.handle(Message::Request {
+        seq: 1,
+        requester: NodeId(3),
+    });
+    assert!(outgoing.messages.is_empty());
+    assert!(holder.token.is_some());
+    assert_eq!(holder.requested, [0, 0]);
+}